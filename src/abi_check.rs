@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use ethers::{
+    contract::{EthCall, EthEvent},
+    providers::Middleware,
+    types::Address,
+};
+
+use crate::{
+    bindings::{
+        clearinghouse::{self, Clearinghouse},
+        cooler,
+        cooler_factory::{
+            ClearRequestFilter, CoolerFactory, DefaultLoanFilter, ExtendLoanFilter, RepayLoanFilter,
+        },
+    },
+    notify::{Notification, Notifier},
+};
+
+/// Readable name and the bytes to look for in the bytecode.
+type Needle = (String, Vec<u8>);
+
+/// Checks that the functions and events the bot relies on are still in the deployed
+/// bytecode. Dispatchers embed every function selector and emitters every event topic,
+/// so a missing one means the contract was upgraded and the bindings are stale.
+/// Returns one line per mismatch, an empty list when everything matches.
+pub async fn check<M: Middleware + 'static>(
+    clearinghouses: &[Clearinghouse<M>],
+    cooler_factory: &CoolerFactory<M>,
+) -> Result<Vec<String>> {
+    let client = cooler_factory.client();
+    let cooler_implementation = cooler_factory.cooler_implementation().call().await?;
+
+    let mut expected: Vec<(&str, Address, Vec<Needle>)> = clearinghouses
+        .iter()
+        .map(|clearinghouse| {
+            (
+                "Clearinghouse",
+                clearinghouse.address(),
+                vec![function::<clearinghouse::ClaimDefaultedCall>()],
+            )
+        })
+        .collect();
+    expected.extend([
+        (
+            "CoolerFactory",
+            cooler_factory.address(),
+            vec![
+                event::<ClearRequestFilter>(),
+                event::<RepayLoanFilter>(),
+                event::<ExtendLoanFilter>(),
+                event::<DefaultLoanFilter>(),
+            ],
+        ),
+        (
+            "Cooler implementation",
+            cooler_implementation,
+            vec![function::<cooler::GetLoanCall>()],
+        ),
+    ]);
+
+    let mut drift = vec![];
+    for (contract, address, items) in expected {
+        let code = client.get_code(address, None).await.map_err(|err| anyhow!("{}", err))?;
+        if code.is_empty() {
+            drift.push(format!("{} {:?} has no code", contract, address));
+            continue;
+        }
+        for (name, needle) in items {
+            if !code.windows(needle.len()).any(|window| window == needle.as_slice()) {
+                drift.push(format!("{} {:?} does not contain {}", contract, address, name));
+            }
+        }
+    }
+
+    Ok(drift)
+}
+
+/// Runs the check and reports any drift on the console and as an error notification.
+/// A failing check is reported too, but never stops the bot.
+pub async fn warn_on_drift<M: Middleware + 'static>(
+    clearinghouses: &[Clearinghouse<M>],
+    cooler_factory: &CoolerFactory<M>,
+    notifier: &Notifier,
+) {
+    let drift = match check(clearinghouses, cooler_factory).await {
+        Ok(drift) => drift,
+        Err(err) => {
+            println!("[ABI] Could not check the deployed contracts: {}", err);
+            return;
+        }
+    };
+    if drift.is_empty() {
+        println!("[ABI] Deployed contracts match the bindings");
+        return;
+    }
+
+    println!("[ABI] !!! Deployed contracts differ from the bindings, events or claims may fail to decode !!!");
+    for line in &drift {
+        println!("[ABI] {}", line);
+    }
+    notifier.notify(Notification::Error {
+        message: format!("ABI drift detected: {}", drift.join("; ")),
+    });
+}
+
+fn function<C: EthCall>() -> Needle {
+    (format!("function {}", C::abi_signature()), C::selector().to_vec())
+}
+
+fn event<E: EthEvent>() -> Needle {
+    (format!("event {}", E::abi_signature()), E::signature().as_bytes().to_vec())
+}
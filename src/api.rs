@@ -0,0 +1,391 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use anyhow::Result;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use ethers::{
+    types::{Address, U256},
+    utils::format_units,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{net::TcpListener, sync::RwLock};
+
+use crate::{
+    attestation::{Attestation, SharedAttestation},
+    auction::Auction,
+    config::{Config, RewardTier},
+    forecast::{project_claimable, ForecastBucket},
+    math::{Price, Usd},
+    metrics::RpcMetrics,
+    utils::get_sys_time_in_secs,
+};
+
+#[derive(Debug, Clone)]
+pub struct LoanSnapshot {
+    pub cooler: Address,
+    pub clearinghouse: Address,
+    pub loan_id: U256,
+    pub collateral: U256,
+    pub expiry: U256,
+    /// When claiming the loan alone clears `min_profit` at the last priced gas.
+    pub profitable_at: Option<u64>,
+}
+
+/// The numbers of the summary table, as of the last block.
+#[derive(Debug, Clone)]
+pub struct BlockSummary {
+    pub claimable: Usd,
+    /// Claimable after gas, of the loans past the reward period target.
+    pub claimable_net: Usd,
+    pub min_profit: Usd,
+    pub reward_period_target: u64,
+    pub expired_loans: usize,
+    /// Collateral of the expired loans, in wei.
+    pub total_collateral_gohm: U256,
+    /// Seconds until the next loan passes its reward period target.
+    pub next_claimable: Option<u64>,
+    /// Seconds until the next expired loan clears `min_profit` on its own.
+    pub next_profitable: Option<u64>,
+}
+
+/// What one tenant's strategy publishes, the tenants pause and price claims on their own.
+#[derive(Debug, Clone, Default)]
+pub struct TenantSnapshot {
+    pub pause_reason: Option<String>,
+    /// Gas price (wei) at which the current candidate batch stops being profitable.
+    pub break_even_gas_price: Option<U256>,
+    pub summary: Option<BlockSummary>,
+    /// ETH balance (wei) of the tenant's wallet as of its last check.
+    pub balance: Option<U256>,
+}
+
+/// View of the strategy state published once per block for the API to read.
+#[derive(Debug, Clone, Default)]
+pub struct StrategySnapshot {
+    /// Loans of the shared loan book, `profitable_at` as the lead tenant prices them.
+    pub loans: Vec<LoanSnapshot>,
+    pub gohm_price: Price,
+    /// Reward auction the rewards are computed with.
+    pub auction: Auction,
+    /// Tenant keeping the loan book, the first one.
+    pub lead_tenant: String,
+    pub tenants: BTreeMap<String, TenantSnapshot>,
+    /// Whether the engine is running on an open websocket, false while reconnecting.
+    pub connected: bool,
+    /// Number of the last block the strategy started processing and when (unix secs).
+    pub last_block: Option<(u64, u64)>,
+    /// Timestamp of the chain head the loans were published at, expiries and rewards are
+    /// measured against it rather than the local clock.
+    pub head_timestamp: u64,
+}
+
+pub type SharedSnapshot = Arc<RwLock<StrategySnapshot>>;
+
+impl StrategySnapshot {
+    pub fn lead(&self) -> Option<&TenantSnapshot> {
+        self.tenants.get(&self.lead_tenant)
+    }
+
+    /// Reward period target of the lead tenant in effect, which `reload` can change after startup.
+    pub fn reward_period_target(&self, config: &Config) -> u64 {
+        self.lead()
+            .and_then(|tenant| tenant.summary.as_ref())
+            .map_or(config.reward_period_target, |summary| summary.reward_period_target)
+    }
+
+    /// Loan counts, gOHM price and each tenant's pause state, break-even gas price and
+    /// balance, as served by `/status` and the control socket.
+    pub fn status(&self) -> Value {
+        let timestamp = self.head_timestamp;
+        let expired = self
+            .loans
+            .iter()
+            .filter(|loan| !loan.collateral.is_zero() && loan.expiry < timestamp.into())
+            .count();
+
+        json!({
+            "loans": self.loans.len(),
+            "expired": expired,
+            "gohm_price": self.gohm_price.to_f64(),
+            "tenants": self
+                .tenants
+                .iter()
+                .map(|(name, tenant)| {
+                    let status = json!({
+                        "paused": tenant.pause_reason,
+                        "break_even_gas_price": tenant.break_even_gas_price.map(|price| price.to_string()),
+                        "balance": tenant.balance.map(|balance| format_units(balance, "ether").unwrap_or_default()),
+                    });
+                    (name.clone(), status)
+                })
+                .collect::<serde_json::Map<_, _>>(),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct ApiState {
+    pub snapshot: SharedSnapshot,
+    /// The config as loaded at startup.
+    pub config: Arc<Config>,
+    pub metrics: RpcMetrics,
+    /// Latest signed status, if attestations are enabled.
+    pub attestation: SharedAttestation,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastQuery {
+    days: Option<u64>,
+    bucket_hours: Option<u64>,
+}
+
+pub async fn serve(addr: String, state: ApiState) -> Result<()> {
+    let app = Router::new()
+        .route("/status", get(status))
+        .route("/loans", get(loans))
+        .route("/claimable", get(claimable))
+        .route("/config", get(config))
+        .route("/forecast", get(forecast))
+        .route("/metrics", get(metrics))
+        .route("/attestation", get(attestation))
+        .route("/healthz", get(healthz))
+        .with_state(state);
+
+    let listener = TcpListener::bind(&addr).await?;
+    println!("API listening on {}", addr);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+async fn status(State(state): State<ApiState>) -> Json<Value> {
+    Json(state.snapshot.read().await.status())
+}
+
+/// Every tracked loan with its state and current reward.
+async fn loans(State(state): State<ApiState>) -> Json<Vec<Value>> {
+    let snapshot = state.snapshot.read().await;
+    let timestamp = U256::from(snapshot.head_timestamp);
+    let loans = snapshot
+        .loans
+        .iter()
+        .map(|loan| {
+            let status = if loan.collateral.is_zero() {
+                "closed"
+            } else if loan.expiry < timestamp {
+                "expired"
+            } else {
+                "active"
+            };
+            let expired = status == "expired";
+            json!({
+                "cooler": loan.cooler,
+                "loan_id": loan.loan_id.to_string(),
+                "clearinghouse": loan.clearinghouse,
+                "collateral": loan.collateral.to_string(),
+                "expiry": loan.expiry.as_u64(),
+                "status": status,
+                "reward_percentage": expired.then(|| snapshot.auction.reward_percentage(loan.expiry, timestamp).as_u64()),
+                "reward_usd": expired
+                    .then(|| snapshot.auction.rewards_in_dollar(loan.collateral, loan.expiry, timestamp, snapshot.gohm_price).to_f64()),
+                "profitable_at": loan.profitable_at,
+            })
+        })
+        .collect();
+    Json(loans)
+}
+
+/// Expired loans past the reward period target, before gas.
+async fn claimable(State(state): State<ApiState>) -> Json<Value> {
+    let snapshot = state.snapshot.read().await;
+    let timestamp = U256::from(snapshot.head_timestamp);
+    let target = snapshot.reward_period_target(&state.config);
+    let mut total = Usd::zero();
+    let loans: Vec<Value> = snapshot
+        .loans
+        .iter()
+        .filter(|loan| {
+            !loan.collateral.is_zero()
+                && loan.expiry < timestamp
+                && snapshot.auction.reward_percentage(loan.expiry, timestamp)
+                    > RewardTier::target_for(&state.config.reward_period_tiers, loan.collateral, target).into()
+        })
+        .map(|loan| {
+            let reward = snapshot.auction.rewards_in_dollar(loan.collateral, loan.expiry, timestamp, snapshot.gohm_price);
+            total += reward;
+            json!({
+                "cooler": loan.cooler,
+                "loan_id": loan.loan_id.to_string(),
+                "reward_percentage": snapshot.auction.reward_percentage(loan.expiry, timestamp).as_u64(),
+                "reward_usd": reward.to_f64(),
+            })
+        })
+        .collect();
+
+    Json(json!({
+        "reward_period_target": target,
+        "total_reward_usd": total.to_f64(),
+        "loans": loans,
+    }))
+}
+
+async fn config(State(state): State<ApiState>) -> Json<Value> {
+    Json(public_config(&state.config))
+}
+
+/// The settings that decide what gets claimed. Keys and RPC urls are left out.
+pub fn public_config(config: &Config) -> Value {
+    json!({
+        "cooler_factory_address": config.cooler_factory_address,
+        "clearinghouse_address": config.clearinghouse_address,
+        "clearinghouse_addresses": config.clearinghouse_addresses,
+        "kernel_address": config.kernel_address(),
+        "mono_cooler_address": config.mono_cooler_address,
+        "network": config.network,
+        "chain_id": config.chain_id,
+        "signer_type": config.signer_type.to_string(),
+        "rpc_mode": config.rpc_mode.to_string(),
+        "poll_interval_secs": config.poll_interval().as_secs(),
+        "ui": config.ui.to_string(),
+        "dry_run": config.dry_run,
+        "min_profit": config.min_profit.to_f64(),
+        "min_profit_gas_multiple": config.min_profit_gas_multiple,
+        "reward_period_target": config.reward_period_target,
+        "reward_period_tiers": config
+            .reward_period_tiers
+            .iter()
+            .map(|tier| json!({ "min_collateral": tier.min_collateral, "target": tier.target }))
+            .collect::<Vec<_>>(),
+        "min_collateral": config.min_collateral,
+        "price_source": config.price_source.to_string(),
+        "price_max_deviation_percent": config.price_max_deviation_percent(),
+        "price_cache_ttl_secs": config.price_cache_ttl().as_secs(),
+        "sync_source": config.sync_source.to_string(),
+        "loan_refresh_concurrency": config.loan_refresh_concurrency(),
+        "reconcile_interval_hours": config.reconcile_interval_hours(),
+        "price_api": {
+            "requests_per_minute": config.price_api.requests_per_minute,
+            "timeout_secs": config.price_api.timeout_secs,
+            "max_retries": config.price_api.max_retries,
+        },
+        "stale_price": {
+            "policy": config.stale_price.policy.to_string(),
+            "max_age_secs": config.stale_price.max_age_secs,
+            "haircut_percent": config.stale_price.haircut_percent,
+        },
+        "gas": {
+            "priority_fee": config.gas.priority_fee.to_string(),
+            "priority_fee_gwei": config.gas.priority_fee_gwei,
+            "priority_fee_percentile": config.gas.priority_fee_percentile,
+            "profit_bid_percent": config.gas.profit_bid_percent,
+            "stuck_after_blocks": config.gas.stuck_after_blocks,
+            "fee_bump_percent": config.gas.fee_bump_percent,
+            "max_fee_gwei": config.gas.max_fee_gwei,
+            "cancel_at_ceiling": config.gas.cancel_at_ceiling,
+            "break_even_safety_factor": config.gas.break_even_safety_factor,
+            "cancel_stale_nonces": config.gas.cancel_stale_nonces,
+            "max_gas_price_gwei": config.gas.max_gas_price_gwei,
+            "daily_budget_eth": config.gas.daily_budget_eth,
+            "max_batch_gas": config.gas.max_batch_gas,
+        },
+        "balance": {
+            "min_eth": config.balance.min_eth,
+            "check_every_blocks": config.balance.check_every_blocks,
+            "pause_when_low": config.balance.pause_when_low,
+        },
+        "expiry_warning": {
+            "before_mins": config.expiry_warning.before_mins,
+            "min_collateral": config.expiry_warning.min_collateral,
+        },
+        "consolidation": {
+            "window_secs": config.consolidation.window_secs,
+            "max_reward_percent": config.consolidation.max_reward_percent,
+        },
+        "swap": {
+            "enabled": config.swap.enabled,
+            "router": config.swap.router,
+            "output_token": config.swap.output_token,
+            "max_slippage_bps": config.swap.max_slippage_bps,
+        },
+        "submission": {
+            "mode": config.submission.mode.to_string(),
+            "bundle_builders": config.submission.bundle_builders,
+            "bundle_blocks": config.submission.bundle_blocks,
+        },
+        "competition": {
+            "monitor": config.competition.monitor,
+            "response": config.competition.response.to_string(),
+            "outbid_percent": config.competition.outbid_percent,
+            "outbid_increment_gwei": config.competition.outbid_increment_gwei,
+        },
+        "coolers": {
+            "allowlist": config.coolers.allowlist,
+            "blocklist": config.coolers.blocklist,
+        },
+        "policy_script": config.policy_script,
+        "tenants": config.tenants.iter().map(|tenant| &tenant.name).collect::<Vec<_>>(),
+    })
+}
+
+async fn forecast(
+    State(state): State<ApiState>,
+    Query(query): Query<ForecastQuery>,
+) -> Json<Vec<ForecastBucket>> {
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let bucket_hours = query.bucket_hours.unwrap_or(24).clamp(1, days * 24);
+    let bucket_in_s = bucket_hours * 60 * 60;
+    let bucket_count = (days * 24).div_ceil(bucket_hours);
+
+    let snapshot = state.snapshot.read().await;
+    Json(project_claimable(
+        &snapshot.loans,
+        &snapshot.auction,
+        snapshot.gohm_price,
+        |collateral| {
+            RewardTier::target_for(&state.config.reward_period_tiers, collateral, snapshot.reward_period_target(&state.config))
+        },
+        snapshot.head_timestamp,
+        bucket_in_s,
+        bucket_count,
+    ))
+}
+
+async fn metrics(State(state): State<ApiState>) -> String {
+    state.metrics.prometheus()
+}
+
+async fn attestation(State(state): State<ApiState>) -> Result<Json<Attestation>, StatusCode> {
+    state.attestation.read().await.clone().map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// 200 while the websocket is connected and the strategy processed a block recently, 503
+/// otherwise, e.g. during the initial sync or when the block stream silently stalled.
+async fn healthz(State(state): State<ApiState>) -> (StatusCode, Json<Value>) {
+    let snapshot = state.snapshot.read().await;
+    let max_age = state.config.health_max_block_age().as_secs();
+    let age = snapshot
+        .last_block
+        .map(|(_, seen_at)| get_sys_time_in_secs().saturating_sub(seen_at));
+    let healthy = snapshot.connected && age.is_some_and(|age| age <= max_age);
+
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(json!({
+            "healthy": healthy,
+            "connected": snapshot.connected,
+            "last_block": snapshot.last_block.map(|(number, _)| number),
+            "last_block_age_secs": age,
+            "max_block_age_secs": max_age,
+        })),
+    )
+}
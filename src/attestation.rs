@@ -0,0 +1,104 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use ethers::{signers::Signer, types::Address};
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::RwLock;
+
+use crate::{
+    api::{public_config, SharedSnapshot},
+    config::Config,
+    notify::{Notification, Notifier},
+    report::EfficiencyReport,
+    signer::BotSigner,
+    store::LoanStore,
+    utils::get_sys_time_in_secs,
+};
+
+/// The status, the claim settings and the realized rewards, signed by the bot's wallet.
+/// `message` is the exact JSON that was signed (EIP-191), so it can be checked with
+/// `cast wallet verify --address <signer> <message> <signature>`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Attestation {
+    pub signer: Address,
+    pub message: String,
+    pub signature: String,
+}
+
+pub type SharedAttestation = Arc<RwLock<Option<Attestation>>>;
+
+/// Signs a fresh attestation every `interval_secs`, keeps the latest in `latest` for the API
+/// and writes it to `path` if set.
+pub async fn publish(
+    wallet: BotSigner,
+    snapshot: SharedSnapshot,
+    config: Arc<Config>,
+    store: Option<Arc<LoanStore>>,
+    latest: SharedAttestation,
+    notifier: Notifier,
+) {
+    let interval = Duration::from_secs(config.attestation.interval_secs);
+    let path = config.attestation.path.clone();
+    loop {
+        match sign(&wallet, &snapshot, &config, store.as_deref()).await {
+            Ok(attestation) => {
+                if let Some(path) = &path {
+                    if let Err(err) = write(path, &attestation) {
+                        println!("[ATTESTATION] Could not write {}: {}", path.display(), err);
+                    }
+                }
+                *latest.write().await = Some(attestation);
+            }
+            Err(err) => {
+                println!("[ATTESTATION] Could not sign the status: {}", err);
+                notifier.notify(Notification::Error {
+                    message: format!("Could not sign the status: {}", err),
+                });
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn sign(
+    wallet: &BotSigner,
+    snapshot: &SharedSnapshot,
+    config: &Config,
+    store: Option<&LoanStore>,
+) -> Result<Attestation> {
+    let snapshot = snapshot.read().await;
+    // Gross rewards of our own claims, the database does not keep the gas paid.
+    let realized = store
+        .map(|store| store.load_claims())
+        .transpose()?
+        .map(|claims| EfficiencyReport::from_claims(&claims, &snapshot.auction).realized);
+    let message = json!({
+        "timestamp": get_sys_time_in_secs(),
+        "signer": wallet.address(),
+        "status": snapshot.status(),
+        "reward_period_target": snapshot.reward_period_target(config),
+        "config": public_config(config),
+        "realized": realized.map(|realized| json!({
+            "loans": realized.loans,
+            "rewards_usd": realized.rewards_usd.to_string(),
+        })),
+    })
+    .to_string();
+    drop(snapshot);
+
+    let signature = wallet.sign_message(&message).await?;
+    Ok(Attestation {
+        signer: wallet.address(),
+        message,
+        signature: format!("0x{}", signature),
+    })
+}
+
+/// Writes next to the file and renames, so readers never see a partial attestation.
+fn write(path: &Path, attestation: &Attestation) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(attestation)?)?;
+    std::fs::rename(tmp, path)?;
+    Ok(())
+}
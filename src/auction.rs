@@ -0,0 +1,101 @@
+use anyhow::{anyhow, Result};
+use ethers::{providers::Middleware, types::U256};
+
+use crate::{
+    bindings::clearinghouse::Clearinghouse,
+    math::{Price, Usd},
+};
+
+/// Parameters of the keeper reward auction `claimDefaulted` runs after a loan expires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Auction {
+    /// Most gOHM a single loan pays out, the clearinghouse's `MAX_REWARD`.
+    pub max_reward: U256,
+    /// Seconds after expiry until the reward reaches its maximum.
+    pub duration: u64,
+    /// Cap of the reward as a share of the collateral, with 18 decimals.
+    pub collateral_cap: U256,
+}
+
+impl Default for Auction {
+    /// The auction of the deployed clearinghouses: 0.1 gOHM over seven days, at most 5% of
+    /// the collateral.
+    fn default() -> Self {
+        Self {
+            max_reward: U256::exp10(17),
+            duration: 7 * 24 * 60 * 60,
+            collateral_cap: U256::from(5) * U256::exp10(16),
+        }
+    }
+}
+
+impl Auction {
+    /// Reads `MAX_REWARD` from `clearinghouse`. The auction duration and the collateral cap
+    /// are not exposed by the contract, so they keep their defaults.
+    pub async fn fetch<M: Middleware + 'static>(clearinghouse: &Clearinghouse<M>) -> Result<Self> {
+        let max_reward = clearinghouse
+            .max_reward()
+            .call()
+            .await
+            .map_err(|err| anyhow!("could not read MAX_REWARD of the clearinghouse: {}", err))?;
+        Ok(Self {
+            max_reward,
+            ..Default::default()
+        })
+    }
+
+    /// Share of the auction (in percent) that has passed at `timestamp` for a loan expiring at `expiry`.
+    pub fn reward_percentage(&self, expiry: U256, timestamp: U256) -> U256 {
+        let elapsed = timestamp - expiry;
+        let duration = U256::from(self.duration);
+        if elapsed < duration {
+            elapsed * 100 / duration
+        } else {
+            100.into()
+        }
+    }
+
+    /// Seconds after expiry at which `reward_percentage` passes `reward_period_target`, None
+    /// if it never does.
+    pub fn claim_offset(&self, reward_period_target: u64) -> Option<u64> {
+        (reward_period_target < 100).then(|| (self.duration * (reward_period_target + 1)).div_ceil(100))
+    }
+
+    /// Seconds after expiry at which `reward_percentage` reaches `percent`.
+    pub fn offset_of(&self, percent: u64) -> u64 {
+        self.duration * percent / 100
+    }
+
+    /// Keeper reward in gOHM (wei) for a loan with the given collateral and expiry at `timestamp`.
+    pub fn reward(&self, collateral: U256, expiry: U256, timestamp: U256) -> U256 {
+        let elapsed = timestamp - expiry;
+        let duration = U256::from(self.duration);
+        let max_reward = self.max_reward_for(collateral);
+        if elapsed < duration {
+            max_reward * elapsed / duration
+        } else {
+            max_reward
+        }
+    }
+
+    /// Seconds after expiry at which the reward of a loan with `collateral` reaches `reward`
+    /// (gOHM wei), None if it never does.
+    pub fn time_to_reward(&self, collateral: U256, reward: U256) -> Option<u64> {
+        let max_reward = self.max_reward_for(collateral);
+        if reward > max_reward || max_reward.is_zero() {
+            return None;
+        }
+        let duration = U256::from(self.duration);
+        Some(((duration * reward + max_reward - 1) / max_reward).as_u64())
+    }
+
+    /// Reward of a loan with `collateral` at the end of the auction.
+    fn max_reward_for(&self, collateral: U256) -> U256 {
+        self.max_reward.min(collateral * self.collateral_cap / U256::exp10(18))
+    }
+
+    /// Dollar value of `reward` at `ohm_price`.
+    pub fn rewards_in_dollar(&self, collateral: U256, expiry: U256, timestamp: U256, ohm_price: Price) -> Usd {
+        ohm_price.value(self.reward(collateral, expiry, timestamp))
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{anyhow, bail, Result};
+use comfy_table::{presets::UTF8_FULL, Table};
+use ethers::{
+    contract::Multicall,
+    providers::Middleware,
+    types::{BlockNumber, U256},
+    utils::parse_units,
+};
+
+use crate::{
+    bindings::cooler::{Cooler, Loan},
+    math::{Price, Usd},
+    oracle::{DefiLlama, ETH_ID, GOHM_ID},
+    planner::TX_BASE_GAS,
+    strategy::{LiquidationStrategy, MULTICALL_CHUNK_SIZE},
+    types::LoanKey,
+};
+
+/// Historical prices are looked up once per this many seconds.
+const PRICE_RESOLUTION_SECS: u64 = 3600;
+
+/// A loan as it was on chain from `block` on.
+#[derive(Debug, Clone, Copy)]
+struct LoanState {
+    block: u64,
+    collateral: U256,
+    expiry: U256,
+}
+
+#[derive(Debug, Default)]
+struct LoanHistory {
+    states: Vec<LoanState>,
+    /// Block at which the loan was claimed on chain, by whoever that was.
+    defaulted_at: Option<u64>,
+}
+
+impl LoanHistory {
+    fn at(&self, block: u64) -> Option<LoanState> {
+        self.states.iter().rev().find(|state| state.block <= block).copied()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    claims: usize,
+    loans: usize,
+    rewards: Usd,
+    gas: Usd,
+    missed_loans: usize,
+    missed_rewards: Usd,
+}
+
+/// Replays `from_block..=to_block` every `step` blocks and reports what the configured
+/// MIN_PROFIT and REWARD_PERIOD_TARGET would have earned. Loan states come from the
+/// ClearRequest, RepayLoan and ExtendLoan logs, a DefaultLoan log means another keeper got
+/// there first. The live strategy reads the current chain, so the backtest applies its
+/// reward math and thresholds to the historical states instead of running it block by block.
+pub async fn run<M: Middleware + 'static>(
+    strategy: LiquidationStrategy<M>,
+    from_block: u64,
+    to_block: u64,
+    step: u64,
+    gas_per_loan: u64,
+) -> Result<()> {
+    if from_block >= to_block {
+        bail!("--from-block must be below --to-block");
+    }
+    if step == 0 {
+        bail!("--step must be at least 1");
+    }
+    let client = strategy.client.clone();
+    let factory = &strategy.cooler_factory;
+    let tip: U256 = parse_units(strategy.config.gas.priority_fee_gwei, "gwei")?.into();
+
+    println!("[BACKTEST] Fetching loan events up to block {}...", to_block);
+    let created = factory.clear_request_filter().from_block(0).to_block(to_block).query_with_meta().await?;
+    let repaid = factory.repay_loan_filter().from_block(from_block).to_block(to_block).query_with_meta().await?;
+    let extended = factory.extend_loan_filter().from_block(from_block).to_block(to_block).query_with_meta().await?;
+    let defaulted = factory.default_loan_filter().from_block(from_block).to_block(to_block).query_with_meta().await?;
+
+    // Loans are read at the start of the range, or when they were opened within it, and
+    // again after every repayment or extension.
+    let mut reads: BTreeMap<u64, Vec<LoanKey>> = BTreeMap::new();
+    for (log, meta) in &created {
+        reads.entry(meta.block_number.as_u64().max(from_block)).or_default().push((log.cooler, log.loan_id));
+    }
+    for (key, meta) in repaid
+        .iter()
+        .map(|(log, meta)| ((log.cooler, log.loan_id), meta))
+        .chain(extended.iter().map(|(log, meta)| ((log.cooler, log.loan_id), meta)))
+    {
+        reads.entry(meta.block_number.as_u64()).or_default().push(key);
+    }
+
+    println!("[BACKTEST] Reading {} loans at {} blocks...", created.len(), reads.len());
+    let mut history: HashMap<LoanKey, LoanHistory> = HashMap::new();
+    let multicall = Multicall::new(client.clone(), None).await?;
+    for (block, keys) in &reads {
+        for chunk in keys.chunks(MULTICALL_CHUNK_SIZE) {
+            let mut multicall = multicall.clone().block(*block);
+            for (cooler, loan_id) in chunk {
+                multicall.add_call(Cooler::new(*cooler, client.clone()).get_loan(*loan_id), false);
+            }
+            let loans: Vec<Loan> = multicall.call_array().await?;
+            for (key, loan) in chunk.iter().zip(loans) {
+                history.entry(*key).or_default().states.push(LoanState {
+                    block: *block,
+                    collateral: loan.collateral,
+                    expiry: loan.expiry,
+                });
+            }
+        }
+    }
+    for (log, meta) in &defaulted {
+        history.entry((log.cooler, log.loan_id)).or_default().defaulted_at = Some(meta.block_number.as_u64());
+    }
+
+    let mut prices: HashMap<u64, (Price, Price)> = HashMap::new();
+    let defillama = DefiLlama::new(&strategy.config.price_api);
+    let mut ours: HashSet<LoanKey> = HashSet::new();
+    let mut totals = Totals::default();
+    let evaluations = (to_block - from_block) / step + 1;
+    println!("[BACKTEST] Evaluating {} blocks...", evaluations);
+
+    for block in (from_block..=to_block).step_by(step as usize) {
+        let (timestamp, base_fee) = block_info(&*client, block).await?;
+        let now = U256::from(timestamp);
+
+        let claimable: Vec<(LoanKey, LoanState)> = history
+            .iter()
+            .filter(|(key, loan)| !ours.contains(*key) && loan.defaulted_at.is_none_or(|at| at > block))
+            .filter_map(|(key, loan)| Some((*key, loan.at(block)?)))
+            .filter(|(_, state)| {
+                !state.collateral.is_zero()
+                    && state.expiry < now
+                    && strategy.auction.reward_percentage(state.expiry, now) > strategy.config.reward_period_target_for(state.collateral).into()
+            })
+            .collect();
+        if claimable.is_empty() {
+            continue;
+        }
+
+        let (gohm_price, eth_price) = prices_at(&defillama, &mut prices, timestamp).await?;
+        let rewards: Usd = claimable
+            .iter()
+            .map(|(_, state)| strategy.auction.rewards_in_dollar(state.collateral, state.expiry, now, gohm_price))
+            .sum();
+        let gas_usage = U256::from(TX_BASE_GAS) + U256::from(gas_per_loan) * claimable.len();
+        let gas = eth_price.value(gas_usage * (base_fee + tip));
+        let net = rewards.saturating_sub(gas);
+        if net <= strategy.config.min_profit_for(gas) {
+            continue;
+        }
+
+        println!(
+            "[BACKTEST] Block {}: claim {} loans, reward {} dollar, gas {} dollar, net {} dollar",
+            block,
+            claimable.len(),
+            rewards,
+            gas,
+            net
+        );
+        ours.extend(claimable.iter().map(|(key, _)| *key));
+        totals.claims += 1;
+        totals.loans += claimable.len();
+        totals.rewards += rewards;
+        totals.gas += gas;
+    }
+
+    for (key, loan) in &history {
+        let Some(block) = loan.defaulted_at else {
+            continue;
+        };
+        if ours.contains(key) {
+            continue;
+        }
+        let Some(state) = loan.at(block) else {
+            continue;
+        };
+        let (timestamp, _) = block_info(&*client, block).await?;
+        let (gohm_price, _) = prices_at(&defillama, &mut prices, timestamp).await?;
+        totals.missed_loans += 1;
+        totals.missed_rewards += strategy.auction.rewards_in_dollar(state.collateral, state.expiry, timestamp.into(), gohm_price);
+    }
+
+    print_totals(&strategy, from_block, to_block, &totals);
+    Ok(())
+}
+
+async fn block_info<M: Middleware>(client: &M, block: u64) -> Result<(u64, U256)> {
+    let header = client
+        .get_block(BlockNumber::Number(block.into()))
+        .await
+        .map_err(|err| anyhow!("could not get block {}: {}", block, err))?
+        .ok_or_else(|| anyhow!("block {} not found", block))?;
+    Ok((header.timestamp.as_u64(), header.base_fee_per_gas.unwrap_or_default()))
+}
+
+/// gOHM and ETH prices in dollar around `timestamp`.
+async fn prices_at(defillama: &DefiLlama, cache: &mut HashMap<u64, (Price, Price)>, timestamp: u64) -> Result<(Price, Price)> {
+    let slot = timestamp / PRICE_RESOLUTION_SECS * PRICE_RESOLUTION_SECS;
+    if let Some(prices) = cache.get(&slot) {
+        return Ok(*prices);
+    }
+    let gohm_price = defillama.price_at(GOHM_ID, slot).await?;
+    let eth_price = defillama.price_at(ETH_ID, slot).await?;
+    cache.insert(slot, (gohm_price, eth_price));
+    Ok((gohm_price, eth_price))
+}
+
+fn print_totals<M>(strategy: &LiquidationStrategy<M>, from_block: u64, to_block: u64, totals: &Totals) {
+    println!();
+    println!(
+        "Backtest of blocks {} to {} with MIN_PROFIT {} dollar and REWARD_PERIOD_TARGET {}%",
+        from_block, to_block, strategy.config.min_profit, strategy.config.reward_period_target
+    );
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL).set_header(vec!["", "Value"]);
+    table.add_row(vec!["Claim transactions".to_string(), totals.claims.to_string()]);
+    table.add_row(vec!["Loans claimed".to_string(), totals.loans.to_string()]);
+    table.add_row(vec!["Rewards".to_string(), format!("{} dollar", totals.rewards)]);
+    table.add_row(vec!["Gas".to_string(), format!("{} dollar", totals.gas)]);
+    table.add_row(vec![
+        "Net profit".to_string(),
+        format!("{} dollar", totals.rewards.saturating_sub(totals.gas)),
+    ]);
+    table.add_row(vec!["Loans claimed by others first".to_string(), totals.missed_loans.to_string()]);
+    table.add_row(vec![
+        "Rewards claimed by others".to_string(),
+        format!("{} dollar", totals.missed_rewards),
+    ]);
+    println!("{table}");
+}
@@ -0,0 +1,12 @@
+//! Olympus CHREG module, the registry of every Clearinghouse deployment.
+use ethers::contract::abigen;
+
+abigen!(
+    ClearinghouseRegistry,
+    r#"[
+        function activeCount() external view returns (uint256)
+        function active(uint256 index) external view returns (address)
+        function registryCount() external view returns (uint256)
+        function registry(uint256 index) external view returns (address)
+    ]"#
+);
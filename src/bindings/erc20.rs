@@ -0,0 +1,11 @@
+//! ERC-20, only what is needed to follow and sell the gOHM reward.
+use ethers::contract::abigen;
+
+abigen!(
+    Erc20,
+    r#"[
+        event Transfer(address indexed from, address indexed to, uint256 value)
+        function allowance(address owner, address spender) external view returns (uint256)
+        function approve(address spender, uint256 amount) external returns (bool)
+    ]"#
+);
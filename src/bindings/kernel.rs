@@ -0,0 +1,9 @@
+//! Olympus V3 Kernel, only the module lookup the bot needs.
+use ethers::contract::abigen;
+
+abigen!(
+    Kernel,
+    r#"[
+        function getModuleForKeycode(bytes5 keycode) external view returns (address)
+    ]"#
+);
@@ -1,3 +1,7 @@
 pub mod clearinghouse;
+pub mod clearinghouse_registry;
 pub mod cooler_factory;
 pub mod cooler;
+pub mod erc20;
+pub mod kernel;
+pub mod mono_cooler;
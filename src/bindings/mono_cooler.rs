@@ -0,0 +1,13 @@
+//! Olympus Cooler V2 (MonoCooler), only the position checks and liquidation the bot needs.
+use ethers::contract::abigen;
+
+abigen!(
+    MonoCooler,
+    r#"[
+        struct LiquidationStatus { uint128 collateral; uint128 currentDebt; uint128 currentLtv; uint256 totalDelegated; bool exceededLiquidationLtv; bool exceededMaxOriginationLtv; uint128 currentIncentive; }
+        function computeLiquidity(address[] accounts) external view returns (LiquidationStatus[])
+        function batchLiquidate(address[] accounts) external returns (uint128 totalCollateralClaimed, uint128 totalDebtWiped, uint128 totalLiquidationIncentive)
+        event Borrow(address indexed caller, address indexed onBehalfOf, address indexed recipient, uint128 amount)
+        event Liquidated(address indexed caller, address indexed account, uint128 collateralSeized, uint128 debtWiped, uint128 incentives)
+    ]"#
+);
@@ -0,0 +1,183 @@
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, H256, U256, U64},
+    utils::{hex, keccak256},
+};
+use reqwest::Client;
+use serde_json::{json, Value};
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+use crate::pending::{Resolution, MAX_POLL_FAILURES};
+
+/// Polled faster than the block time so every block gets targeted.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sends signed claims as single transaction bundles to every builder at once. Requests are
+/// authenticated with `X-Flashbots-Signature`, signed by the claiming wallet, which the
+/// Flashbots relay requires and the other builders ignore.
+#[derive(Debug, Clone)]
+pub struct BundleSender {
+    builders: Vec<String>,
+    /// Blocks a bundle is retargeted for before the claim counts as dropped.
+    blocks: u64,
+    http: Client,
+}
+
+impl BundleSender {
+    pub fn new(builders: Vec<String>, blocks: u64) -> Self {
+        Self {
+            builders,
+            blocks,
+            http: Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+        }
+    }
+
+    /// Targets `block` with the bundle on every builder concurrently. Returns the builders
+    /// that accepted it.
+    async fn send<M: Middleware + 'static>(&self, client: &M, from: Address, raw: &Bytes, block: U64) -> Result<Vec<String>> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_sendBundle",
+            "params": [{ "txs": [raw], "blockNumber": block }],
+        })
+        .to_string();
+        let digest = format!("0x{}", hex::encode(keccak256(body.as_bytes())));
+        let signature = client
+            .sign(digest.into_bytes(), &from)
+            .await
+            .map_err(|err| anyhow!("could not sign bundle: {}", err))?;
+        let auth = format!("{:?}:0x{}", from, signature);
+
+        let mut requests = JoinSet::new();
+        for builder in &self.builders {
+            let request = self
+                .http
+                .post(builder)
+                .header("Content-Type", "application/json")
+                .header("X-Flashbots-Signature", &auth)
+                .body(body.clone());
+            let builder = builder.clone();
+            requests.spawn(async move {
+                let response: Result<Value> = async { Ok(request.send().await?.json().await?) }.await;
+                (builder, response)
+            });
+        }
+
+        let mut accepted = vec![];
+        while let Some(joined) = requests.join_next().await {
+            let Ok((builder, response)) = joined else { continue };
+            match response {
+                Ok(response) if response.get("error").is_none() => accepted.push(builder),
+                Ok(response) => warn!(%builder, error = %response["error"], "builder rejected the bundle"),
+                Err(err) => warn!(%builder, %err, "could not reach builder"),
+            }
+        }
+
+        Ok(accepted)
+    }
+
+    /// Sends the signed claim `raw` as a bundle for every new block until it is included, its
+    /// nonce is used by another transaction, or `blocks` blocks passed. A block no builder
+    /// accepted is retargeted on the next poll, and a failed RPC request retried, only
+    /// `MAX_POLL_FAILURES` in a row give up on the claim.
+    pub async fn submit_and_watch<M: Middleware + 'static>(
+        &self,
+        client: Arc<M>,
+        tx: &TypedTransaction,
+        raw: Bytes,
+    ) -> Result<Resolution> {
+        let mut watch = BundleWatch {
+            from: *tx.from().ok_or_else(|| anyhow!("claim has no sender"))?,
+            nonce: *tx.nonce().ok_or_else(|| anyhow!("claim has no nonce"))?,
+            tx_hash: H256::from(keccak256(&raw)),
+            raw,
+            first: block_number(&*client).await.ok(),
+            targeted: U64::zero(),
+            accepted_by: BTreeSet::new(),
+        };
+        let mut failures = 0;
+        loop {
+            match self.poll(&*client, &mut watch).await {
+                Ok(Some(resolution)) => return Ok(resolution),
+                Ok(None) => failures = 0,
+                Err(err) => {
+                    failures += 1;
+                    if failures == MAX_POLL_FAILURES {
+                        return Err(err.context(format!("bundle unresolved after {} failed checks", failures)));
+                    }
+                    warn!(%err, failures, "could not check the bundle, retrying");
+                }
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Checks the bundle once and sends it for the next block when a new one arrived. None
+    /// while it is pending.
+    async fn poll<M: Middleware + 'static>(&self, client: &M, watch: &mut BundleWatch) -> Result<Option<Resolution>> {
+        if let Some(receipt) = client.get_transaction_receipt(watch.tx_hash).await.map_err(|err| anyhow!("{}", err))? {
+            let block = match receipt.block_hash {
+                Some(hash) => client.get_block(hash).await.map_err(|err| anyhow!("{}", err))?,
+                None => None,
+            };
+            let builder = block.map(|block| builder_tag(&block.extra_data)).unwrap_or_default();
+            info!(tx_hash = ?watch.tx_hash, %builder, accepted_by = ?watch.accepted_by, "bundled claim included");
+            return Ok(Some(Resolution::Mined(receipt)));
+        }
+
+        let next_nonce = client.get_transaction_count(watch.from, None).await.map_err(|err| anyhow!("{}", err))?;
+        if next_nonce > watch.nonce {
+            return Ok(Some(Resolution::NonceTaken));
+        }
+
+        let block = block_number(client).await?;
+        let first = *watch.first.get_or_insert(block);
+        if block >= first + self.blocks {
+            return Ok(Some(Resolution::Dropped));
+        }
+        if block > watch.targeted {
+            let accepted = self.send(client, watch.from, &watch.raw, block + 1).await?;
+            if accepted.is_empty() {
+                warn!(target_block = %(block + 1), "no builder accepted the bundle, retrying");
+                return Ok(None);
+            }
+            debug!(target_block = %(block + 1), ?accepted, "bundle sent");
+            watch.accepted_by.extend(accepted);
+            watch.targeted = block;
+        }
+        Ok(None)
+    }
+}
+
+/// A bundled claim being followed.
+struct BundleWatch {
+    from: Address,
+    nonce: U256,
+    tx_hash: H256,
+    raw: Bytes,
+    /// Block the claim was first sent at, unknown until it could be read.
+    first: Option<U64>,
+    /// Block whose successor the bundle was last sent for.
+    targeted: U64,
+    /// Builders that accepted the bundle for any block.
+    accepted_by: BTreeSet<String>,
+}
+
+/// Builder name from a block's extra data, e.g. `beaverbuild.org` or `Titan (titanbuilder.xyz)`.
+pub fn builder_tag(extra_data: &Bytes) -> String {
+    let tag = String::from_utf8_lossy(extra_data).trim_matches(char::from(0)).trim().to_string();
+    if tag.is_empty() || tag.chars().any(char::is_control) {
+        return format!("unknown builder ({})", extra_data);
+    }
+    tag
+}
+
+async fn block_number<M: Middleware>(client: &M) -> Result<U64> {
+    client.get_block_number().await.map_err(|err| anyhow!("could not get block number: {}", err))
+}
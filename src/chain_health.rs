@@ -0,0 +1,88 @@
+use crate::config::ChainHealthConfig;
+
+/// Watches the block stream for signs of an unstable chain: repeated or decreasing block
+/// numbers, timestamps going backwards and finality falling too far behind the head.
+#[derive(Debug)]
+pub struct ChainMonitor {
+    config: ChainHealthConfig,
+    last_number: Option<u64>,
+    last_timestamp: Option<u64>,
+    healthy_streak: u64,
+    pause_reason: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HealthChange {
+    Paused(String),
+    Resumed,
+    Unchanged,
+}
+
+impl ChainMonitor {
+    pub fn new(config: ChainHealthConfig) -> Self {
+        Self {
+            config,
+            last_number: None,
+            last_timestamp: None,
+            healthy_streak: 0,
+            pause_reason: None,
+        }
+    }
+
+    pub fn pause_reason(&self) -> Option<&str> {
+        self.pause_reason.as_deref()
+    }
+
+    /// Records a new head and returns whether submissions should be paused or resumed.
+    pub fn observe(&mut self, number: u64, timestamp: u64, finalized: Option<u64>) -> HealthChange {
+        let anomaly = self.check(number, timestamp, finalized);
+        self.last_number = Some(number);
+        self.last_timestamp = Some(timestamp);
+
+        match anomaly {
+            Some(reason) => {
+                self.healthy_streak = 0;
+                if self.pause_reason.is_none() {
+                    self.pause_reason = Some(reason.clone());
+                    return HealthChange::Paused(reason);
+                }
+                HealthChange::Unchanged
+            }
+            None => {
+                self.healthy_streak += 1;
+                if self.pause_reason.is_some() && self.healthy_streak >= self.config.healthy_blocks_to_resume {
+                    self.pause_reason = None;
+                    return HealthChange::Resumed;
+                }
+                HealthChange::Unchanged
+            }
+        }
+    }
+
+    fn check(&self, number: u64, timestamp: u64, finalized: Option<u64>) -> Option<String> {
+        if let Some(last_number) = self.last_number {
+            if number == last_number {
+                return Some(format!("block {} received twice", number));
+            }
+            if number < last_number {
+                return Some(format!("block number went back from {} to {}", last_number, number));
+            }
+        }
+        if let Some(last_timestamp) = self.last_timestamp {
+            if timestamp < last_timestamp {
+                return Some(format!(
+                    "block timestamp went back from {} to {}",
+                    last_timestamp, timestamp
+                ));
+            }
+        }
+        if let Some(finalized) = finalized {
+            let lag = number.saturating_sub(finalized);
+            if lag > self.config.max_finality_lag {
+                return Some(format!("finality stalled, {} blocks behind head", lag));
+            }
+        }
+
+        None
+    }
+}
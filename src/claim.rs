@@ -0,0 +1,205 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use tokio::io::{stdin, AsyncBufReadExt, BufReader};
+
+use crate::{
+    bindings::{
+        clearinghouse::Clearinghouse,
+        cooler::{Cooler, Loan},
+    },
+    gas,
+    gas_budget::to_ether,
+    math::Usd,
+    notify::{Notification, Notifier, REVERTED},
+    strategy::LiquidationStrategy,
+    types::LoanKey,
+};
+
+/// Claims the given loans right away, ignoring the profit and reward period thresholds.
+/// Loans that are not expired or already cleared are refused, and the batch is simulated
+/// with `eth_call` before anything is sent. The expected reward and gas are printed and
+/// have to be confirmed, unless `force` is set.
+pub async fn run<M, S>(
+    strategy: &mut LiquidationStrategy<M>,
+    signer: Arc<S>,
+    notifier: Notifier,
+    loans: &[LoanKey],
+    force: bool,
+) -> Result<()>
+where
+    M: Middleware + 'static,
+    S: Middleware + 'static,
+{
+    if loans.is_empty() {
+        bail!("no loans given, use --cooler <address> --loan-id <id> or <cooler>:<loan_id>");
+    }
+
+    strategy.sync_head().await;
+    let timestamp = U256::from(strategy.head_timestamp);
+    let mut targets: Vec<(LoanKey, Loan)> = vec![];
+    for &(cooler, loan_id) in loans {
+        let loan = Cooler::new(cooler, strategy.client.clone()).get_loan(loan_id).await?;
+        if loan.collateral.is_zero() {
+            println!("[CLAIM] Skipping {:?}:{}: no collateral left (repaid or already claimed)", cooler, loan_id);
+            continue;
+        }
+        if loan.expiry >= timestamp {
+            println!("[CLAIM] Skipping {:?}:{}: not expired yet", cooler, loan_id);
+            continue;
+        }
+        if !strategy.clearinghouses.iter().any(|clearinghouse| clearinghouse.address() == loan.lender) {
+            println!("[CLAIM] Skipping {:?}:{}: lent by {:?}, not a configured clearinghouse", cooler, loan_id, loan.lender);
+            continue;
+        }
+        if !strategy.permits_cooler(cooler).await {
+            println!("[CLAIM] Skipping {:?}:{}: the cooler or its owner is filtered out", cooler, loan_id);
+            continue;
+        }
+        targets.push(((cooler, loan_id), loan));
+    }
+
+    if targets.is_empty() {
+        bail!("no claimable loans left");
+    }
+
+    // One transaction per clearinghouse, simulated and estimated before asking.
+    let mut batches = vec![];
+    let mut gas_estimate = U256::zero();
+    for clearinghouse in &strategy.clearinghouses {
+        let (coolers, loan_ids): (Vec<Address>, Vec<U256>) = targets
+            .iter()
+            .filter(|(_, loan)| loan.lender == clearinghouse.address())
+            .map(|(key, _)| *key)
+            .unzip();
+        if coolers.is_empty() {
+            continue;
+        }
+        let call = clearinghouse.claim_defaulted(coolers.clone(), loan_ids.clone());
+        call.call()
+            .await
+            .map_err(|err| anyhow!("simulation failed, nothing was sent: {}", err))?;
+        gas_estimate += call.estimate_gas().await.map_err(|err| anyhow!("could not estimate gas: {}", err))?;
+        batches.push((clearinghouse, coolers, loan_ids));
+    }
+
+    print_expected(strategy, &targets, timestamp, gas_estimate).await?;
+    if !force && !confirm().await? {
+        println!("[CLAIM] Aborted, nothing was sent");
+        return Ok(());
+    }
+
+    // Each transaction waits for the previous to be mined.
+    for (clearinghouse, coolers, loan_ids) in batches {
+        claim_through(clearinghouse, signer.clone(), &notifier, coolers, loan_ids).await?;
+    }
+    notifier.flush().await;
+
+    Ok(())
+}
+
+/// Prints the reward of every loan at `timestamp` and what the gas of all transactions costs
+/// at the current fees.
+async fn print_expected<M: Middleware + 'static>(
+    strategy: &LiquidationStrategy<M>,
+    targets: &[(LoanKey, Loan)],
+    timestamp: U256,
+    gas_estimate: U256,
+) -> Result<()> {
+    let gohm_price = strategy.oracle.gohm_price().await.ok();
+    let mut reward_dollar = Usd::zero();
+    for ((cooler, loan_id), loan) in targets {
+        let reward = strategy.auction.reward(loan.collateral, loan.expiry, timestamp);
+        let percentage = strategy.auction.reward_percentage(loan.expiry, timestamp);
+        print!("[CLAIM] {:?}:{}: {:.4} gOHM ({}%)", cooler, loan_id, to_ether(reward), percentage);
+        if let Some(price) = gohm_price {
+            let dollar = strategy.auction.rewards_in_dollar(loan.collateral, loan.expiry, timestamp, price);
+            reward_dollar += dollar;
+            print!(", {} dollar", dollar);
+        }
+        println!();
+    }
+
+    let client = &*strategy.client;
+    let gas_price = gas::base_fee(client).await? + gas::priority_fee(client, &strategy.config.gas, None, U256::zero()).await?;
+    let gas_cost = gas_estimate * gas_price;
+    print!("[CLAIM] Gas: {} units, {:.5} ETH", gas_estimate, to_ether(gas_cost));
+    let eth_price = strategy.oracle.eth_price().await.ok();
+    match eth_price {
+        Some(eth_price) => println!(", {} dollar", eth_price.value(gas_cost)),
+        None => println!(),
+    }
+    // Signed, a marginal claim may well lose money.
+    if let (Some(_), Some(eth_price)) = (gohm_price, eth_price) {
+        let profit = reward_dollar.to_f64() - eth_price.value(gas_cost).to_f64();
+        println!("[CLAIM] Expected profit: {:.2} dollar", profit);
+    }
+
+    Ok(())
+}
+
+/// Asks on stdin whether to send, anything but yes declines.
+async fn confirm() -> Result<bool> {
+    print!("Send the claim? [y/N] ");
+    std::io::Write::flush(&mut std::io::stdout())?;
+    let answer = BufReader::new(stdin()).lines().next_line().await?.unwrap_or_default();
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+async fn claim_through<M, S>(
+    clearinghouse: &Clearinghouse<M>,
+    signer: Arc<S>,
+    notifier: &Notifier,
+    coolers: Vec<Address>,
+    loan_ids: Vec<U256>,
+) -> Result<()>
+where
+    M: Middleware + 'static,
+    S: Middleware + 'static,
+{
+    // Simulated again, the confirmation may have taken a while.
+    let call = clearinghouse.claim_defaulted(coolers.clone(), loan_ids);
+    call.call()
+        .await
+        .map_err(|err| anyhow!("simulation failed, nothing was sent: {}", err))?;
+
+    println!("[ACTION] Claiming {} loans through {:?}...", coolers.len(), clearinghouse.address());
+    let pending = signer.send_transaction(call.tx, None).await?;
+    let tx_hash = *pending;
+    println!("[ACTION] Claim submitted: {:?}", tx_hash);
+    notifier.notify(Notification::ClaimSubmitted { tx_hash });
+
+    let event = match pending.await? {
+        Some(receipt) if receipt.status == Some(1.into()) => Notification::ClaimMined {
+            tx_hash,
+            block_number: receipt.block_number,
+            gas_used: receipt.gas_used,
+        },
+        Some(_) => Notification::ClaimFailed {
+            tx_hash: Some(tx_hash),
+            error: REVERTED.to_string(),
+        },
+        None => Notification::ClaimFailed {
+            tx_hash: Some(tx_hash),
+            error: "transaction dropped from mempool".to_string(),
+        },
+    };
+    println!("[ACTION] {}", event.name());
+    notifier.notify(event);
+
+    Ok(())
+}
+
+/// Parses a `<cooler>:<loan_id>` pair.
+pub fn parse_target(target: &str) -> Result<(Address, U256)> {
+    let (cooler, loan_id) = target
+        .split_once(':')
+        .ok_or_else(|| anyhow!("invalid target {}, expected <cooler>:<loan_id>", target))?;
+
+    Ok((cooler.parse()?, U256::from_dec_str(loan_id)?))
+}
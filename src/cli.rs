@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use ethers::types::{Address, U256};
+
+use crate::{claim::parse_target, types::LoanKey};
+
+/// Keeper bot claiming defaulted Cooler loans through the Olympus Clearinghouse.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Network profile from the config to run against, e.g. a local fork
+    #[arg(long, global = true)]
+    pub network: Option<String>,
+    /// Log more: -v every block summary, -vv everything down to traces
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+    /// Log less: only claims and problems
+    #[arg(short, long, action = ArgAction::Count, global = true, conflicts_with = "verbose")]
+    pub quiet: u8,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the keeper (the default without a subcommand)
+    Run {
+        /// Print plain output instead of the dashboard, the default when stdout is not a terminal
+        #[arg(long)]
+        plain: bool,
+        /// Boot from a snapshot written by `snapshot` and read only the blocks after it
+        #[arg(long)]
+        from_snapshot: Option<PathBuf>,
+    },
+    /// Print the tracked loans and which of them are claimable, then exit
+    Loans {
+        #[arg(long, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
+    /// Claim loans right away, ignoring MIN_PROFIT and REWARD_PERIOD_TARGET
+    Claim {
+        /// Cooler of the loan, repeat together with --loan-id for several loans
+        #[arg(long)]
+        cooler: Vec<Address>,
+        /// Loan id within the cooler
+        #[arg(long, value_parser = parse_loan_id)]
+        loan_id: Vec<U256>,
+        /// Further loans as <cooler>:<loan_id>
+        #[arg(value_parser = parse_loan)]
+        loans: Vec<LoanKey>,
+        /// Send without asking for confirmation
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print wallet balances, prices and gas, then exit
+    Status {
+        #[arg(long, value_enum, default_value_t)]
+        output: OutputFormat,
+    },
+    /// Interactive what-if analysis over the live loan set
+    Whatif,
+    /// Print the claim transaction that would be sent right now
+    Preview,
+    /// Print the capital efficiency report and the realized PnL per day
+    Report {
+        /// Summarize the PnL per week instead
+        #[arg(long)]
+        weekly: bool,
+    },
+    /// Write the open loans and the recorded claims to CSV files
+    Export {
+        /// Directory the files are written to
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Write the tracked loans to a snapshot file to boot another bot from with `run --from-snapshot`
+    Snapshot { path: PathBuf },
+    /// Plot the reward curve and claim history of one loan
+    Inspect {
+        /// The loan as <cooler>:<loan_id>
+        #[arg(value_parser = parse_loan)]
+        loan: LoanKey,
+    },
+    /// Replay historical blocks and report what the profit settings would have earned
+    Backtest {
+        #[arg(long)]
+        from_block: u64,
+        #[arg(long)]
+        to_block: u64,
+        /// Blocks between evaluations
+        #[arg(long, default_value_t = 300)]
+        step: u64,
+        /// Gas a claim uses per loan, on top of the 21000 base
+        #[arg(long, default_value_t = 100_000)]
+        gas_per_loan: u64,
+    },
+    /// Feed a recording through the strategy without sending anything
+    Replay { recording: PathBuf },
+}
+
+/// How `loans` and `status` print their results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    /// One JSON object on stdout, everything else goes to stderr
+    Json,
+}
+
+impl Command {
+    /// Whether the command prints JSON, which has to be the only thing on stdout.
+    pub fn json_output(&self) -> bool {
+        matches!(
+            self,
+            Command::Loans { output: OutputFormat::Json } | Command::Status { output: OutputFormat::Json }
+        )
+    }
+}
+
+/// Loans given to `claim`, flag pairs first.
+pub fn claim_targets(cooler: &[Address], loan_id: &[U256], loans: &[LoanKey]) -> Result<Vec<LoanKey>> {
+    if cooler.len() != loan_id.len() {
+        bail!("every --cooler needs a matching --loan-id");
+    }
+    Ok(cooler.iter().copied().zip(loan_id.iter().copied()).chain(loans.iter().copied()).collect())
+}
+
+fn parse_loan_id(value: &str) -> Result<U256, String> {
+    U256::from_dec_str(value).map_err(|err| err.to_string())
+}
+
+fn parse_loan(value: &str) -> Result<LoanKey, String> {
+    parse_target(value).map_err(|err| err.to_string())
+}
@@ -0,0 +1,25 @@
+use anyhow::Result;
+use artemis_core::types::{Collector, CollectorStream};
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+
+/// Turns values sent on a broadcast channel into engine events, so other parts of the bot
+/// (e.g. the executor) can feed information back to the strategy.
+pub struct ChannelCollector<E> {
+    sender: broadcast::Sender<E>,
+}
+
+impl<E> ChannelCollector<E> {
+    pub fn new(sender: broadcast::Sender<E>) -> Self {
+        Self { sender }
+    }
+}
+
+#[async_trait]
+impl<E: Clone + Send + Sync + 'static> Collector<E> for ChannelCollector<E> {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, E>> {
+        let stream = BroadcastStream::new(self.sender.subscribe()).filter_map(|event| event.ok());
+        Ok(Box::pin(stream))
+    }
+}
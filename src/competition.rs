@@ -0,0 +1,102 @@
+use std::{fmt, str::FromStr, sync::Arc};
+
+use anyhow::Result;
+use artemis_core::types::{Collector, CollectorStream};
+use async_trait::async_trait;
+use ethers::{
+    abi::AbiDecode,
+    providers::{Middleware, PubsubClient},
+    types::{Address, Transaction},
+};
+use serde::Deserialize;
+use tokio_stream::StreamExt;
+
+use crate::{bindings::clearinghouse::ClaimDefaultedCall, types::CompetitorClaim};
+
+/// Pending transactions fetched at once, as in artemis' `MempoolCollector`.
+const PENDING_TX_CONCURRENCY: usize = 256;
+
+/// What the strategy does with loans another `claimDefaulted` in the mempool targets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompetitionResponse {
+    /// Leave the contested loans out of our batches until the competing claim is resolved.
+    #[default]
+    Drop,
+    /// Claim them anyway, with a priority fee `outbid_percent` above the competitor's, as
+    /// long as the claim stays profitable at it.
+    Outbid,
+}
+
+impl FromStr for CompetitionResponse {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "drop" => Ok(CompetitionResponse::Drop),
+            "outbid" => Ok(CompetitionResponse::Outbid),
+            _ => Err(format!("unknown competition response {}, expected drop or outbid", value)),
+        }
+    }
+}
+
+impl fmt::Display for CompetitionResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompetitionResponse::Drop => write!(f, "drop"),
+            CompetitionResponse::Outbid => write!(f, "outbid"),
+        }
+    }
+}
+
+/// Watches the mempool for `claimDefaulted` calls to the clearinghouses that are not sent
+/// by one of our `wallets`. Every pending transaction is fetched, so this costs a request
+/// per transaction on the read endpoint.
+pub struct CompetitorCollector<M> {
+    client: Arc<M>,
+    clearinghouses: Vec<Address>,
+    wallets: Vec<Address>,
+}
+
+impl<M> CompetitorCollector<M> {
+    pub fn new(client: Arc<M>, clearinghouses: Vec<Address>, wallets: Vec<Address>) -> Self {
+        Self {
+            client,
+            clearinghouses,
+            wallets,
+        }
+    }
+
+    fn competitor_claim(&self, tx: &Transaction) -> Option<CompetitorClaim> {
+        let clearinghouse = tx.to.filter(|to| self.clearinghouses.contains(to))?;
+        if self.wallets.contains(&tx.from) {
+            return None;
+        }
+        let call = ClaimDefaultedCall::decode(&tx.input).ok()?;
+
+        Some(CompetitorClaim {
+            tx_hash: tx.hash,
+            from: tx.from,
+            clearinghouse,
+            // Legacy transactions tip everything above the base fee, counting all of it is on the safe side.
+            priority_fee: tx.max_priority_fee_per_gas.or(tx.gas_price).unwrap_or_default(),
+            loans: call.coolers.into_iter().zip(call.loans).collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl<M> Collector<CompetitorClaim> for CompetitorCollector<M>
+where
+    M: Middleware,
+    M::Provider: PubsubClient,
+    M::Error: 'static,
+{
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, CompetitorClaim>> {
+        let stream = self.client.subscribe_pending_txs().await?;
+        let stream = stream
+            .transactions_unordered(PENDING_TX_CONCURRENCY)
+            .filter_map(|tx| tx.ok().and_then(|tx| self.competitor_claim(&tx)));
+        Ok(Box::pin(stream))
+    }
+}
@@ -0,0 +1,1268 @@
+use std::{collections::{HashMap, HashSet}, fmt::Display, path::{Path, PathBuf}, str::FromStr, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+
+use crate::{
+    competition::CompetitionResponse,
+    dashboard::UiMode,
+    executor::Submission,
+    gas::PriorityFee,
+    gas_budget::to_ether,
+    logging::{LogFormat, LogRotation},
+    math::Usd,
+    oracle::{PriceSource, StalePricePolicy},
+    polling::RpcMode,
+    signer::SignerType,
+    subgraph::SyncSource,
+    swap::WETH,
+};
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+const DEFAULT_PRICE_CACHE_TTL: Duration = Duration::from_secs(30);
+const DEFAULT_PRICE_MAX_DEVIATION_PERCENT: u64 = 5;
+const DEFAULT_HEALTH_MAX_BLOCK_AGE: Duration = Duration::from_secs(120);
+const DEFAULT_LOAN_REFRESH_CONCURRENCY: usize = 8;
+const DEFAULT_RECONCILE_INTERVAL_HOURS: u64 = 6;
+const DEFAULT_EXPORT_INTERVAL_HOURS: u64 = 24;
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(4);
+/// Chain id of the built-in `mainnet` network.
+const MAINNET_CHAIN_ID: u64 = 1;
+
+/// Settings a claim decision depends on, as (file key, env var). They default to 0, which is
+/// worth a warning when it was not chosen.
+const DEFAULTED_SETTINGS: [(&str, &str); 2] = [("min_profit", "MIN_PROFIT"), ("reward_period_target", "REWARD_PERIOD_TARGET")];
+
+/// Olympus V3 Kernel on mainnet.
+const DEFAULT_KERNEL_ADDRESS: &str = "0x2286d7f9639e8158FaD1169e76d1FbC38247f54b";
+
+/// Bot configuration. Loaded from a TOML file (`CONFIG_FILE`, default `config.toml`),
+/// then every value can be overridden by its upper-case env var (e.g. `MIN_PROFIT`).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where the signing key lives, `private_key` (default), `keystore` or `aws`.
+    pub signer_type: SignerType,
+    pub private_key: String,
+    /// KMS key id or ARN, with `signer_type = "aws"`.
+    pub aws_kms_key_id: Option<String>,
+    /// Encrypted JSON keystore, with `signer_type = "keystore"`.
+    pub keystore_path: Option<PathBuf>,
+    /// Password of the keystore, prompted for on startup when unset.
+    pub keystore_password: Option<String>,
+    pub rpc_provider_read: String,
+    pub rpc_provider_sign: String,
+    /// Tried in order when `rpc_provider_read` times out or errors.
+    pub rpc_provider_read_fallbacks: Vec<String>,
+    /// Tried in order when `rpc_provider_sign` times out or errors.
+    pub rpc_provider_sign_fallbacks: Vec<String>,
+    pub rpc_quota: RpcQuotaConfig,
+    /// Profile from `networks` to run against, `--network` takes precedence.
+    pub network: Option<String>,
+    /// Chain id the read and sign endpoints must serve, checked on connect.
+    pub chain_id: Option<u64>,
+    /// Named networks, e.g. a local fork for rehearsals. The values a profile sets replace
+    /// the ones above and their env vars.
+    pub networks: HashMap<String, NetworkConfig>,
+    /// `ws` (default) subscribes to blocks and logs, `http-poll` polls HTTPS read endpoints.
+    pub rpc_mode: RpcMode,
+    /// How often blocks and logs are polled with `rpc_mode = "http-poll"`, 4 by default.
+    pub poll_interval_secs: Option<u64>,
+    /// Looked up from the current clearinghouse when unset.
+    pub cooler_factory_address: Address,
+    /// Looked up from the Olympus clearinghouse registry when unset.
+    pub clearinghouse_address: Address,
+    /// Older Clearinghouse deployments whose defaulted loans are claimed too.
+    pub clearinghouse_addresses: Vec<Address>,
+    /// Olympus Kernel the clearinghouse registry is found through. Defaults to mainnet.
+    pub kernel_address: Option<Address>,
+    /// Cooler V2 (MonoCooler) contract whose positions are liquidated too. Unset watches V1 coolers only.
+    pub mono_cooler_address: Option<Address>,
+    /// Block the MonoCooler was deployed at, its `Borrow` logs are read from there.
+    pub mono_cooler_start_block: u64,
+    /// Run the full strategy but only print the claims instead of submitting them.
+    pub dry_run: bool,
+    /// Minimum net profit in dollars before a claim is submitted, cents allowed.
+    pub min_profit: Usd,
+    /// Require a net profit of at least this many times the gas cost of the claim, `min_profit` stays the floor.
+    pub min_profit_gas_multiple: Option<f64>,
+    /// Percentage of the auction period that has to pass before a loan is claimed.
+    pub reward_period_target: u64,
+    /// Targets of loans from a collateral size on, `reward_period_target` applies below all of them.
+    pub reward_period_tiers: Vec<RewardTier>,
+    /// Env vars of the profit settings neither the file nor the env set, logged at startup.
+    #[serde(skip)]
+    pub defaulted: Vec<&'static str>,
+    /// Loans with less gOHM collateral than this are ignored like closed ones, 0 tracks all.
+    pub min_collateral: f64,
+    /// Where gOHM and ETH prices come from first, the other source is the fallback.
+    pub price_source: PriceSource,
+    /// With `price_source = "median"`, quotes further than this off the median are ignored. Defaults to 5.
+    pub price_max_deviation_percent: Option<u64>,
+    /// Seconds a price is reused before it is refreshed in the background. Defaults to 30,
+    /// 0 fetches a fresh price every time.
+    pub price_cache_ttl_secs: Option<u64>,
+    pub stale_price: StalePriceConfig,
+    pub price_api: PriceApiConfig,
+    pub custom_price: CustomPriceConfig,
+    pub api_listen_addr: Option<String>,
+    /// `/healthz` fails once the last processed block is older than this. Defaults to 120.
+    pub health_max_block_age_secs: Option<u64>,
+    /// Unix socket for the control protocol (status, pause, resume, claim, reload).
+    pub control_socket: Option<PathBuf>,
+    /// Rhai script defining `fn should_claim(loan)`, consulted before each claim.
+    pub policy_script: Option<PathBuf>,
+    /// SQLite file persisting loans and the last processed block across restarts.
+    pub database_path: Option<PathBuf>,
+    /// Snapshot of the loans to boot from, written by `snapshot`. Ignored once the database is past it.
+    pub snapshot_path: Option<PathBuf>,
+    /// Blocks per `eth_getLogs` request while syncing, for providers capping the range. Unset queries at once.
+    pub log_block_range: Option<u64>,
+    /// Where the loans are discovered on the first start, `logs` (default) or `subgraph`.
+    pub sync_source: SyncSource,
+    /// GraphQL endpoint of a subgraph indexing the CoolerFactory, with `sync_source = "subgraph"`.
+    pub subgraph_url: Option<String>,
+    /// Loans re-read from their coolers at once before a claim. Defaults to 8.
+    pub loan_refresh_concurrency: Option<usize>,
+    /// Append every received event to this JSON lines file, for `replay`.
+    pub record_path: Option<PathBuf>,
+    /// Tracing filter, e.g. `warn,olympusdao_liquidation_bot::executor=debug`, also read from `RUST_LOG`.
+    /// Can be changed at runtime with the `log` control command. Picked by `verbosity` when unset.
+    pub log_filter: Option<String>,
+    /// -1 logs only claims and problems, 0 (default) state changes, 1 every block summary and
+    /// 2 everything down to traces. `-v` and `-q` raise and lower it.
+    pub verbosity: i8,
+    /// `text` (default) or `json`, one object per event for log shippers.
+    pub log_format: LogFormat,
+    /// `auto` (default), `plain` or `headless`, which leaves only the log lines.
+    pub ui: UiMode,
+    /// Also write the logs to rolling files in this directory.
+    pub log_dir: Option<PathBuf>,
+    /// `daily` (default), `hourly` or `never`, when a new file in `log_dir` is started.
+    pub log_rotation: LogRotation,
+    /// Print the capital efficiency report this often. 0 disables it.
+    pub report_interval_hours: u64,
+    /// Write the loans and claims as CSV files to this directory while the bot runs.
+    pub export_dir: Option<PathBuf>,
+    /// How often `export_dir` is rewritten. Defaults to 24, 0 disables it.
+    pub export_interval_hours: Option<u64>,
+    /// Re-read the loans from chain this often and correct the tracked ones. Defaults to 6, 0 disables it.
+    pub reconcile_interval_hours: Option<u64>,
+    pub hooks: HooksConfig,
+    pub discord: DiscordConfig,
+    pub slack: SlackConfig,
+    pub email: EmailConfig,
+    pub pagerduty: PagerDutyConfig,
+    pub webhook: WebhookConfig,
+    pub chain_health: ChainHealthConfig,
+    pub balance: BalanceConfig,
+    pub expiry_warning: ExpiryWarningConfig,
+    pub gas: GasConfig,
+    pub consolidation: ConsolidationConfig,
+    pub attestation: AttestationConfig,
+    pub account_abstraction: AccountAbstractionConfig,
+    pub swap: SwapConfig,
+    pub submission: SubmissionConfig,
+    pub competition: CompetitionConfig,
+    pub coolers: CoolerFilterConfig,
+    /// Keeper profiles sharing this process. Empty runs a single profile from the top-level keys.
+    pub tenants: Vec<TenantConfig>,
+}
+
+/// Endpoints and contracts of a named network. Unset values keep the top-level ones.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub chain_id: Option<u64>,
+    pub rpc_provider_read: Option<String>,
+    pub rpc_provider_sign: Option<String>,
+    pub rpc_provider_read_fallbacks: Option<Vec<String>>,
+    pub rpc_provider_sign_fallbacks: Option<Vec<String>>,
+    pub kernel_address: Option<Address>,
+    pub cooler_factory_address: Option<Address>,
+    pub clearinghouse_address: Option<Address>,
+    pub clearinghouse_addresses: Option<Vec<Address>>,
+    pub mono_cooler_address: Option<Address>,
+    /// A separate database keeps a fork's loans and claims apart from the real ones.
+    pub database_path: Option<PathBuf>,
+}
+
+/// A keeper profile with its own wallet. Unset keys are taken from the top-level config.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantConfig {
+    pub name: String,
+    pub private_key: String,
+    pub min_profit: Option<Usd>,
+    pub reward_period_target: Option<u64>,
+    pub reward_period_tiers: Option<Vec<RewardTier>>,
+    pub policy_script: Option<PathBuf>,
+    pub hooks: Option<HooksConfig>,
+    pub discord: Option<DiscordConfig>,
+    pub slack: Option<SlackConfig>,
+    pub webhook: Option<WebhookConfig>,
+}
+
+/// Reward period target of loans with at least `min_collateral` gOHM. Written `50:30` in the env.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RewardTier {
+    pub min_collateral: f64,
+    pub target: u64,
+}
+
+impl RewardTier {
+    /// Target of the largest of `tiers` a loan with `collateral` (wei) reaches, `default` below all of them.
+    pub fn target_for(tiers: &[RewardTier], collateral: U256, default: u64) -> u64 {
+        let collateral = to_ether(collateral);
+        tiers
+            .iter()
+            .filter(|tier| collateral >= tier.min_collateral)
+            .max_by(|a, b| a.min_collateral.total_cmp(&b.min_collateral))
+            .map_or(default, |tier| tier.target)
+    }
+}
+
+impl FromStr for RewardTier {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid reward tier {}, expected <min collateral>:<target>", value);
+        let (min_collateral, target) = value.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            min_collateral: min_collateral.trim().parse().map_err(|_| invalid())?,
+            target: target.trim().parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksConfig {
+    pub claim_mined: Option<String>,
+    pub claim_failed: Option<String>,
+    pub engine_paused: Option<String>,
+    pub engine_resumed: Option<String>,
+    pub low_balance: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscordConfig {
+    pub webhook_url: Option<String>,
+    /// Notification types to post, e.g. `claim_mined`, `claim_failed`, `error`. Empty posts all.
+    pub events: Vec<String>,
+    /// Notifications arriving within this many seconds of each other are posted as one digest.
+    pub digest_window_secs: u64,
+    /// Messages posted per minute at most, the rest waits for the next digest. 0 is unlimited.
+    pub max_per_minute: usize,
+}
+
+impl Default for DiscordConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            events: vec![],
+            digest_window_secs: 5,
+            max_per_minute: 10,
+        }
+    }
+}
+
+/// Slack incoming webhooks. Without `alert_webhook_url` everything goes to `webhook_url`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlackConfig {
+    /// Channel for claims and other informational notifications.
+    pub webhook_url: Option<String>,
+    /// Channel for pauses, low balances, failed claims and errors.
+    pub alert_webhook_url: Option<String>,
+    /// Notification types to post, e.g. `claim_mined`, `claim_failed`, `error`. Empty posts all.
+    pub events: Vec<String>,
+}
+
+/// SMTP server and recipients of the critical alerts: engine stopped, RPC unreachable, low
+/// wallet balance and reverted claims.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    /// Unset disables email alerts.
+    pub smtp_host: Option<String>,
+    /// 465 connects over TLS, any other port upgrades with STARTTLS. Defaults to 587.
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            smtp_host: None,
+            smtp_port: 587,
+            smtp_username: None,
+            smtp_password: None,
+            from: String::new(),
+            to: vec![],
+        }
+    }
+}
+
+/// HTTP endpoints receiving every notification as a JSON POST.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// Empty disables the webhooks.
+    pub urls: Vec<String>,
+    /// Signs each request with HMAC-SHA256 when set.
+    pub secret: Option<String>,
+    /// Notification types to send, e.g. `claim_mined`, `loan_expired`, `error`. Empty sends all.
+    pub events: Vec<String>,
+    /// Attempts per endpoint before a notification is dropped. Defaults to 3.
+    pub max_attempts: u32,
+    /// Timeout of each attempt. Defaults to 10.
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            urls: vec![],
+            secret: None,
+            events: vec![],
+            max_attempts: 3,
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// PagerDuty service paged when the engine stops, the RPC is unreachable or claims keep failing.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PagerDutyConfig {
+    /// Integration key of an Events API v2 integration. Unset disables PagerDuty.
+    pub routing_key: Option<String>,
+    /// Page after this many failed claims in a row. Defaults to 3.
+    pub claim_failures: u32,
+}
+
+impl Default for PagerDutyConfig {
+    fn default() -> Self {
+        Self {
+            routing_key: None,
+            claim_failures: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChainHealthConfig {
+    /// Pause claiming when the finalized block lags the head by more than this many blocks.
+    pub max_finality_lag: u64,
+    /// Consecutive healthy blocks required before claiming resumes.
+    pub healthy_blocks_to_resume: u64,
+    /// Reorgs replacing more than this many blocks re-fetch the loans touched by logs in them.
+    pub reorg_depth: u64,
+}
+
+impl Default for ChainHealthConfig {
+    fn default() -> Self {
+        Self {
+            max_finality_lag: 128,
+            healthy_blocks_to_resume: 3,
+            reorg_depth: 0,
+        }
+    }
+}
+
+/// Checks of the claiming wallet's ETH balance.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BalanceConfig {
+    /// Alert below this many ETH. Unset alerts below the most one claim can cost at the current base fee.
+    pub min_eth: Option<f64>,
+    /// Blocks between balance checks.
+    pub check_every_blocks: u64,
+    /// Also stop claiming while the balance is low, instead of only alerting.
+    pub pause_when_low: bool,
+}
+
+impl Default for BalanceConfig {
+    fn default() -> Self {
+        Self {
+            min_eth: None,
+            check_every_blocks: 10,
+            pause_when_low: false,
+        }
+    }
+}
+
+/// `loan_expiring` notifications ahead of the expiry of large loans.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExpiryWarningConfig {
+    /// How long before the expiry to notify, 0 disables the warnings.
+    pub before_mins: u64,
+    /// Only warn about loans with at least this much gOHM collateral.
+    pub min_collateral: f64,
+}
+
+/// Request quota of the RPC providers, checked against the projected monthly usage.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcQuotaConfig {
+    /// Requests a month each endpoint may receive. Unset disables the check.
+    pub monthly_requests: Option<u64>,
+    /// Warn once the projection reaches this share of the quota.
+    pub warn_percent: u64,
+}
+
+impl Default for RpcQuotaConfig {
+    fn default() -> Self {
+        Self {
+            monthly_requests: None,
+            warn_percent: 80,
+        }
+    }
+}
+
+/// Signed status snapshots for stakeholders of a shared keeper operation.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttestationConfig {
+    pub enabled: bool,
+    /// Also write the latest attestation to this file, besides serving it at `/attestation`.
+    pub path: Option<PathBuf>,
+    pub interval_secs: u64,
+}
+
+impl Default for AttestationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            interval_secs: 300,
+        }
+    }
+}
+
+/// Handling of prices that could not be refreshed for a while.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StalePriceConfig {
+    /// Pause claims, haircut the price or only warn once it is this old.
+    pub policy: StalePricePolicy,
+    pub max_age_secs: u64,
+    /// How far `haircut` moves a stale price against us.
+    pub haircut_percent: u64,
+}
+
+impl Default for StalePriceConfig {
+    fn default() -> Self {
+        Self {
+            policy: StalePricePolicy::Pause,
+            max_age_secs: 300,
+            haircut_percent: 10,
+        }
+    }
+}
+
+/// Limits of the requests to DefiLlama and CoinGecko, each API has its own budget.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PriceApiConfig {
+    /// Requests per minute to each API at most. 0 is unlimited.
+    pub requests_per_minute: u32,
+    pub timeout_secs: u64,
+    /// Retries of a request that timed out, failed with a server error or was rate limited.
+    pub max_retries: u32,
+}
+
+impl Default for PriceApiConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 30,
+            timeout_secs: 10,
+            max_retries: 3,
+        }
+    }
+}
+
+/// JSON endpoint of `price_source = "custom"`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CustomPriceConfig {
+    /// GET endpoint, `{token}` is replaced by the CoinGecko id: `governance-ohm` or `ethereum`.
+    pub url: String,
+    /// JSON pointer to the dollar price in the response, `{token}` is replaced as well. Defaults to `/price`.
+    pub pointer: String,
+}
+
+impl Default for CustomPriceConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            pointer: "/price".to_string(),
+        }
+    }
+}
+
+/// Holding ready loans back to claim them in one batch with loans about to pass the target.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsolidationConfig {
+    /// How far ahead to look for loans to batch with, in seconds. 0 disables the planner.
+    pub window_secs: u64,
+    /// Never hold a loan past this share of its auction.
+    pub max_reward_percent: u64,
+}
+
+impl Default for ConsolidationConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: 0,
+            max_reward_percent: 50,
+        }
+    }
+}
+
+/// Fee bidding of claim transactions. Claims are sent as EIP-1559 transactions with
+/// `max_fee_per_gas` at twice the base fee plus the priority fee.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GasConfig {
+    pub priority_fee: PriorityFee,
+    /// Priority fee for `fixed`, and the minimum for the other strategies.
+    pub priority_fee_gwei: f64,
+    /// Percentile of the tips paid in recent blocks, for `percentile`.
+    pub priority_fee_percentile: f64,
+    /// Percentage of the expected profit bid as priority fee, for `profit`.
+    pub profit_bid_percent: u64,
+    /// Replace a claim that isn't mined after this many blocks. 0 never replaces.
+    pub stuck_after_blocks: u64,
+    /// Fee increase per replacement. Nodes only accept replacements bumped by at least 10%.
+    pub fee_bump_percent: u64,
+    /// Replacements never bid a higher `max_fee_per_gas` than this.
+    pub max_fee_gwei: f64,
+    /// Cancel the claim once the ceiling is reached instead of leaving it pending.
+    pub cancel_at_ceiling: bool,
+    /// Only claim while the base fee is below the batch's break-even gas price times this.
+    pub break_even_safety_factor: Option<f64>,
+    /// Replace transactions a previous run left pending with empty transfers at startup.
+    pub cancel_stale_nonces: bool,
+    /// Don't claim while the base fee is above this, however profitable the claim looks.
+    pub max_gas_price_gwei: Option<f64>,
+    /// Pause claiming once the wallet spent this much ETH on gas since midnight UTC.
+    pub daily_budget_eth: Option<f64>,
+    /// Split claims whose gas estimate exceeds this into several transactions. 0 never splits.
+    pub max_batch_gas: u64,
+}
+
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self {
+            priority_fee: PriorityFee::Fixed,
+            priority_fee_gwei: 1.0,
+            priority_fee_percentile: 50.0,
+            profit_bid_percent: 10,
+            stuck_after_blocks: 3,
+            fee_bump_percent: 15,
+            max_fee_gwei: 200.0,
+            cancel_at_ceiling: false,
+            break_even_safety_factor: None,
+            cancel_stale_nonces: false,
+            max_gas_price_gwei: None,
+            daily_budget_eth: None,
+            max_batch_gas: 10_000_000,
+        }
+    }
+}
+
+/// Experimental ERC-4337 submission. Claims are sent as user operations when `bundler_url` is set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccountAbstractionConfig {
+    pub bundler_url: Option<String>,
+    /// Endpoint answering `pm_sponsorUserOperation`, pays the gas when set.
+    pub paymaster_url: Option<String>,
+    /// SimpleAccount compatible smart account owned by `private_key`.
+    pub sender: Address,
+    pub entry_point: Address,
+}
+
+impl Default for AccountAbstractionConfig {
+    fn default() -> Self {
+        Self {
+            bundler_url: None,
+            paymaster_url: None,
+            sender: Address::zero(),
+            // EntryPoint v0.6
+            entry_point: "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789".parse().unwrap(),
+        }
+    }
+}
+
+/// Selling the gOHM reward of mined claims, e.g. to keep the wallet topped up with ETH for gas.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SwapConfig {
+    pub enabled: bool,
+    /// Uniswap V2 compatible router, SushiSwap by default for its gOHM/WETH pool.
+    pub router: Address,
+    /// Token to receive. Anything but WETH is routed through WETH.
+    pub output_token: Address,
+    /// Accepted shortfall against the router's quote, in basis points.
+    pub max_slippage_bps: u64,
+}
+
+impl Default for SwapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            router: "0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F".parse().unwrap(),
+            output_token: WETH.parse().unwrap(),
+            max_slippage_bps: 100,
+        }
+    }
+}
+
+/// How claims reach the builders. With `private` the client signing claims sends the raw
+/// transactions, and the nonce and transaction lookups following them, to `private_rpc_url`.
+/// With `bundle` they go to all of `bundle_builders` as `eth_sendBundle`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SubmissionConfig {
+    pub mode: Submission,
+    pub private_rpc_url: String,
+    pub bundle_builders: Vec<String>,
+    /// Blocks a bundle is retargeted for before the claim is given up.
+    pub bundle_blocks: u64,
+}
+
+impl Default for SubmissionConfig {
+    fn default() -> Self {
+        Self {
+            mode: Submission::Public,
+            private_rpc_url: "https://rpc.flashbots.net/fast".to_string(),
+            bundle_builders: [
+                "https://relay.flashbots.net",
+                "https://rpc.beaverbuild.org",
+                "https://rsync-builder.xyz",
+                "https://rpc.titanbuilder.xyz",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            bundle_blocks: 5,
+        }
+    }
+}
+
+/// Watching the mempool for other bots' `claimDefaulted` calls to the clearinghouses. Every
+/// pending transaction is fetched from the read endpoint, which adds up on metered plans.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompetitionConfig {
+    pub monitor: bool,
+    pub response: CompetitionResponse,
+    /// How far above a competitor's priority fee to bid with `outbid`.
+    pub outbid_percent: u64,
+    /// Added to the bid on top of `outbid_percent`.
+    pub outbid_increment_gwei: f64,
+}
+
+impl Default for CompetitionConfig {
+    fn default() -> Self {
+        Self {
+            monitor: false,
+            response: CompetitionResponse::Drop,
+            outbid_percent: 10,
+            outbid_increment_gwei: 0.0,
+        }
+    }
+}
+
+/// Coolers whose loans must or must not be claimed. Entries match the cooler address or the
+/// address of its owner.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CoolerFilterConfig {
+    /// Only claim loans of these coolers or owners. Empty allows every cooler.
+    pub allowlist: Vec<Address>,
+    /// Never claim loans of these coolers or owners, even if allowlisted.
+    pub blocklist: Vec<Address>,
+}
+
+impl CoolerFilterConfig {
+    pub fn is_empty(&self) -> bool {
+        self.allowlist.is_empty() && self.blocklist.is_empty()
+    }
+
+    /// Whether loans of `cooler`, owned by `owner`, may be claimed.
+    pub fn permits(&self, cooler: Address, owner: Address) -> bool {
+        let listed = |list: &[Address]| list.contains(&cooler) || list.contains(&owner);
+        !listed(&self.blocklist) && (self.allowlist.is_empty() || listed(&self.allowlist))
+    }
+}
+
+impl Config {
+    /// Read endpoints, primary first.
+    pub fn read_endpoints(&self) -> Vec<String> {
+        std::iter::once(&self.rpc_provider_read).chain(&self.rpc_provider_read_fallbacks).cloned().collect()
+    }
+
+    /// Broadcast endpoints, primary first.
+    pub fn sign_endpoints(&self) -> Vec<String> {
+        std::iter::once(&self.rpc_provider_sign).chain(&self.rpc_provider_sign_fallbacks).cloned().collect()
+    }
+
+    /// Clearinghouses to claim through, `clearinghouse_address` first.
+    pub fn clearinghouses(&self) -> Vec<Address> {
+        let mut clearinghouses = vec![self.clearinghouse_address];
+        for address in &self.clearinghouse_addresses {
+            if !clearinghouses.contains(address) {
+                clearinghouses.push(*address);
+            }
+        }
+        clearinghouses
+    }
+
+    pub fn kernel_address(&self) -> Address {
+        self.kernel_address.unwrap_or_else(|| DEFAULT_KERNEL_ADDRESS.parse().unwrap())
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval_secs.map_or(DEFAULT_POLL_INTERVAL, Duration::from_secs)
+    }
+
+    /// Net profit a claim costing `gas_cost` has to beat, see `min_profit_gas_multiple`.
+    pub fn min_profit_for(&self, gas_cost: Usd) -> Usd {
+        self.min_profit_with_floor(self.min_profit, gas_cost)
+    }
+
+    /// Like `min_profit_for`, with `floor` in place of `min_profit`.
+    pub fn min_profit_with_floor(&self, floor: Usd, gas_cost: Usd) -> Usd {
+        self.min_profit_gas_multiple
+            .map_or(floor, |multiple| floor.max(gas_cost.scale(multiple)))
+    }
+
+    /// Reward period target of a loan with `collateral` (wei), see `reward_period_tiers`.
+    pub fn reward_period_target_for(&self, collateral: U256) -> u64 {
+        RewardTier::target_for(&self.reward_period_tiers, collateral, self.reward_period_target)
+    }
+
+    pub fn price_cache_ttl(&self) -> Duration {
+        self.price_cache_ttl_secs.map_or(DEFAULT_PRICE_CACHE_TTL, Duration::from_secs)
+    }
+
+    pub fn price_max_deviation_percent(&self) -> u64 {
+        self.price_max_deviation_percent.unwrap_or(DEFAULT_PRICE_MAX_DEVIATION_PERCENT)
+    }
+
+    pub fn reconcile_interval_hours(&self) -> u64 {
+        self.reconcile_interval_hours.unwrap_or(DEFAULT_RECONCILE_INTERVAL_HOURS)
+    }
+
+    pub fn export_interval_hours(&self) -> u64 {
+        self.export_interval_hours.unwrap_or(DEFAULT_EXPORT_INTERVAL_HOURS)
+    }
+
+    pub fn loan_refresh_concurrency(&self) -> usize {
+        self.loan_refresh_concurrency.unwrap_or(DEFAULT_LOAN_REFRESH_CONCURRENCY)
+    }
+
+    pub fn health_max_block_age(&self) -> Duration {
+        self.health_max_block_age_secs.map_or(DEFAULT_HEALTH_MAX_BLOCK_AGE, Duration::from_secs)
+    }
+
+    /// One config per keeper profile, named. Without tenants this is just the config itself.
+    pub fn profiles(&self) -> Vec<(String, Config)> {
+        if self.tenants.is_empty() {
+            return vec![("default".to_string(), self.clone())];
+        }
+
+        self.tenants
+            .iter()
+            .map(|tenant| {
+                let mut config = Config {
+                    tenants: vec![],
+                    private_key: tenant.private_key.clone(),
+                    ..self.clone()
+                };
+                if let Some(min_profit) = tenant.min_profit {
+                    config.min_profit = min_profit;
+                }
+                if let Some(reward_period_target) = tenant.reward_period_target {
+                    config.reward_period_target = reward_period_target;
+                }
+                if let Some(reward_period_tiers) = &tenant.reward_period_tiers {
+                    config.reward_period_tiers = reward_period_tiers.clone();
+                }
+                if let Some(policy_script) = &tenant.policy_script {
+                    config.policy_script = Some(policy_script.clone());
+                }
+                if let Some(hooks) = &tenant.hooks {
+                    config.hooks = hooks.clone();
+                }
+                if let Some(discord) = &tenant.discord {
+                    config.discord = discord.clone();
+                }
+                if let Some(slack) = &tenant.slack {
+                    config.slack = slack.clone();
+                }
+                if let Some(webhook) = &tenant.webhook {
+                    config.webhook = webhook.clone();
+                }
+                (tenant.name.clone(), config)
+            })
+            .collect()
+    }
+
+    pub fn load() -> Result<Self> {
+        Self::load_network(None)
+    }
+
+    /// Loads the config for `network`, or the one `NETWORK` or the file select.
+    pub fn load_network(network: Option<&str>) -> Result<Self> {
+        let path = std::env::var("CONFIG_FILE").unwrap_or_else(|_| DEFAULT_CONFIG_FILE.to_string());
+        let (mut config, file_keys) = if Path::new(&path).exists() {
+            let content = std::fs::read_to_string(&path).with_context(|| format!("failed to read {}", path))?;
+            let config: Config = toml::from_str(&content).with_context(|| format!("failed to parse {}", path))?;
+            let table: toml::Table = toml::from_str(&content).with_context(|| format!("failed to parse {}", path))?;
+            (config, table.keys().cloned().collect())
+        } else {
+            (Config::default(), HashSet::new())
+        };
+
+        config.defaulted = DEFAULTED_SETTINGS
+            .into_iter()
+            .filter(|(key, var)| !file_keys.contains(*key) && std::env::var(var).map_or(true, |raw| raw.is_empty()))
+            .map(|(_, var)| var)
+            .collect();
+        config.apply_env()?;
+        if let Some(network) = network {
+            config.network = Some(network.to_string());
+        }
+        config.apply_network()?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Replaces the values the selected network sets. `mainnet` only pins the chain id unless
+    /// it is configured.
+    fn apply_network(&mut self) -> Result<()> {
+        let Some(name) = &self.network else {
+            return Ok(());
+        };
+        let network = match self.networks.get(name) {
+            Some(network) => network.clone(),
+            None if name == "mainnet" => NetworkConfig {
+                chain_id: Some(MAINNET_CHAIN_ID),
+                ..Default::default()
+            },
+            None => {
+                let mut known: Vec<&String> = self.networks.keys().collect();
+                known.sort();
+                bail!("unknown network {}, configured networks: {:?}", name, known);
+            }
+        };
+
+        if network.chain_id.is_some() {
+            self.chain_id = network.chain_id;
+        }
+        if let Some(url) = network.rpc_provider_read {
+            self.rpc_provider_read = url;
+        }
+        if let Some(url) = network.rpc_provider_sign {
+            self.rpc_provider_sign = url;
+        }
+        if let Some(urls) = network.rpc_provider_read_fallbacks {
+            self.rpc_provider_read_fallbacks = urls;
+        }
+        if let Some(urls) = network.rpc_provider_sign_fallbacks {
+            self.rpc_provider_sign_fallbacks = urls;
+        }
+        if network.kernel_address.is_some() {
+            self.kernel_address = network.kernel_address;
+        }
+        if let Some(address) = network.cooler_factory_address {
+            self.cooler_factory_address = address;
+        }
+        if let Some(address) = network.clearinghouse_address {
+            self.clearinghouse_address = address;
+        }
+        if let Some(addresses) = network.clearinghouse_addresses {
+            self.clearinghouse_addresses = addresses;
+        }
+        if network.mono_cooler_address.is_some() {
+            self.mono_cooler_address = network.mono_cooler_address;
+        }
+        if network.database_path.is_some() {
+            self.database_path = network.database_path;
+        }
+
+        Ok(())
+    }
+
+    fn apply_env(&mut self) -> Result<()> {
+        env_override(&mut self.signer_type, "SIGNER_TYPE")?;
+        env_override(&mut self.private_key, "PRIVATE_KEY")?;
+        env_override_opt(&mut self.aws_kms_key_id, "AWS_KMS_KEY_ID")?;
+        env_override_opt(&mut self.keystore_path, "KEYSTORE_PATH")?;
+        env_override_opt(&mut self.keystore_password, "KEYSTORE_PASSWORD")?;
+        env_override(&mut self.rpc_provider_read, "RPC_PROVIDER_READ")?;
+        env_override(&mut self.rpc_provider_sign, "RPC_PROVIDER_SIGN")?;
+        env_override_list(&mut self.rpc_provider_read_fallbacks, "RPC_PROVIDER_READ_FALLBACKS");
+        env_override_list(&mut self.rpc_provider_sign_fallbacks, "RPC_PROVIDER_SIGN_FALLBACKS");
+        env_override_opt(&mut self.rpc_quota.monthly_requests, "RPC_QUOTA_MONTHLY_REQUESTS")?;
+        env_override(&mut self.rpc_quota.warn_percent, "RPC_QUOTA_WARN_PERCENT")?;
+        env_override_opt(&mut self.network, "NETWORK")?;
+        env_override_opt(&mut self.chain_id, "CHAIN_ID")?;
+        env_override(&mut self.rpc_mode, "RPC_MODE")?;
+        env_override_opt(&mut self.poll_interval_secs, "POLL_INTERVAL_SECS")?;
+        env_override(&mut self.cooler_factory_address, "COOLER_FACTORY_ADDRESS")?;
+        env_override(&mut self.clearinghouse_address, "CLEARINGHOUSE_ADDRESS")?;
+        env_override_parsed_list(&mut self.clearinghouse_addresses, "CLEARINGHOUSE_ADDRESSES")?;
+        env_override_opt(&mut self.kernel_address, "KERNEL_ADDRESS")?;
+        env_override_opt(&mut self.mono_cooler_address, "MONO_COOLER_ADDRESS")?;
+        env_override(&mut self.mono_cooler_start_block, "MONO_COOLER_START_BLOCK")?;
+        env_override(&mut self.dry_run, "DRY_RUN")?;
+        env_override(&mut self.min_profit, "MIN_PROFIT")?;
+        env_override_opt(&mut self.min_profit_gas_multiple, "MIN_PROFIT_GAS_MULTIPLE")?;
+        env_override(&mut self.reward_period_target, "REWARD_PERIOD_TARGET")?;
+        env_override_parsed_list(&mut self.reward_period_tiers, "REWARD_PERIOD_TIERS")?;
+        env_override(&mut self.min_collateral, "MIN_COLLATERAL")?;
+        env_override(&mut self.price_source, "PRICE_SOURCE")?;
+        env_override_opt(&mut self.price_max_deviation_percent, "PRICE_MAX_DEVIATION_PERCENT")?;
+        env_override_opt(&mut self.price_cache_ttl_secs, "PRICE_CACHE_TTL_SECS")?;
+        env_override(&mut self.stale_price.policy, "STALE_PRICE_POLICY")?;
+        env_override(&mut self.stale_price.max_age_secs, "STALE_PRICE_MAX_AGE_SECS")?;
+        env_override(&mut self.stale_price.haircut_percent, "STALE_PRICE_HAIRCUT_PERCENT")?;
+        env_override(&mut self.price_api.requests_per_minute, "PRICE_API_REQUESTS_PER_MINUTE")?;
+        env_override(&mut self.price_api.timeout_secs, "PRICE_API_TIMEOUT_SECS")?;
+        env_override(&mut self.price_api.max_retries, "PRICE_API_MAX_RETRIES")?;
+        env_override(&mut self.custom_price.url, "CUSTOM_PRICE_URL")?;
+        env_override(&mut self.custom_price.pointer, "CUSTOM_PRICE_POINTER")?;
+        env_override_opt(&mut self.api_listen_addr, "API_LISTEN_ADDR")?;
+        env_override_opt(&mut self.health_max_block_age_secs, "HEALTH_MAX_BLOCK_AGE_SECS")?;
+        env_override_opt(&mut self.control_socket, "CONTROL_SOCKET")?;
+        env_override_opt(&mut self.policy_script, "POLICY_SCRIPT")?;
+        env_override_opt(&mut self.database_path, "DATABASE_PATH")?;
+        env_override_opt(&mut self.snapshot_path, "SNAPSHOT_PATH")?;
+        env_override_opt(&mut self.log_block_range, "LOG_BLOCK_RANGE")?;
+        env_override(&mut self.sync_source, "SYNC_SOURCE")?;
+        env_override_opt(&mut self.subgraph_url, "SUBGRAPH_URL")?;
+        env_override_opt(&mut self.loan_refresh_concurrency, "LOAN_REFRESH_CONCURRENCY")?;
+        env_override_opt(&mut self.record_path, "RECORD_PATH")?;
+        env_override_opt(&mut self.log_filter, "RUST_LOG")?;
+        env_override_opt(&mut self.log_filter, "LOG_FILTER")?;
+        env_override(&mut self.verbosity, "VERBOSITY")?;
+        env_override(&mut self.log_format, "LOG_FORMAT")?;
+        env_override(&mut self.ui, "UI")?;
+        env_override_opt(&mut self.log_dir, "LOG_DIR")?;
+        env_override(&mut self.log_rotation, "LOG_ROTATION")?;
+        env_override(&mut self.report_interval_hours, "REPORT_INTERVAL_HOURS")?;
+        env_override_opt(&mut self.export_dir, "EXPORT_DIR")?;
+        env_override_opt(&mut self.export_interval_hours, "EXPORT_INTERVAL_HOURS")?;
+        env_override_opt(&mut self.reconcile_interval_hours, "RECONCILE_INTERVAL_HOURS")?;
+        env_override_opt(&mut self.hooks.claim_mined, "HOOK_CLAIM_MINED")?;
+        env_override_opt(&mut self.hooks.claim_failed, "HOOK_CLAIM_FAILED")?;
+        env_override_opt(&mut self.hooks.engine_paused, "HOOK_ENGINE_PAUSED")?;
+        env_override_opt(&mut self.hooks.engine_resumed, "HOOK_ENGINE_RESUMED")?;
+        env_override_opt(&mut self.hooks.low_balance, "HOOK_LOW_BALANCE")?;
+        env_override_opt(&mut self.discord.webhook_url, "DISCORD_WEBHOOK_URL")?;
+        env_override_list(&mut self.discord.events, "DISCORD_EVENTS");
+        env_override(&mut self.discord.digest_window_secs, "DISCORD_DIGEST_WINDOW_SECS")?;
+        env_override(&mut self.discord.max_per_minute, "DISCORD_MAX_PER_MINUTE")?;
+        env_override_opt(&mut self.slack.webhook_url, "SLACK_WEBHOOK_URL")?;
+        env_override_opt(&mut self.slack.alert_webhook_url, "SLACK_ALERT_WEBHOOK_URL")?;
+        env_override_list(&mut self.slack.events, "SLACK_EVENTS");
+        env_override_opt(&mut self.email.smtp_host, "SMTP_HOST")?;
+        env_override(&mut self.email.smtp_port, "SMTP_PORT")?;
+        env_override_opt(&mut self.email.smtp_username, "SMTP_USERNAME")?;
+        env_override_opt(&mut self.email.smtp_password, "SMTP_PASSWORD")?;
+        env_override(&mut self.email.from, "EMAIL_FROM")?;
+        env_override_list(&mut self.email.to, "EMAIL_TO");
+        env_override_opt(&mut self.pagerduty.routing_key, "PAGERDUTY_ROUTING_KEY")?;
+        env_override(&mut self.pagerduty.claim_failures, "PAGERDUTY_CLAIM_FAILURES")?;
+        env_override_list(&mut self.webhook.urls, "WEBHOOK_URLS");
+        env_override_opt(&mut self.webhook.secret, "WEBHOOK_SECRET")?;
+        env_override_list(&mut self.webhook.events, "WEBHOOK_EVENTS");
+        env_override(&mut self.webhook.max_attempts, "WEBHOOK_MAX_ATTEMPTS")?;
+        env_override(&mut self.webhook.timeout_secs, "WEBHOOK_TIMEOUT_SECS")?;
+        env_override(&mut self.chain_health.max_finality_lag, "MAX_FINALITY_LAG")?;
+        env_override(&mut self.chain_health.healthy_blocks_to_resume, "HEALTHY_BLOCKS_TO_RESUME")?;
+        env_override(&mut self.chain_health.reorg_depth, "REORG_DEPTH")?;
+        env_override_opt(&mut self.balance.min_eth, "LOW_BALANCE_ETH")?;
+        env_override(&mut self.balance.check_every_blocks, "BALANCE_CHECK_BLOCKS")?;
+        env_override(&mut self.balance.pause_when_low, "PAUSE_ON_LOW_BALANCE")?;
+        env_override(&mut self.expiry_warning.before_mins, "EXPIRY_WARNING_MINS")?;
+        env_override(&mut self.expiry_warning.min_collateral, "EXPIRY_WARNING_MIN_COLLATERAL")?;
+        env_override(&mut self.gas.priority_fee, "PRIORITY_FEE")?;
+        env_override(&mut self.gas.priority_fee_gwei, "PRIORITY_FEE_GWEI")?;
+        env_override(&mut self.gas.priority_fee_percentile, "PRIORITY_FEE_PERCENTILE")?;
+        env_override(&mut self.gas.profit_bid_percent, "PROFIT_BID_PERCENT")?;
+        env_override(&mut self.gas.stuck_after_blocks, "STUCK_AFTER_BLOCKS")?;
+        env_override(&mut self.gas.fee_bump_percent, "FEE_BUMP_PERCENT")?;
+        env_override(&mut self.gas.max_fee_gwei, "MAX_FEE_GWEI")?;
+        env_override(&mut self.gas.cancel_at_ceiling, "CANCEL_AT_CEILING")?;
+        env_override_opt(&mut self.gas.break_even_safety_factor, "BREAK_EVEN_SAFETY_FACTOR")?;
+        env_override(&mut self.gas.cancel_stale_nonces, "CANCEL_STALE_NONCES")?;
+        env_override_opt(&mut self.gas.max_gas_price_gwei, "MAX_GAS_PRICE_GWEI")?;
+        env_override_opt(&mut self.gas.daily_budget_eth, "GAS_DAILY_BUDGET_ETH")?;
+        env_override(&mut self.gas.max_batch_gas, "MAX_BATCH_GAS")?;
+        env_override(&mut self.consolidation.window_secs, "CONSOLIDATION_WINDOW_SECS")?;
+        env_override(&mut self.attestation.enabled, "ATTESTATION_ENABLED")?;
+        env_override_opt(&mut self.attestation.path, "ATTESTATION_PATH")?;
+        env_override(&mut self.attestation.interval_secs, "ATTESTATION_INTERVAL_SECS")?;
+        env_override(&mut self.consolidation.max_reward_percent, "CONSOLIDATION_MAX_REWARD_PERCENT")?;
+        env_override_opt(&mut self.account_abstraction.bundler_url, "AA_BUNDLER_URL")?;
+        env_override_opt(&mut self.account_abstraction.paymaster_url, "AA_PAYMASTER_URL")?;
+        env_override(&mut self.account_abstraction.sender, "AA_SENDER")?;
+        env_override(&mut self.account_abstraction.entry_point, "AA_ENTRY_POINT")?;
+        env_override(&mut self.swap.enabled, "SWAP_ENABLED")?;
+        env_override(&mut self.swap.router, "SWAP_ROUTER")?;
+        env_override(&mut self.swap.output_token, "SWAP_OUTPUT_TOKEN")?;
+        env_override(&mut self.swap.max_slippage_bps, "SWAP_MAX_SLIPPAGE_BPS")?;
+        env_override(&mut self.submission.mode, "SUBMISSION")?;
+        env_override(&mut self.submission.private_rpc_url, "PRIVATE_RPC_URL")?;
+        env_override_list(&mut self.submission.bundle_builders, "BUNDLE_BUILDERS");
+        env_override(&mut self.submission.bundle_blocks, "BUNDLE_BLOCKS")?;
+        env_override(&mut self.competition.monitor, "MEMPOOL_MONITOR")?;
+        env_override(&mut self.competition.response, "COMPETITION_RESPONSE")?;
+        env_override_parsed_list(&mut self.coolers.allowlist, "COOLER_ALLOWLIST")?;
+        env_override_parsed_list(&mut self.coolers.blocklist, "COOLER_BLOCKLIST")?;
+        env_override(&mut self.competition.outbid_percent, "OUTBID_PERCENT")?;
+        env_override(&mut self.competition.outbid_increment_gwei, "OUTBID_INCREMENT_GWEI")?;
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self.signer_type {
+            SignerType::PrivateKey if self.private_key.is_empty() && self.tenants.is_empty() => {
+                bail!("PRIVATE_KEY must be set");
+            }
+            SignerType::Aws if self.aws_kms_key_id.is_none() => bail!("AWS_KMS_KEY_ID must be set with SIGNER_TYPE=aws"),
+            SignerType::Aws if !self.tenants.is_empty() => bail!("SIGNER_TYPE=aws can't be combined with tenants"),
+            SignerType::Keystore if self.keystore_path.is_none() => {
+                bail!("KEYSTORE_PATH must be set with SIGNER_TYPE=keystore")
+            }
+            SignerType::Keystore if !self.tenants.is_empty() => {
+                bail!("SIGNER_TYPE=keystore can't be combined with tenants")
+            }
+            _ => {}
+        }
+        if self.price_max_deviation_percent() == 0 {
+            bail!("PRICE_MAX_DEVIATION_PERCENT must be at least 1");
+        }
+        if self.log_block_range == Some(0) {
+            bail!("LOG_BLOCK_RANGE must be at least 1");
+        }
+        if self.sync_source == SyncSource::Subgraph && self.subgraph_url.is_none() {
+            bail!("SUBGRAPH_URL must be set with SYNC_SOURCE=subgraph");
+        }
+        if self.loan_refresh_concurrency() == 0 {
+            bail!("LOAN_REFRESH_CONCURRENCY must be at least 1");
+        }
+        if self.email.smtp_host.is_some() && (self.email.from.is_empty() || self.email.to.is_empty()) {
+            bail!("EMAIL_FROM and EMAIL_TO must be set with SMTP_HOST");
+        }
+        if self.pagerduty.claim_failures == 0 {
+            bail!("PAGERDUTY_CLAIM_FAILURES must be at least 1");
+        }
+        if self.webhook.max_attempts == 0 {
+            bail!("WEBHOOK_MAX_ATTEMPTS must be at least 1");
+        }
+        match self.rpc_mode {
+            RpcMode::Ws => {
+                if !self.rpc_provider_read.starts_with("ws") {
+                    bail!("RPC_PROVIDER_READ must be set to a ws:// or wss:// url");
+                }
+                if self.rpc_provider_read_fallbacks.iter().any(|url| !url.starts_with("ws")) {
+                    bail!("RPC_PROVIDER_READ_FALLBACKS must only contain ws:// or wss:// urls");
+                }
+            }
+            RpcMode::HttpPoll => {
+                if !self.rpc_provider_read.starts_with("http") {
+                    bail!("RPC_PROVIDER_READ must be set to a http:// or https:// url with RPC_MODE=http-poll");
+                }
+                if self.rpc_provider_read_fallbacks.iter().any(|url| !url.starts_with("http")) {
+                    bail!("RPC_PROVIDER_READ_FALLBACKS must only contain http:// or https:// urls with RPC_MODE=http-poll");
+                }
+                if self.competition.monitor {
+                    bail!("MEMPOOL_MONITOR needs a websocket read endpoint, it can't be combined with RPC_MODE=http-poll");
+                }
+            }
+        }
+        if self.poll_interval_secs == Some(0) {
+            bail!("POLL_INTERVAL_SECS must be at least 1");
+        }
+        if self.rpc_provider_sign.is_empty() {
+            bail!("RPC_PROVIDER_SIGN must be set");
+        }
+        if self.clearinghouse_addresses.iter().any(Address::is_zero) {
+            bail!("CLEARINGHOUSE_ADDRESSES must not contain the zero address");
+        }
+        if self.swap.enabled && self.swap.max_slippage_bps >= 10_000 {
+            bail!("SWAP_MAX_SLIPPAGE_BPS must be below 10000");
+        }
+        if self.swap.enabled && self.account_abstraction.bundler_url.is_some() {
+            bail!("SWAP_ENABLED can't be combined with account abstraction, the reward goes to the smart account");
+        }
+        if self.submission.mode == Submission::Private && self.submission.private_rpc_url.is_empty() {
+            bail!("PRIVATE_RPC_URL must be set when SUBMISSION is private");
+        }
+        if self.submission.mode == Submission::Bundle && self.submission.bundle_builders.is_empty() {
+            bail!("BUNDLE_BUILDERS must not be empty when SUBMISSION is bundle");
+        }
+        if self.submission.mode == Submission::Bundle && self.submission.bundle_blocks == 0 {
+            bail!("BUNDLE_BLOCKS must be at least 1");
+        }
+        if self.submission.mode != Submission::Public && self.account_abstraction.bundler_url.is_some() {
+            bail!("SUBMISSION can't be combined with account abstraction, user operations go to the bundler");
+        }
+        if self.account_abstraction.bundler_url.is_some() && self.account_abstraction.sender.is_zero() {
+            bail!("AA_SENDER must be set when AA_BUNDLER_URL is set");
+        }
+        if self.competition.outbid_increment_gwei < 0.0 {
+            bail!("OUTBID_INCREMENT_GWEI must not be negative");
+        }
+        let outbids = self.competition.outbid_percent > 0 || self.competition.outbid_increment_gwei > 0.0;
+        if self.competition.monitor && self.competition.response == CompetitionResponse::Outbid && !outbids {
+            bail!("OUTBID_PERCENT or OUTBID_INCREMENT_GWEI must be above 0");
+        }
+        if self.reward_period_target > 100 {
+            bail!("REWARD_PERIOD_TARGET must be a percentage between 0 and 100");
+        }
+        if self.min_profit_gas_multiple.is_some_and(|multiple| multiple <= 0.0) {
+            bail!("MIN_PROFIT_GAS_MULTIPLE must be greater than 0");
+        }
+        if self.min_collateral < 0.0 {
+            bail!("MIN_COLLATERAL must not be negative");
+        }
+        if self.reward_period_tiers.iter().any(|tier| tier.target > 100) {
+            bail!("REWARD_PERIOD_TIERS targets must be percentages between 0 and 100");
+        }
+        if !(0.0..=100.0).contains(&self.gas.priority_fee_percentile) {
+            bail!("PRIORITY_FEE_PERCENTILE must be between 0 and 100");
+        }
+        if self.gas.profit_bid_percent > 100 {
+            bail!("PROFIT_BID_PERCENT must be a percentage between 0 and 100");
+        }
+        if self.stale_price.haircut_percent >= 100 {
+            bail!("STALE_PRICE_HAIRCUT_PERCENT must be below 100");
+        }
+        if self.price_source == PriceSource::Custom && self.custom_price.url.is_empty() {
+            bail!("CUSTOM_PRICE_URL must be set with PRICE_SOURCE=custom");
+        }
+        if self.price_api.timeout_secs == 0 {
+            bail!("PRICE_API_TIMEOUT_SECS must be at least 1");
+        }
+        if self.consolidation.max_reward_percent > 100 {
+            bail!("CONSOLIDATION_MAX_REWARD_PERCENT must be a percentage between 0 and 100");
+        }
+        if self.attestation.enabled && self.attestation.interval_secs == 0 {
+            bail!("ATTESTATION_INTERVAL_SECS must be at least 1");
+        }
+        if self.gas.break_even_safety_factor.is_some_and(|factor| factor <= 0.0) {
+            bail!("BREAK_EVEN_SAFETY_FACTOR must be positive");
+        }
+        if self.balance.check_every_blocks == 0 {
+            bail!("BALANCE_CHECK_BLOCKS must be at least 1");
+        }
+        if self.gas.max_gas_price_gwei.is_some_and(|cap| cap <= 0.0) {
+            bail!("MAX_GAS_PRICE_GWEI must be positive");
+        }
+        if self.gas.daily_budget_eth.is_some_and(|budget| budget <= 0.0) {
+            bail!("GAS_DAILY_BUDGET_ETH must be positive");
+        }
+        if self.gas.fee_bump_percent < 10 {
+            bail!("FEE_BUMP_PERCENT must be at least 10, nodes reject smaller replacements");
+        }
+        if !self.tenants.is_empty() && self.account_abstraction.bundler_url.is_some() {
+            bail!("account abstraction is not supported together with tenants");
+        }
+        let mut names = std::collections::HashSet::new();
+        for tenant in &self.tenants {
+            if tenant.name.is_empty() || !names.insert(&tenant.name) {
+                bail!("every tenant needs a unique name");
+            }
+            if tenant.private_key.is_empty() {
+                bail!("tenant {} needs a private_key", tenant.name);
+            }
+            if tenant.reward_period_target.unwrap_or(0) > 100 {
+                bail!("reward_period_target of tenant {} must be a percentage between 0 and 100", tenant.name);
+            }
+            if tenant.reward_period_tiers.iter().flatten().any(|tier| tier.target > 100) {
+                bail!("reward_period_tiers targets of tenant {} must be percentages between 0 and 100", tenant.name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn env_override<T>(value: &mut T, var: &str) -> Result<()>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Some(parsed) = env_value(var)? {
+        *value = parsed;
+    }
+
+    Ok(())
+}
+
+fn env_override_opt<T>(value: &mut Option<T>, var: &str) -> Result<()>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Some(parsed) = env_value(var)? {
+        *value = Some(parsed);
+    }
+
+    Ok(())
+}
+
+/// Comma separated list, e.g. `DISCORD_EVENTS=claim_mined,error`.
+fn env_override_list(value: &mut Vec<String>, var: &str) {
+    if let Some(raw) = std::env::var(var).ok().filter(|raw| !raw.is_empty()) {
+        *value = raw.split(',').map(|item| item.trim().to_string()).collect();
+    }
+}
+
+fn env_override_parsed_list<T>(value: &mut Vec<T>, var: &str) -> Result<()>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let mut raw = vec![];
+    env_override_list(&mut raw, var);
+    if !raw.is_empty() {
+        *value = raw
+            .iter()
+            .map(|item| item.parse().map_err(|err| anyhow::anyhow!("invalid {} entry {}: {}", var, item, err)))
+            .collect::<Result<_>>()?;
+    }
+    Ok(())
+}
+
+fn env_value<T>(var: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match std::env::var(var).ok().filter(|raw| !raw.is_empty()) {
+        Some(raw) => raw
+            .parse()
+            .map(Some)
+            .map_err(|err| anyhow::anyhow!("invalid {}: {}", var, err)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::broadcast,
+};
+
+use crate::{
+    api::SharedSnapshot,
+    claim::parse_target,
+    logging::{self, LogHandle},
+    types::ControlCommand,
+};
+
+/// Serves the line based control protocol on a Unix socket. Every line is one command,
+/// answered with one line:
+///
+/// - `status`: JSON with loan counts, gOHM price, pause state and the break-even gas price
+/// - `pause` / `resume`: stop or restart submitting claims
+/// - `claim <cooler>:<loan_id> ...`: claim the given loans on the next block
+/// - `reload`: re-read thresholds and the policy script from the config
+/// - `log [<directives>]`: show or replace the log filter, e.g. `log warn,olympusdao_liquidation_bot::pending=debug`
+pub async fn serve(
+    path: PathBuf,
+    snapshot: SharedSnapshot,
+    commands: broadcast::Sender<ControlCommand>,
+    log_handle: LogHandle,
+) -> Result<()> {
+    // A socket file left behind by a previous run would make bind fail.
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    println!("Control socket listening on {}", path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let snapshot = snapshot.clone();
+        let commands = commands.clone();
+        let log_handle = log_handle.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle(stream, snapshot, commands, log_handle).await {
+                println!("[CONTROL] Connection failed: {}", err);
+            }
+        });
+    }
+}
+
+async fn handle(
+    stream: UnixStream,
+    snapshot: SharedSnapshot,
+    commands: broadcast::Sender<ControlCommand>,
+    log_handle: LogHandle,
+) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let reply = match execute(&line, &snapshot, &commands, &log_handle).await {
+            Ok(reply) => reply,
+            Err(err) => format!("error: {}", err),
+        };
+        writer.write_all(format!("{}\n", reply).as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+async fn execute(
+    line: &str,
+    snapshot: &SharedSnapshot,
+    commands: &broadcast::Sender<ControlCommand>,
+    log_handle: &LogHandle,
+) -> Result<String> {
+    let mut words = line.split_whitespace();
+    let command = match words.next() {
+        Some("status") => return status(snapshot).await,
+        Some("log") => {
+            let directives = words.collect::<Vec<_>>().join(",");
+            if directives.is_empty() {
+                return logging::filter(log_handle);
+            }
+            logging::set_filter(log_handle, &directives)?;
+            println!("[CONTROL] Log filter set to {}", directives);
+            return Ok("ok".to_string());
+        }
+        Some("pause") => ControlCommand::Pause,
+        Some("resume") => ControlCommand::Resume,
+        Some("reload") => ControlCommand::Reload,
+        Some("claim") => {
+            let loans = words.map(parse_target).collect::<Result<Vec<_>>>()?;
+            if loans.is_empty() {
+                bail!("usage: claim <cooler>:<loan_id> [<cooler>:<loan_id> ...]");
+            }
+            ControlCommand::Claim(loans)
+        }
+        Some(other) => bail!("unknown command {}", other),
+        None => return Ok(String::new()),
+    };
+
+    commands
+        .send(command)
+        .map_err(|_| anyhow!("the strategy is not running"))?;
+
+    Ok("ok".to_string())
+}
+
+async fn status(snapshot: &SharedSnapshot) -> Result<String> {
+    Ok(snapshot.read().await.status().to_string())
+}
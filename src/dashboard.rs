@@ -0,0 +1,301 @@
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    fs::File,
+    io::{BufRead, BufReader},
+    os::fd::FromRawFd,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use chrono::{TimeZone, Utc};
+use crossterm::{
+    event::{self, Event as TermEvent, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ethers::types::U256;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+use serde::Deserialize;
+
+use crate::{
+    api::{SharedSnapshot, StrategySnapshot},
+    math::Amount,
+};
+
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+const LOG_LINES: usize = 500;
+const EVENT_LINES: usize = 100;
+/// Log lines with these tags also go to the recent events pane.
+const EVENT_TAGS: [&str; 6] = ["[EVENT]", "[ACTION]", "[CLAIM]", "[PLAN]", "[DRY RUN]", "[PAUSED]"];
+
+/// What `run` shows besides the log lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UiMode {
+    /// The dashboard on a terminal, the tables otherwise.
+    #[default]
+    Auto,
+    /// The tables after every block, same as `run --plain`.
+    Plain,
+    /// Log lines only: no greeting, tables, progress bars or colors, for systemd and Docker.
+    Headless,
+}
+
+impl FromStr for UiMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "auto" => Ok(UiMode::Auto),
+            "plain" => Ok(UiMode::Plain),
+            "headless" => Ok(UiMode::Headless),
+            _ => Err(format!("unknown ui {}, expected auto, plain or headless", value)),
+        }
+    }
+}
+
+impl Display for UiMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UiMode::Auto => write!(f, "auto"),
+            UiMode::Plain => write!(f, "plain"),
+            UiMode::Headless => write!(f, "headless"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Output {
+    logs: VecDeque<String>,
+    events: VecDeque<String>,
+}
+
+type SharedOutput = Arc<Mutex<Output>>;
+
+/// Takes over the terminal and redraws the summary, the expired loans, recent events and
+/// the log output in place every 500ms. Everything the bot prints to stdout and stderr is
+/// captured for the log pane. `q` or Ctrl-C restores the terminal and exits.
+pub fn start(snapshot: SharedSnapshot) -> Result<()> {
+    let output = SharedOutput::default();
+    let terminal = capture_stdout(output.clone())?;
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(terminal))?;
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+
+    let panic_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        panic_hook(info);
+    }));
+
+    std::thread::spawn(move || loop {
+        let state = snapshot.blocking_read().clone();
+        let drawn = terminal.draw(|frame| draw(frame, &state, &output.lock().unwrap()));
+        if drawn.is_err() {
+            break;
+        }
+        if let Ok(true) = event::poll(REFRESH_INTERVAL) {
+            if let Ok(TermEvent::Key(key)) = event::read() {
+                if is_quit(key) {
+                    restore();
+                    std::process::exit(0);
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn is_quit(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('q') || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+/// Leaves the alternate screen. The std streams stay captured, so this writes to the terminal directly.
+fn restore() {
+    let _ = disable_raw_mode();
+    if let Ok(mut tty) = File::options().write(true).open("/dev/tty") {
+        let _ = execute!(tty, LeaveAlternateScreen);
+    }
+}
+
+/// Points stdout and stderr at a pipe read into `output` and returns a handle to the real terminal.
+fn capture_stdout(output: SharedOutput) -> Result<File> {
+    // SAFETY: plain fd juggling on fds we own; the returned files take ownership of theirs.
+    let (terminal, reader) = unsafe {
+        let terminal = libc::dup(libc::STDOUT_FILENO);
+        let mut fds = [0; 2];
+        if terminal < 0 || libc::pipe(fds.as_mut_ptr()) != 0 {
+            bail!("could not capture stdout: {}", std::io::Error::last_os_error());
+        }
+        // stderr too, progress bars and panics would draw over the dashboard.
+        if libc::dup2(fds[1], libc::STDOUT_FILENO) < 0 || libc::dup2(fds[1], libc::STDERR_FILENO) < 0 {
+            bail!("could not capture stdout: {}", std::io::Error::last_os_error());
+        }
+        libc::close(fds[1]);
+        (File::from_raw_fd(terminal), File::from_raw_fd(fds[0]))
+    };
+
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            // Drop the screen clears and colors some libraries print.
+            let line = console::strip_ansi_codes(&line).trim_end().to_string();
+            if line.is_empty() {
+                continue;
+            }
+            let mut output = output.lock().unwrap();
+            if EVENT_TAGS.iter().any(|tag| line.starts_with(tag)) {
+                push_capped(&mut output.events, line.clone(), EVENT_LINES);
+            }
+            push_capped(&mut output.logs, line, LOG_LINES);
+        }
+    });
+
+    Ok(terminal)
+}
+
+fn push_capped(lines: &mut VecDeque<String>, line: String, cap: usize) {
+    if lines.len() == cap {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+fn draw(frame: &mut Frame, state: &StrategySnapshot, output: &Output) {
+    let [summary, middle, logs] =
+        Layout::vertical([Constraint::Length(6), Constraint::Min(8), Constraint::Length(12)]).areas(frame.size());
+    let [loans, events] = Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)]).areas(middle);
+
+    draw_summary(frame, summary, state);
+    draw_loans(frame, loans, state);
+    draw_lines(frame, events, "Recent events", &output.events);
+    draw_lines(frame, logs, "Log", &output.logs);
+}
+
+fn draw_summary(frame: &mut Frame, area: Rect, state: &StrategySnapshot) {
+    let block = Block::default().borders(Borders::ALL).title(" Clearinghouse Bot (q to quit) ");
+    let Some(summary) = state.lead().and_then(|tenant| tenant.summary.as_ref()) else {
+        frame.render_widget(Paragraph::new("Waiting for the first block...").block(block), area);
+        return;
+    };
+
+    let hours = |secs: Option<u64>| secs.map_or("-".to_string(), |secs| format!("{}h {:02}m", secs / 3600, secs % 3600 / 60));
+    let header = Row::new([
+        "Claimable",
+        "Inc. gas and target",
+        "Profit target",
+        "Reward target",
+        "Expired",
+        "Collateral",
+        "Next claimable",
+        "Next profitable",
+        "gOHM",
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD));
+    let values = Row::new([
+        format!("{} $", summary.claimable),
+        format!("{} $", summary.claimable_net),
+        format!("{} $", summary.min_profit),
+        format!("{}%", summary.reward_period_target),
+        summary.expired_loans.to_string(),
+        format!("{:.2} gOHM", Amount(summary.total_collateral_gohm)),
+        hours(summary.next_claimable),
+        hours(summary.next_profitable),
+        format!("{} $", state.gohm_price),
+    ]);
+    let paused: Vec<String> = state
+        .tenants
+        .iter()
+        .filter_map(|(name, tenant)| Some(format!("{} ({})", tenant.pause_reason.as_ref()?, name)))
+        .collect();
+    let status = if paused.is_empty() {
+        Cell::new("Running").style(Style::default().fg(Color::Green))
+    } else {
+        Cell::new(format!("Paused: {}", paused.join(", "))).style(Style::default().fg(Color::Yellow))
+    };
+    let balances = state
+        .tenants
+        .iter()
+        .filter_map(|(name, tenant)| Some(format!("{}: {} ETH", name, Amount(tenant.balance?))))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let status = Row::new([status, Cell::new(balances)]);
+    let table = Table::new([values, status], [Constraint::Ratio(1, 9); 9]).header(header).block(block);
+    frame.render_widget(table, area);
+}
+
+fn draw_loans(frame: &mut Frame, area: Rect, state: &StrategySnapshot) {
+    let now = U256::from(state.head_timestamp);
+    let target = state.lead().and_then(|tenant| tenant.summary.as_ref()).map_or(0, |summary| summary.reward_period_target);
+    let rows: Vec<Row> = state
+        .loans
+        .iter()
+        .filter(|loan| !loan.collateral.is_zero() && loan.expiry < now)
+        .map(|loan| {
+            let percentage = state.auction.reward_percentage(loan.expiry, now);
+            let reward = state.auction.rewards_in_dollar(loan.collateral, loan.expiry, now, state.gohm_price);
+            let expiry = Utc
+                .timestamp_opt(loan.expiry.as_u64() as i64, 0)
+                .single()
+                .map_or_else(String::new, |expiry| expiry.format("%Y-%m-%d %H:%M").to_string());
+            let profitable_at = match loan.profitable_at {
+                Some(at) if U256::from(at) <= now => "now".to_string(),
+                Some(at) => Utc
+                    .timestamp_opt(at as i64, 0)
+                    .single()
+                    .map_or_else(String::new, |at| at.format("%Y-%m-%d %H:%M").to_string()),
+                None => "-".to_string(),
+            };
+            let percentage = if percentage > target.into() {
+                Cell::new(format!("{}%", percentage)).style(Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+            } else {
+                Cell::new(format!("{}%", percentage))
+            };
+            Row::new([
+                Cell::new(format!("{:?}", loan.cooler)),
+                Cell::new(loan.loan_id.to_string()),
+                Cell::new(expiry),
+                percentage,
+                Cell::new(format!("{} $", reward)),
+                Cell::new(profitable_at),
+            ])
+        })
+        .collect();
+
+    let title = format!(" Expired loans ({}) ", rows.len());
+    let header = Row::new(["Cooler", "Loan", "Expired (UTC)", "Auction", "Reward", "Profitable (UTC)"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let widths = [
+        Constraint::Length(42),
+        Constraint::Length(6),
+        Constraint::Length(16),
+        Constraint::Length(8),
+        Constraint::Length(12),
+        Constraint::Min(16),
+    ];
+    let table = Table::new(rows, widths).header(header).block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(table, area);
+}
+
+/// The newest lines that fit, oldest on top.
+fn draw_lines(frame: &mut Frame, area: Rect, title: &str, lines: &VecDeque<String>) {
+    let visible = area.height.saturating_sub(2) as usize;
+    let items: Vec<ListItem> = lines
+        .iter()
+        .skip(lines.len().saturating_sub(visible))
+        .map(|line| ListItem::new(Line::raw(line.as_str())))
+        .collect();
+    let block = Block::default().borders(Borders::ALL).title(format!(" {} ", title));
+    frame.render_widget(List::new(items).block(block), area);
+}
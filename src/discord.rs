@@ -0,0 +1,148 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use crate::{config::DiscordConfig, notify::Notification};
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+/// Digests are posted by the notification task, so a slow Discord can't hold up the other channels longer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Notifications listed in a digest, the rest is only counted.
+const DIGEST_LINES: usize = 20;
+
+/// Posts notifications to a Discord channel. Notifications are queued and posted together
+/// once `digest_window_secs` passed since the first one, at most `max_per_minute` times a minute.
+pub struct DiscordWebhook {
+    client: Client,
+    url: String,
+    events: Vec<String>,
+    digest_window: Duration,
+    max_per_minute: usize,
+    queue: Vec<Notification>,
+    first_queued: Option<Instant>,
+    sent: VecDeque<Instant>,
+}
+
+impl DiscordWebhook {
+    pub fn new(config: &DiscordConfig) -> Option<Self> {
+        config.webhook_url.as_ref().map(|url| Self {
+            client: Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            url: url.clone(),
+            events: config.events.clone(),
+            digest_window: Duration::from_secs(config.digest_window_secs),
+            max_per_minute: config.max_per_minute,
+            queue: vec![],
+            first_queued: None,
+            sent: VecDeque::new(),
+        })
+    }
+
+    /// Queues `notification` if its event type is enabled.
+    pub fn push(&mut self, notification: Notification) {
+        if !self.events.is_empty() && !self.events.iter().any(|event| event == notification.name()) {
+            return;
+        }
+        self.first_queued.get_or_insert_with(Instant::now);
+        self.queue.push(notification);
+    }
+
+    /// When the queued notifications are due, respecting the digest window and the rate limit.
+    pub fn next_delivery(&mut self) -> Option<Instant> {
+        let due = self.first_queued? + self.digest_window;
+        while self.sent.front().is_some_and(|sent| sent.elapsed() >= RATE_WINDOW) {
+            self.sent.pop_front();
+        }
+        match self.sent.front() {
+            Some(oldest) if self.max_per_minute > 0 && self.sent.len() >= self.max_per_minute => {
+                Some(due.max(*oldest + RATE_WINDOW))
+            }
+            _ => Some(due),
+        }
+    }
+
+    /// Posts everything queued, a single notification as its own embed and more as a digest.
+    pub async fn deliver(&mut self) {
+        let queue = std::mem::take(&mut self.queue);
+        self.first_queued = None;
+        let (name, embed) = match queue.as_slice() {
+            [] => return,
+            [notification] => (notification.name(), embed(notification)),
+            notifications => ("digest", digest(notifications)),
+        };
+        self.sent.push_back(Instant::now());
+
+        let result = self
+            .client
+            .post(&self.url)
+            .json(&json!({ "embeds": [embed] }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(err) = result {
+            println!("[DISCORD] Failed to send {}: {}", name, err);
+        }
+    }
+}
+
+fn color(notification: &Notification) -> u32 {
+    match notification {
+        Notification::ClaimSubmitted { .. } | Notification::LoanExpired { .. } | Notification::LoanExpiring { .. } => 0x3498db,
+        Notification::ClaimMined { .. } | Notification::EngineResumed => 0x2ecc71,
+        Notification::EnginePaused { .. } | Notification::LowBalance { .. } => 0xe67e22,
+        Notification::ClaimFailed { .. }
+        | Notification::EngineStopped { .. }
+        | Notification::RpcUnreachable { .. }
+        | Notification::Error { .. } => 0xe74c3c,
+    }
+}
+
+fn embed(notification: &Notification) -> Value {
+    let fields: Vec<Value> = notification
+        .fields()
+        .into_iter()
+        .map(|(name, value)| json!({ "name": name, "value": value, "inline": false }))
+        .collect();
+
+    json!({
+        "title": notification.title(),
+        "color": color(notification),
+        "fields": fields,
+        "footer": { "text": "Clearinghouse Bot" },
+    })
+}
+
+/// One embed listing several notifications, colored like the most severe of them.
+fn digest(notifications: &[Notification]) -> Value {
+    let mut lines: Vec<String> = notifications
+        .iter()
+        .take(DIGEST_LINES)
+        .map(|notification| {
+            let details: Vec<String> =
+                notification.fields().into_iter().map(|(name, value)| format!("{}: {}", name, value)).collect();
+            if details.is_empty() {
+                notification.title().to_string()
+            } else {
+                format!("{} ({})", notification.title(), details.join(", "))
+            }
+        })
+        .collect();
+    if notifications.len() > DIGEST_LINES {
+        lines.push(format!("and {} more", notifications.len() - DIGEST_LINES));
+    }
+    let color = notifications
+        .iter()
+        .map(color)
+        .find(|color| *color == 0xe74c3c)
+        .unwrap_or(0x3498db);
+
+    json!({
+        "title": format!("{} notifications", notifications.len()),
+        "color": color,
+        "description": lines.join("\n"),
+        "footer": { "text": "Clearinghouse Bot" },
+    })
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    providers::Provider,
+    types::{Address, U256},
+};
+
+use crate::{
+    bindings::{clearinghouse::Clearinghouse, clearinghouse_registry::ClearinghouseRegistry, kernel::Kernel},
+    config::Config,
+    polling::ReadTransport,
+};
+
+const CLEARINGHOUSE_REGISTRY_KEYCODE: [u8; 5] = *b"CHREG";
+
+/// Fills in the clearinghouse and CoolerFactory addresses that are not configured from the
+/// Olympus Kernel. The active clearinghouses come first, followed by the retired ones that
+/// may still hold loans. The CoolerFactory is the one of the current clearinghouse.
+pub async fn resolve(config: &mut Config) -> Result<()> {
+    if !config.clearinghouse_address.is_zero() && !config.cooler_factory_address.is_zero() {
+        return Ok(());
+    }
+    let client = Arc::new(connect(config).await?);
+
+    if config.clearinghouse_address.is_zero() {
+        let (active, retired) = clearinghouses(client.clone(), config.kernel_address()).await?;
+        let Some((current, others)) = active.split_first() else {
+            bail!("the Olympus clearinghouse registry has no active clearinghouse, set CLEARINGHOUSE_ADDRESS");
+        };
+        config.clearinghouse_address = *current;
+        for address in others.iter().chain(&retired) {
+            if !config.clearinghouse_addresses.contains(address) {
+                config.clearinghouse_addresses.push(*address);
+            }
+        }
+        println!(
+            "[DISCOVERY] Clearinghouse {:?}, {} active and {} retired ones besides",
+            current,
+            others.len(),
+            retired.len()
+        );
+    }
+
+    if config.cooler_factory_address.is_zero() {
+        config.cooler_factory_address = Clearinghouse::new(config.clearinghouse_address, client)
+            .factory()
+            .call()
+            .await
+            .map_err(|err| anyhow!("could not read the CoolerFactory of the clearinghouse: {}", err))?;
+        println!("[DISCOVERY] CoolerFactory {:?}", config.cooler_factory_address);
+    }
+
+    Ok(())
+}
+
+/// The first reachable read endpoint, the engine connects separately later.
+async fn connect(config: &Config) -> Result<Provider<ReadTransport>> {
+    for url in config.read_endpoints() {
+        match ReadTransport::connect(&url, config.rpc_mode).await {
+            Ok(transport) => return Ok(Provider::new(transport)),
+            Err(err) => println!("[DISCOVERY] Could not connect to {}: {}", url, err),
+        }
+    }
+    bail!("none of the read endpoints is reachable to look up the contract addresses")
+}
+
+/// Active and retired clearinghouses as listed by the CHREG module.
+async fn clearinghouses(client: Arc<Provider<ReadTransport>>, kernel: Address) -> Result<(Vec<Address>, Vec<Address>)> {
+    let registry = Kernel::new(kernel, client.clone())
+        .get_module_for_keycode(CLEARINGHOUSE_REGISTRY_KEYCODE)
+        .call()
+        .await
+        .map_err(|err| anyhow!("could not look up CHREG in the Kernel {:?}: {}", kernel, err))?;
+    if registry.is_zero() {
+        bail!("the Kernel {:?} has no CHREG module installed", kernel);
+    }
+    let registry = ClearinghouseRegistry::new(registry, client);
+
+    let mut active = vec![];
+    for index in 0..registry.active_count().call().await?.as_u64() {
+        active.push(registry.active(U256::from(index)).call().await?);
+    }
+    // Newest first, older deployments are less likely to have open loans.
+    let mut retired = vec![];
+    for index in (0..registry.registry_count().call().await?.as_u64()).rev() {
+        let address = registry.registry(U256::from(index)).call().await?;
+        if !active.contains(&address) && !retired.contains(&address) {
+            retired.push(address);
+        }
+    }
+
+    Ok((active, retired))
+}
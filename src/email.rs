@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Message,
+    Tokio1Executor,
+};
+
+use crate::{config::EmailConfig, notify::Notification};
+
+/// Port of SMTP over TLS, any other port upgrades the connection with STARTTLS.
+const SMTPS_PORT: u16 = 465;
+/// Emails are sent by the notification task, so a slow server can't hold up the other channels longer.
+const SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Emails critical notifications to the configured recipients over SMTP.
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl EmailNotifier {
+    /// None without `smtp_host`, an error if the server or an address is invalid.
+    pub fn new(config: &EmailConfig) -> Result<Option<Self>> {
+        let Some(host) = &config.smtp_host else {
+            return Ok(None);
+        };
+        let mut transport = if config.smtp_port == SMTPS_PORT {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+        }
+        .port(config.smtp_port)
+        .timeout(Some(SMTP_TIMEOUT));
+        if let Some(username) = &config.smtp_username {
+            let password = config.smtp_password.clone().unwrap_or_default();
+            transport = transport.credentials(Credentials::new(username.clone(), password));
+        }
+        let parse = |address: &str| address.parse().map_err(|err| anyhow!("invalid email address {}: {}", address, err));
+
+        Ok(Some(Self {
+            transport: transport.build(),
+            from: parse(&config.from)?,
+            to: config.to.iter().map(|address| parse(address)).collect::<Result<_>>()?,
+        }))
+    }
+
+    pub async fn send(&self, notification: &Notification) {
+        if let Err(err) = self.try_send(notification).await {
+            println!("[EMAIL] Failed to send {}: {}", notification.name(), err);
+        }
+    }
+
+    async fn try_send(&self, notification: &Notification) -> Result<()> {
+        let mut body = format!("{}\n", notification.title());
+        for (name, value) in notification.fields() {
+            body.push_str(&format!("\n{}: {}", name, value));
+        }
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .subject(format!("[Clearinghouse Bot] {}", notification.title()));
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+        self.transport.send(message.body(body)?).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+use std::{fmt, str::FromStr, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use artemis_core::{executors::mempool_executor::SubmitTxToMempool, types::Executor};
+use async_trait::async_trait;
+use serde::Deserialize;
+use ethers::{
+    abi::AbiDecode,
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Bytes, TransactionReceipt, H256},
+    utils::keccak256,
+};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use crate::{
+    bindings::clearinghouse::ClaimDefaultedCall,
+    bundle::BundleSender,
+    config::{Config, GasConfig, SwapConfig},
+    gas,
+    gas_budget::GasTracker,
+    ledger::LedgerEntry,
+    mono_cooler,
+    nonce::{self, NonceTracker},
+    notify::{Notification, Notifier, REVERTED},
+    oracle::PriceOracle,
+    pending::{self, Resolution},
+    revert,
+    store::LoanStore,
+    swap,
+    types::{ClaimOutcome, LoanKey},
+    utils::get_sys_time_in_secs,
+};
+
+/// Where claim transactions are broadcast.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Submission {
+    /// The public mempool, through the sign endpoints.
+    #[default]
+    Public,
+    /// A private order flow RPC like Flashbots Protect or MEV-Share, so searchers can't
+    /// read the loans from the calldata and claim them first.
+    Private,
+    /// Bundles sent straight to several builders at once.
+    Bundle,
+}
+
+impl FromStr for Submission {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "public" => Ok(Submission::Public),
+            "private" => Ok(Submission::Private),
+            "bundle" => Ok(Submission::Bundle),
+            _ => Err(format!("unknown submission {}, expected public, private or bundle", value)),
+        }
+    }
+}
+
+impl fmt::Display for Submission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Submission::Public => write!(f, "public"),
+            Submission::Private => write!(f, "private"),
+            Submission::Bundle => write!(f, "bundle"),
+        }
+    }
+}
+
+/// Submits claim transactions like artemis' `MempoolExecutor`, but keeps watching them
+/// until they are mined so the outcome can be reported to the operator and the strategy.
+/// Claims are sent as EIP-1559 transactions, bidding according to the gas config, and
+/// replaced with higher fees while they are stuck. The gas of every mined transaction counts
+/// against the daily budget, and mined claims go into the PnL ledger when a database is
+/// configured. With swapping enabled their gOHM reward is sold right away.
+pub struct ClaimExecutor<M> {
+    client: Arc<M>,
+    gas: GasConfig,
+    swap: SwapConfig,
+    submission: Submission,
+    /// Set in bundle mode, claims are then sent to the builders instead of broadcast.
+    bundles: Option<BundleSender>,
+    oracle: Arc<dyn PriceOracle>,
+    store: Option<Arc<LoanStore>>,
+    gas_spent: GasTracker,
+    nonces: NonceTracker,
+    notifier: Notifier,
+    outcomes: broadcast::Sender<ClaimOutcome>,
+}
+
+impl<M: Middleware + 'static> ClaimExecutor<M> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client: Arc<M>,
+        config: &Config,
+        store: Option<Arc<LoanStore>>,
+        gas_spent: GasTracker,
+        nonces: NonceTracker,
+        oracle: Arc<dyn PriceOracle>,
+        notifier: Notifier,
+        outcomes: broadcast::Sender<ClaimOutcome>,
+    ) -> Self {
+        Self {
+            oracle,
+            client,
+            gas: config.gas.clone(),
+            swap: config.swap.clone(),
+            submission: config.submission.mode,
+            bundles: (config.submission.mode == Submission::Bundle).then(|| {
+                BundleSender::new(config.submission.bundle_builders.clone(), config.submission.bundle_blocks)
+            }),
+            store,
+            gas_spent,
+            nonces,
+            notifier,
+            outcomes,
+        }
+    }
+
+    /// Sends the claim and returns it with the nonce filled in, for replacements. In bundle
+    /// mode it is only signed, and returned raw for the builders.
+    async fn submit(&self, action: SubmitTxToMempool) -> Result<(TypedTransaction, H256, Option<Bytes>)> {
+        let gas_usage = self
+            .client
+            .estimate_gas(&action.tx, None)
+            .await
+            .map_err(|err| anyhow!("gas estimate failed: {}", revert::reason(&err)))?;
+        let fees = gas::fees(&*self.client, &self.gas, action.gas_bid_info.as_ref(), gas_usage).await?;
+        debug!(%gas_usage, ?fees, "claim priced");
+        let mut tx = gas::with_fees(action.tx, fees);
+        tx.set_gas(gas_usage);
+        // Claims through several clearinghouses can be pending at once, so count pending ones.
+        let from = self.client.default_sender().ok_or_else(|| anyhow!("claim client has no sender"))?;
+        tx.set_from(from);
+        tx.set_nonce(self.nonces.next(&*self.client, from).await?);
+        self.client.fill_transaction(&mut tx, None).await?;
+        if self.bundles.is_some() {
+            let signature = self.client.sign_transaction(&tx, from).await?;
+            let raw = tx.rlp_signed(&signature);
+            return Ok((tx, keccak256(&raw).into(), Some(raw)));
+        }
+        debug!(?tx, "sending claim");
+        let tx_hash = match self.client.send_transaction(tx.clone(), None).await {
+            Ok(pending) => *pending,
+            // The pending count was stale, e.g. after a restart or from a lagging endpoint.
+            Err(err) if nonce::is_nonce_error(&err.to_string()) => {
+                warn!(%err, "claim rejected, retrying with a fresh nonce");
+                self.nonces.resync(&*self.client, from, tx.nonce().copied()).await?;
+                tx.set_nonce(self.nonces.next(&*self.client, from).await?);
+                *self.client.send_transaction(tx.clone(), None).await?
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok((tx, tx_hash, None))
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Executor<SubmitTxToMempool> for ClaimExecutor<M> {
+    async fn execute(&self, action: SubmitTxToMempool) -> Result<()> {
+        let loans = claimed_loans(&action.tx);
+        let (tx, tx_hash, raw) = match self.submit(action).await {
+            Ok(sent) => sent,
+            Err(err) => {
+                if let Some(from) = self.client.default_sender() {
+                    let _ = self.nonces.resync(&*self.client, from, None).await;
+                }
+                self.notifier.notify(Notification::ClaimFailed {
+                    tx_hash: None,
+                    error: err.to_string(),
+                });
+                let _ = self.outcomes.send(ClaimOutcome::Failed(loans));
+                return Err(err);
+            }
+        };
+        info!(submission = %self.submission, ?tx_hash, "claim submitted");
+        self.notifier.notify(Notification::ClaimSubmitted { tx_hash });
+
+        let client = self.client.clone();
+        let gas = self.gas.clone();
+        let bundles = self.bundles.clone();
+        let swap = self.swap.clone();
+        let oracle = self.oracle.clone();
+        let store = self.store.clone();
+        let gas_spent = self.gas_spent.clone();
+        let nonces = self.nonces.clone();
+        let notifier = self.notifier.clone();
+        let outcomes = self.outcomes.clone();
+        tokio::spawn(async move {
+            let resolution = match (bundles, raw) {
+                (Some(bundles), Some(raw)) => bundles.submit_and_watch(client.clone(), &tx, raw).await,
+                _ => pending::watch(client.clone(), gas, tx, tx_hash).await,
+            };
+            if let Ok(Resolution::Mined(receipt) | Resolution::Cancelled(receipt)) = &resolution {
+                if let Err(err) = gas_spent.add(receipt) {
+                    warn!(%err, "could not record gas spent");
+                }
+            }
+            // Nonces handed out after this one would otherwise queue behind a gap.
+            if matches!(resolution, Ok(Resolution::NonceTaken | Resolution::Dropped) | Err(_)) {
+                if let Some(from) = client.default_sender() {
+                    if let Err(err) = nonces.resync(&*client, from, None).await {
+                        warn!(%err, "could not resync nonce");
+                    }
+                }
+            }
+            let abandoned = matches!(resolution, Ok(Resolution::Cancelled(_)));
+            let mined = match &resolution {
+                Ok(Resolution::Mined(receipt)) if receipt.status == Some(1.into()) => Some(receipt.clone()),
+                _ => None,
+            };
+            let event = match resolution {
+                Ok(Resolution::Mined(receipt)) if receipt.status == Some(1.into()) => Notification::ClaimMined {
+                    tx_hash: receipt.transaction_hash,
+                    block_number: receipt.block_number,
+                    gas_used: receipt.gas_used,
+                },
+                Ok(Resolution::Mined(receipt)) => Notification::ClaimFailed {
+                    tx_hash: Some(receipt.transaction_hash),
+                    error: REVERTED.to_string(),
+                },
+                Ok(Resolution::Cancelled(receipt)) => Notification::ClaimFailed {
+                    tx_hash: Some(receipt.transaction_hash),
+                    error: "cancelled after reaching the fee ceiling".to_string(),
+                },
+                Ok(Resolution::NonceTaken) => Notification::ClaimFailed {
+                    tx_hash: Some(tx_hash),
+                    error: "nonce used by another transaction".to_string(),
+                },
+                Ok(Resolution::Dropped) => Notification::ClaimFailed {
+                    tx_hash: Some(tx_hash),
+                    error: "transaction dropped from mempool".to_string(),
+                },
+                Err(err) => Notification::ClaimFailed {
+                    tx_hash: Some(tx_hash),
+                    error: err.to_string(),
+                },
+            };
+            let outcome = match event {
+                Notification::ClaimMined { .. } => ClaimOutcome::Mined(loans.clone()),
+                _ if abandoned => ClaimOutcome::Abandoned(loans.clone()),
+                _ => ClaimOutcome::Failed(loans.clone()),
+            };
+            notifier.notify(event);
+            let _ = outcomes.send(outcome);
+
+            if let (Some(receipt), Some(store)) = (&mined, &store) {
+                if let Err(err) = record(&*oracle, store, receipt, loans).await {
+                    warn!(tx_hash = ?receipt.transaction_hash, %err, "could not record claim in the ledger");
+                }
+            }
+
+            if let Some(receipt) = mined.filter(|_| swap.enabled) {
+                if let Err(err) = swap::sell_reward(client, &swap, &nonces, &receipt).await {
+                    warn!(%err, "could not sell the gOHM reward");
+                    notifier.notify(Notification::Error {
+                        message: format!("Could not sell the gOHM reward: {}", err),
+                    });
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Adds a mined claim to the ledger, valued at the current prices.
+async fn record(
+    oracle: &dyn PriceOracle,
+    store: &LoanStore,
+    receipt: &TransactionReceipt,
+    loans: Vec<LoanKey>,
+) -> Result<()> {
+    let gohm_price = oracle.gohm_price().await?;
+    let eth_price = oracle.eth_price().await?;
+    let entry = LedgerEntry::from_receipt(
+        receipt,
+        loans,
+        get_sys_time_in_secs(),
+        gohm_price.to_f64(),
+        eth_price.to_f64(),
+    );
+    store.record_ledger_entry(&entry)?;
+    info!(
+        tx_hash = ?entry.tx_hash,
+        reward_usd = entry.reward_usd(),
+        gas_usd = entry.gas_usd(),
+        "recorded claim in the ledger"
+    );
+
+    Ok(())
+}
+
+/// Cooler/loan pairs a `claimDefaulted` transaction claims, or the Cooler V2 positions a
+/// `batchLiquidate` liquidates.
+pub fn claimed_loans(tx: &TypedTransaction) -> Vec<LoanKey> {
+    tx.data()
+        .and_then(|data| ClaimDefaultedCall::decode(data).ok())
+        .map(|call| call.coolers.into_iter().zip(call.loans).collect())
+        .unwrap_or_else(|| mono_cooler::liquidated_positions(tx))
+}
@@ -0,0 +1,89 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::{TimeZone, Utc};
+use ethers::{providers::Middleware, types::U256};
+
+use crate::{gas_budget::to_ether, strategy::LiquidationStrategy};
+
+/// Syncs the loans and writes the CSV files to `dir`.
+pub async fn run<M: Middleware + 'static>(mut strategy: LiquidationStrategy<M>, dir: &Path) -> Result<()> {
+    strategy.set_loans().await?;
+    strategy.sync_head().await;
+    for path in write(&strategy, dir)? {
+        println!("[EXPORT] Wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Writes the open loans to `loans.csv` and, with a database, every recorded claim to
+/// `claims.csv` in `dir`. Files are replaced as a whole, so a spreadsheet never reads half
+/// of one. Returns the paths written.
+pub fn write<M: Middleware + 'static>(strategy: &LiquidationStrategy<M>, dir: &Path) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir).with_context(|| format!("could not create {}", dir.display()))?;
+    let timestamp = U256::from(strategy.head_timestamp);
+
+    let mut loans = String::from("cooler,loan_id,req_id,clearinghouse,collateral_gohm,expiry,expiry_utc,defaulted\n");
+    for loan in strategy.loans.open() {
+        writeln!(
+            loans,
+            "{:?},{},{},{:?},{},{},{},{}",
+            loan.cooler.address(),
+            loan.loan_id,
+            loan.req_id,
+            loan.clearinghouse,
+            to_ether(loan.collateral),
+            loan.expiry,
+            utc(loan.expiry.as_u64()),
+            loan.is_claimable(timestamp),
+        )?;
+    }
+    let mut written = vec![replace(&dir.join("loans.csv"), &loans)?];
+
+    if let Some(store) = &strategy.store {
+        let mut claims = String::from(
+            "cooler,loan_id,collateral_gohm,expiry,claimed_at,claimed_at_utc,reward_percentage,reward_gohm,reward_usd,gohm_price,ours\n",
+        );
+        for claim in store.load_claims()? {
+            let claimed_at = U256::from(claim.claimed_at);
+            writeln!(
+                claims,
+                "{:?},{},{},{},{},{},{},{},{},{},{}",
+                claim.cooler,
+                claim.loan_id,
+                to_ether(claim.collateral),
+                claim.expiry,
+                claim.claimed_at,
+                utc(claim.claimed_at),
+                strategy.auction.reward_percentage(claim.expiry, claimed_at),
+                to_ether(strategy.auction.reward(claim.collateral, claim.expiry, claimed_at)),
+                strategy.auction.rewards_in_dollar(claim.collateral, claim.expiry, claimed_at, claim.gohm_price).to_f64(),
+                claim.gohm_price.to_f64(),
+                claim.ours,
+            )?;
+        }
+        written.push(replace(&dir.join("claims.csv"), &claims)?);
+    }
+
+    Ok(written)
+}
+
+fn utc(timestamp: u64) -> String {
+    Utc.timestamp_opt(timestamp as i64, 0)
+        .single()
+        .map_or(String::new(), |time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// Writes `contents` next to `path` and moves it over `path`.
+fn replace(path: &Path, contents: &str) -> Result<PathBuf> {
+    let partial = path.with_extension("csv.partial");
+    fs::write(&partial, contents).with_context(|| format!("could not write {}", partial.display()))?;
+    fs::rename(&partial, path).with_context(|| format!("could not write {}", path.display()))?;
+
+    Ok(path.to_path_buf())
+}
@@ -0,0 +1,196 @@
+use std::{
+    fmt::{self, Debug},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use ethers::{
+    providers::{JsonRpcClient, JsonRpcError, ProviderError, PubsubClient, RpcError},
+    types::U256,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{info, warn};
+
+use crate::metrics::RpcMetrics;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Sent to the private endpoint when there is one: broadcasting, and following our own
+/// transactions, which only the private endpoint knows about until they are mined.
+const PRIVATE_METHODS: [&str; 3] = ["eth_sendRawTransaction", "eth_getTransactionByHash", "eth_getTransactionCount"];
+
+/// JSON-RPC transport over a list of endpoints. Requests go to the current endpoint and
+/// rotate to the next one on transport errors or timeouts. Error responses from the node
+/// (e.g. a reverting `eth_estimateGas`) are returned as is. Every attempt is counted in `metrics`.
+#[derive(Debug)]
+pub struct FailoverClient<T> {
+    transports: Vec<(String, T)>,
+    current: AtomicUsize,
+    metrics: RpcMetrics,
+    /// Private order flow endpoint, never failed over to the public ones.
+    private: Option<(String, T)>,
+}
+
+impl<T> FailoverClient<T> {
+    /// `transports` pairs each endpoint url (used for logging) with its transport.
+    pub fn new(transports: Vec<(String, T)>, metrics: RpcMetrics) -> Self {
+        assert!(!transports.is_empty(), "FailoverClient needs at least one transport");
+        Self {
+            transports,
+            current: AtomicUsize::new(0),
+            metrics,
+            private: None,
+        }
+    }
+
+    /// Broadcasts through `url` instead, e.g. Flashbots Protect, so transactions never
+    /// reach the public mempool.
+    pub fn with_private(mut self, url: String, transport: T) -> Self {
+        self.private = Some((url, transport));
+        self
+    }
+
+    fn current(&self) -> &T {
+        &self.transports[self.current.load(Ordering::Relaxed)].1
+    }
+}
+
+#[derive(Debug)]
+pub enum FailoverError {
+    Provider(ProviderError),
+    Timeout,
+    SerdeJson(serde_json::Error),
+}
+
+impl fmt::Display for FailoverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailoverError::Provider(err) => write!(f, "{}", err),
+            FailoverError::Timeout => write!(f, "request timed out after {:?}", REQUEST_TIMEOUT),
+            FailoverError::SerdeJson(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for FailoverError {}
+
+impl RpcError for FailoverError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            FailoverError::Provider(err) => err.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            FailoverError::Provider(err) => err.as_serde_error(),
+            FailoverError::SerdeJson(err) => Some(err),
+            FailoverError::Timeout => None,
+        }
+    }
+}
+
+impl From<FailoverError> for ProviderError {
+    fn from(err: FailoverError) -> Self {
+        match err {
+            FailoverError::Provider(err) => err,
+            FailoverError::SerdeJson(err) => err.into(),
+            FailoverError::Timeout => ProviderError::JsonRpcClientError(Box::new(err)),
+        }
+    }
+}
+
+/// Keeps zero sized params (e.g. `()` for `eth_gasPrice`) as they are, so the inner
+/// transport still omits them from the request.
+enum Params {
+    Value(serde_json::Value),
+    Zst(()),
+}
+
+#[async_trait]
+impl<T> JsonRpcClient for FailoverClient<T>
+where
+    T: JsonRpcClient + 'static,
+{
+    type Error = FailoverError;
+
+    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    where
+        A: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = if std::mem::size_of::<A>() == 0 {
+            Params::Zst(())
+        } else {
+            Params::Value(serde_json::to_value(params).map_err(FailoverError::SerdeJson)?)
+        };
+
+        if let Some((url, transport)) = self.private.as_ref().filter(|_| PRIVATE_METHODS.contains(&method)) {
+            self.metrics.record(url, method);
+            let response = match &params {
+                Params::Value(params) => tokio::time::timeout(REQUEST_TIMEOUT, transport.request(method, params)).await,
+                Params::Zst(unit) => tokio::time::timeout(REQUEST_TIMEOUT, transport.request(method, unit)).await,
+            };
+            return match response {
+                Ok(result) => result.map_err(|err| FailoverError::Provider(err.into())),
+                Err(_) => Err(FailoverError::Timeout),
+            };
+        }
+
+        let start = self.current.load(Ordering::Relaxed);
+        let mut last_err = FailoverError::Timeout;
+        for attempt in 0..self.transports.len() {
+            let index = (start + attempt) % self.transports.len();
+            let (url, transport) = &self.transports[index];
+            self.metrics.record(url, method);
+            let response = match &params {
+                Params::Value(params) => tokio::time::timeout(REQUEST_TIMEOUT, transport.request(method, params)).await,
+                Params::Zst(unit) => tokio::time::timeout(REQUEST_TIMEOUT, transport.request(method, unit)).await,
+            };
+
+            let err = match response {
+                Ok(Ok(result)) => {
+                    if index != start {
+                        info!(%url, "switched rpc endpoint");
+                        self.current.store(index, Ordering::Relaxed);
+                    }
+                    return Ok(result);
+                }
+                Ok(Err(err)) => {
+                    let err: ProviderError = err.into();
+                    if err.as_error_response().is_some() {
+                        return Err(FailoverError::Provider(err));
+                    }
+                    FailoverError::Provider(err)
+                }
+                Err(_) => FailoverError::Timeout,
+            };
+            warn!(%method, %url, %err, "rpc request failed");
+            last_err = err;
+        }
+
+        Err(last_err)
+    }
+}
+
+/// Subscriptions stay on the endpoint that answered `eth_subscribe`, which is the current
+/// one. If it dies the stream ends and the engine reconnects.
+impl<T> PubsubClient for FailoverClient<T>
+where
+    T: PubsubClient + 'static,
+{
+    type NotificationStream = T::NotificationStream;
+
+    fn subscribe<I: Into<U256>>(&self, id: I) -> Result<Self::NotificationStream, Self::Error> {
+        self.current()
+            .subscribe(id)
+            .map_err(|err| FailoverError::Provider(err.into()))
+    }
+
+    fn unsubscribe<I: Into<U256>>(&self, id: I) -> Result<(), Self::Error> {
+        self.current()
+            .unsubscribe(id)
+            .map_err(|err| FailoverError::Provider(err.into()))
+    }
+}
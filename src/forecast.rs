@@ -0,0 +1,50 @@
+use ethers::types::U256;
+use serde::Serialize;
+
+use crate::{api::LoanSnapshot, auction::Auction, math::Price};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastBucket {
+    pub start: u64,
+    pub end: u64,
+    pub loans: usize,
+    pub claimable_usd: f64,
+}
+
+/// Projects which loans cross their reward period target, given by `reward_period_target` for
+/// their collateral, in each bucket and the dollar reward they pay out at that moment. Loans that
+/// already crossed the target land in the first bucket.
+pub fn project_claimable(
+    loans: &[LoanSnapshot],
+    auction: &Auction,
+    gohm_price: Price,
+    reward_period_target: impl Fn(U256) -> u64,
+    now: u64,
+    bucket_in_s: u64,
+    bucket_count: u64,
+) -> Vec<ForecastBucket> {
+    let mut buckets: Vec<ForecastBucket> = (0..bucket_count)
+        .map(|i| ForecastBucket {
+            start: now + i * bucket_in_s,
+            end: now + (i + 1) * bucket_in_s,
+            loans: 0,
+            claimable_usd: 0.0,
+        })
+        .collect();
+
+    for loan in loans.iter().filter(|loan| loan.collateral > U256::zero()) {
+        let target_hit_at = loan.expiry.low_u64() + auction.offset_of(reward_period_target(loan.collateral));
+        let claim_at = target_hit_at.max(now);
+        let index = (claim_at - now) / bucket_in_s;
+        if index >= bucket_count {
+            continue;
+        }
+
+        let reward = auction.rewards_in_dollar(loan.collateral, loan.expiry, claim_at.into(), gohm_price);
+        let bucket = &mut buckets[index as usize];
+        bucket.loans += 1;
+        bucket.claimable_usd += reward.to_f64();
+    }
+
+    buckets
+}
@@ -0,0 +1,151 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use artemis_core::executors::mempool_executor::GasBidInfo;
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, BlockNumber, Eip1559TransactionRequest, U256},
+    utils::parse_units,
+};
+use serde::Deserialize;
+
+use crate::{
+    config::GasConfig,
+    math::{Price, Usd},
+};
+
+/// Blocks looked at for the `percentile` priority fee.
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// How the priority fee of a claim is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorityFee {
+    /// Always `priority_fee_gwei`.
+    #[default]
+    Fixed,
+    /// A percentile of the tips paid in the last blocks.
+    Percentile,
+    /// A share of the expected profit, spread over the gas used.
+    Profit,
+}
+
+impl FromStr for PriorityFee {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "fixed" => Ok(PriorityFee::Fixed),
+            "percentile" => Ok(PriorityFee::Percentile),
+            "profit" => Ok(PriorityFee::Profit),
+            _ => Err(format!("unknown priority fee strategy {}, expected fixed, percentile or profit", value)),
+        }
+    }
+}
+
+impl fmt::Display for PriorityFee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriorityFee::Fixed => write!(f, "fixed"),
+            PriorityFee::Percentile => write!(f, "percentile"),
+            PriorityFee::Profit => write!(f, "profit"),
+        }
+    }
+}
+
+/// Fee caps of a type-2 transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct Fees {
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+pub async fn base_fee<M: Middleware>(client: &M) -> Result<U256> {
+    client
+        .get_block(BlockNumber::Latest)
+        .await
+        .map_err(|err| anyhow!("could not get latest block: {}", err))?
+        .and_then(|block| block.base_fee_per_gas)
+        .ok_or_else(|| anyhow!("latest block has no base fee"))
+}
+
+/// Priority fee per gas. `priority_fee_gwei` is the floor for the `percentile` and `profit`
+/// strategies; `profit` needs the bid info and the gas usage to spread it over. With the other
+/// strategies a bid info, e.g. to outbid a competing claim, raises the fee to its bid.
+pub async fn priority_fee<M: Middleware>(
+    client: &M,
+    config: &GasConfig,
+    gas_bid_info: Option<&GasBidInfo>,
+    gas_usage: U256,
+) -> Result<U256> {
+    let floor: U256 = parse_units(config.priority_fee_gwei, "gwei")?.into();
+    let bid = match gas_bid_info {
+        Some(gas_bid_info) if !gas_usage.is_zero() => {
+            Some(gas_bid_info.total_profit / gas_usage * gas_bid_info.bid_percentage / 100)
+        }
+        _ => None,
+    };
+    let fee = match config.priority_fee {
+        PriorityFee::Fixed => floor,
+        PriorityFee::Percentile => {
+            let history = client
+                .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[config.priority_fee_percentile])
+                .await
+                .map_err(|err| anyhow!("could not get fee history: {}", err))?;
+            let rewards: Vec<U256> = history.reward.iter().filter_map(|reward| reward.first().copied()).collect();
+            if rewards.is_empty() {
+                floor
+            } else {
+                rewards.iter().fold(U256::zero(), |sum, reward| sum + reward) / rewards.len()
+            }
+        }
+        PriorityFee::Profit => bid.unwrap_or(floor),
+    };
+
+    Ok(fee.max(bid.unwrap_or_default()).max(floor))
+}
+
+/// Gas price in wei at which claiming a batch worth `reward` costs as much as it earns.
+pub fn break_even_gas_price(reward: Usd, gas_usage: U256, eth_price: Price) -> U256 {
+    if gas_usage.is_zero() {
+        return U256::zero();
+    }
+    eth_price.amount(reward) / gas_usage
+}
+
+/// Fees that stay valid for a few blocks of rising base fees, like ethers' own estimator.
+pub async fn fees<M: Middleware>(
+    client: &M,
+    config: &GasConfig,
+    gas_bid_info: Option<&GasBidInfo>,
+    gas_usage: U256,
+) -> Result<Fees> {
+    let base_fee = base_fee(client).await?;
+    let max_priority_fee_per_gas = priority_fee(client, config, gas_bid_info, gas_usage).await?;
+
+    Ok(Fees {
+        max_fee_per_gas: base_fee * 2 + max_priority_fee_per_gas,
+        max_priority_fee_per_gas,
+    })
+}
+
+/// Turns the transaction into a type-2 transaction with the given fees.
+pub fn with_fees(tx: TypedTransaction, fees: Fees) -> TypedTransaction {
+    let mut tx = match tx {
+        TypedTransaction::Eip1559(tx) => tx,
+        tx => Eip1559TransactionRequest {
+            from: tx.from().copied(),
+            to: tx.to().cloned(),
+            gas: tx.gas().copied(),
+            value: tx.value().copied(),
+            data: tx.data().cloned(),
+            nonce: tx.nonce().copied(),
+            chain_id: tx.chain_id(),
+            ..Default::default()
+        },
+    };
+    tx.max_fee_per_gas = Some(fees.max_fee_per_gas);
+    tx.max_priority_fee_per_gas = Some(fees.max_priority_fee_per_gas);
+
+    tx.into()
+}
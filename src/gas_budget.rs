@@ -0,0 +1,82 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use ethers::{
+    types::{Address, TransactionReceipt, U256},
+    utils::format_units,
+};
+
+use crate::{store::LoanStore, utils::get_sys_time_in_secs};
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// Gas a wallet's transactions spent per UTC day. Shared by a tenant's executor, which adds
+/// to it, and strategy, which pauses claiming once the daily budget is spent. Written through
+/// to the database when one is configured so the budget holds across restarts.
+#[derive(Debug, Clone, Default)]
+pub struct GasTracker {
+    sender: Address,
+    store: Option<Arc<LoanStore>>,
+    /// Wei spent per day since the epoch.
+    spent: Arc<Mutex<BTreeMap<u64, U256>>>,
+}
+
+impl GasTracker {
+    pub fn load(sender: Address, store: Option<Arc<LoanStore>>) -> Result<Self> {
+        let spent = match &store {
+            Some(store) => store.load_gas_spent(sender)?.into_iter().collect(),
+            None => BTreeMap::new(),
+        };
+        Ok(Self {
+            sender,
+            store,
+            spent: Arc::new(Mutex::new(spent)),
+        })
+    }
+
+    /// Adds what a mined transaction paid, reverted or not, to today.
+    pub fn add(&self, receipt: &TransactionReceipt) -> Result<()> {
+        let day = get_sys_time_in_secs() / DAY;
+        let total = {
+            let mut spent = self.spent.lock().unwrap();
+            let today = spent.entry(day).or_default();
+            *today += gas_paid(receipt);
+            *today
+        };
+        if let Some(store) = &self.store {
+            store.set_gas_spent(self.sender, day, total)?;
+        }
+
+        Ok(())
+    }
+
+    /// Wei spent since midnight UTC.
+    pub fn today(&self) -> U256 {
+        let day = get_sys_time_in_secs() / DAY;
+        self.spent.lock().unwrap().get(&day).copied().unwrap_or_default()
+    }
+
+    /// Wei spent since the database was created.
+    pub fn total(&self) -> U256 {
+        self.spent.lock().unwrap().values().fold(U256::zero(), |acc, spent| acc + spent)
+    }
+
+    /// Why claiming should pause, once today's spending reached `budget_eth`.
+    pub fn exceeded(&self, budget_eth: Option<f64>) -> Option<String> {
+        let budget = budget_eth?;
+        let today = to_ether(self.today());
+        (today >= budget).then(|| format!("daily gas budget spent ({:.4} of {} ETH)", today, budget))
+    }
+}
+
+/// Gas used times the effective gas price, in wei.
+pub fn gas_paid(receipt: &TransactionReceipt) -> U256 {
+    receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default()
+}
+
+pub fn to_ether(wei: U256) -> f64 {
+    format_units(wei, 18).ok().and_then(|value| value.parse().ok()).unwrap_or_default()
+}
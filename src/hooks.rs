@@ -0,0 +1,79 @@
+use std::{collections::HashMap, process::Stdio, time::Duration};
+
+use anyhow::Result;
+use tokio::{io::AsyncWriteExt, process::Command, task::JoinSet};
+
+use crate::{config::HooksConfig, notify::Notification};
+
+/// A hook still running after this is killed, so it can't hold up the other notifications.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    commands: HashMap<&'static str, String>,
+}
+
+impl Hooks {
+    pub fn new(config: &HooksConfig) -> Self {
+        let mut commands = HashMap::new();
+        for (event, command) in [
+            ("claim_mined", &config.claim_mined),
+            ("claim_failed", &config.claim_failed),
+            ("engine_paused", &config.engine_paused),
+            // An engine stop pauses claiming as well.
+            ("engine_stopped", &config.engine_paused),
+            ("engine_resumed", &config.engine_resumed),
+            ("low_balance", &config.low_balance),
+        ] {
+            if let Some(command) = command {
+                commands.insert(event, command.clone());
+            }
+        }
+
+        Self { commands }
+    }
+
+    /// Starts the shell command configured for `notification`, if any, in `running`, so a slow
+    /// hook never delays the other channels. It is killed after `HOOK_TIMEOUT`. The notification
+    /// is passed as JSON on stdin.
+    pub fn spawn(&self, notification: &Notification, running: &mut JoinSet<()>) {
+        let Some(command) = self.commands.get(notification.name()).cloned() else {
+            return;
+        };
+        let notification = notification.clone();
+        running.spawn(async move {
+            if let Err(err) = run_command(&command, &notification).await {
+                println!("[HOOK] {} hook failed: {}", notification.name(), err);
+            }
+        });
+    }
+}
+
+async fn run_command(command: &str, notification: &Notification) -> Result<()> {
+    let payload = serde_json::to_vec(notification)?;
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let stdin = child.stdin.take();
+    let finished = tokio::time::timeout(HOOK_TIMEOUT, async {
+        if let Some(mut stdin) = stdin {
+            stdin.write_all(&payload).await?;
+        }
+        child.wait().await
+    })
+    .await;
+    let Ok(status) = finished else {
+        child.kill().await?;
+        anyhow::bail!("killed after {}s", HOOK_TIMEOUT.as_secs());
+    };
+    let status = status?;
+    if !status.success() {
+        anyhow::bail!("exited with {}", status);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use ethers::{providers::Middleware, types::U256};
+
+use crate::{
+    auction::Auction,
+    bindings::cooler::Cooler,
+    math::{Amount, Price, Usd},
+    strategy::LiquidationStrategy,
+    types::LoanKey,
+};
+
+const PLOT_WIDTH: usize = 57;
+const PLOT_HEIGHT: usize = 12;
+
+/// Shows a single loan: its reward curve over the auction with the reward period target,
+/// the minimum profit and the current time marked, and the recorded claims on its cooler.
+pub async fn run<M: Middleware + 'static>(mut strategy: LiquidationStrategy<M>, (cooler, loan_id): LoanKey) -> Result<()> {
+    let loan = Cooler::new(cooler, strategy.client.clone()).get_loan(loan_id).await?;
+    let gohm_price = strategy.oracle.gohm_price().await?;
+    strategy.sync_head().await;
+    let timestamp = U256::from(strategy.head_timestamp);
+
+    println!("Loan {:?}:{}", cooler, loan_id);
+    println!("Collateral: {} gOHM", Amount(loan.collateral));
+    println!("Expiry:     {}", loan.expiry);
+    let now = if loan.collateral.is_zero() {
+        println!("Status:     repaid or claimed");
+        None
+    } else if loan.expiry >= timestamp {
+        println!("Status:     active, expires in {}h", (loan.expiry - timestamp) / 3600);
+        None
+    } else {
+        let percentage = strategy.auction.reward_percentage(loan.expiry, timestamp);
+        println!(
+            "Status:     defaulted, {}% of the auction passed, reward {} dollar",
+            percentage,
+            strategy.auction.rewards_in_dollar(loan.collateral, loan.expiry, timestamp, gohm_price)
+        );
+        Some(percentage.as_u64())
+    };
+    println!();
+
+    print_curve(
+        &strategy.auction,
+        loan.collateral,
+        gohm_price,
+        strategy.config.reward_period_target_for(loan.collateral),
+        strategy.config.min_profit,
+        now,
+    );
+    println!();
+
+    let Some(store) = &strategy.store else {
+        println!("Set DATABASE_PATH to see the claim history of this cooler.");
+        return Ok(());
+    };
+    let claims: Vec<_> = store.load_claims()?.into_iter().filter(|claim| claim.cooler == cooler).collect();
+    if claims.is_empty() {
+        println!("No recorded claims on this cooler.");
+    }
+    for claim in claims {
+        let percentage = strategy.auction.reward_percentage(claim.expiry, claim.claimed_at.into());
+        println!(
+            "Loan {} claimed by {} at {}% of the auction ({} dollar){}",
+            claim.loan_id,
+            if claim.ours { "us" } else { "another keeper" },
+            percentage,
+            strategy.auction.rewards_in_dollar(claim.collateral, claim.expiry, claim.claimed_at.into(), claim.gohm_price),
+            if claim.loan_id == loan_id { "  <- this loan" } else { "" }
+        );
+    }
+
+    Ok(())
+}
+
+/// Plots the dollar reward over the auction. `│` marks the reward period target, `─` the
+/// minimum profit and `●` now.
+fn print_curve(auction: &Auction, collateral: U256, gohm_price: Price, target: u64, min_profit: Usd, now: Option<u64>) {
+    let reward_at = |percentage: u64| {
+        auction.rewards_in_dollar(collateral, 0.into(), auction.offset_of(percentage).into(), gohm_price)
+    };
+    let max_reward = reward_at(100).max(Usd::from_dollars(1));
+    let row_of = |value: Usd| (value.min(max_reward).to_f64() * (PLOT_HEIGHT - 1) as f64 / max_reward.to_f64()) as usize;
+    let column_of = |percentage: u64| percentage.min(100) as usize * (PLOT_WIDTH - 1) / 100;
+
+    let mut grid = vec![vec![' '; PLOT_WIDTH]; PLOT_HEIGHT];
+    if min_profit <= max_reward {
+        grid[row_of(min_profit)].fill('─');
+    }
+    for row in grid.iter_mut() {
+        row[column_of(target)] = '│';
+    }
+    let curve: Vec<usize> = (0..PLOT_WIDTH as u64)
+        .map(|column| row_of(reward_at(column * 100 / (PLOT_WIDTH as u64 - 1))))
+        .collect();
+    for (column, row) in curve.into_iter().enumerate() {
+        grid[row][column] = '•';
+    }
+    if let Some(now) = now {
+        grid[row_of(reward_at(now))][column_of(now)] = '●';
+    }
+
+    println!("Reward (dollar) over the {} day auction", auction.duration / (24 * 60 * 60));
+    for (i, row) in grid.iter().enumerate().rev() {
+        let label = match i {
+            i if i == PLOT_HEIGHT - 1 => format!("{:>7.0}", max_reward),
+            0 => format!("{:>7}", 0),
+            _ => " ".repeat(7),
+        };
+        println!("{} ┤{}", label, row.iter().collect::<String>());
+    }
+    println!("{} └{}", " ".repeat(7), "─".repeat(PLOT_WIDTH));
+    println!("{}  0%{:>width$}", " ".repeat(7), "100%", width = PLOT_WIDTH - 2);
+    println!(
+        "│ reward period target {}%   ─ min profit {} dollar   ● now",
+        target, min_profit
+    );
+}
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use chrono::{TimeZone, Utc};
+use comfy_table::{presets::UTF8_FULL, Table};
+use ethers::{
+    contract::parse_log,
+    types::{Address, TransactionReceipt, H256, U256},
+};
+
+use crate::{
+    bindings::erc20::TransferFilter,
+    gas_budget::{gas_paid, to_ether},
+    math::Amount,
+    oracle::GOHM,
+    types::LoanKey,
+};
+
+const DAY: u64 = 24 * 60 * 60;
+const WEEK: u64 = 7 * DAY;
+
+/// A mined claim with what it earned and cost, valued at the prices when it was recorded.
+#[derive(Debug, Clone)]
+pub struct LedgerEntry {
+    pub tx_hash: H256,
+    pub sender: Address,
+    pub block: u64,
+    pub recorded_at: u64,
+    pub loans: Vec<LoanKey>,
+    /// gOHM paid to the sender, in wei.
+    pub gohm_received: U256,
+    /// Gas used times the effective gas price, in wei.
+    pub gas_paid: U256,
+    pub gohm_price: f64,
+    pub eth_price: f64,
+}
+
+impl LedgerEntry {
+    pub fn from_receipt(
+        receipt: &TransactionReceipt,
+        loans: Vec<LoanKey>,
+        recorded_at: u64,
+        gohm_price: f64,
+        eth_price: f64,
+    ) -> Self {
+        Self {
+            tx_hash: receipt.transaction_hash,
+            sender: receipt.from,
+            block: receipt.block_number.unwrap_or_default().as_u64(),
+            recorded_at,
+            loans,
+            gohm_received: gohm_received(receipt),
+            gas_paid: gas_paid(receipt),
+            gohm_price,
+            eth_price,
+        }
+    }
+
+    pub fn reward_usd(&self) -> f64 {
+        to_ether(self.gohm_received) * self.gohm_price
+    }
+
+    pub fn gas_usd(&self) -> f64 {
+        to_ether(self.gas_paid) * self.eth_price
+    }
+}
+
+/// gOHM a receipt transferred to its sender.
+pub fn gohm_received(receipt: &TransactionReceipt) -> U256 {
+    let Ok(gohm) = GOHM.parse::<Address>() else {
+        return U256::zero();
+    };
+    receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == gohm)
+        .filter_map(|log| parse_log::<TransferFilter>(log.clone()).ok())
+        .filter(|transfer| transfer.to == receipt.from)
+        .fold(U256::zero(), |acc, transfer| acc + transfer.value)
+}
+
+#[derive(Debug, Default)]
+struct Period {
+    claims: usize,
+    loans: usize,
+    gohm: U256,
+    reward_usd: f64,
+    gas_usd: f64,
+}
+
+/// Prints the realized PnL of the ledger per day, or per week starting on Monday.
+pub fn print_pnl(entries: &[LedgerEntry], weekly: bool) {
+    let mut periods: BTreeMap<u64, Period> = BTreeMap::new();
+    for entry in entries {
+        let start = if weekly {
+            // The epoch was a Thursday.
+            (entry.recorded_at + 3 * DAY) / WEEK * WEEK - 3 * DAY
+        } else {
+            entry.recorded_at / DAY * DAY
+        };
+        let period = periods.entry(start).or_default();
+        period.claims += 1;
+        period.loans += entry.loans.len();
+        period.gohm += entry.gohm_received;
+        period.reward_usd += entry.reward_usd();
+        period.gas_usd += entry.gas_usd();
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL).set_header(vec![
+        if weekly { "Week of" } else { "Day" },
+        "Claims",
+        "Loans",
+        "gOHM",
+        "Rewards",
+        "Gas",
+        "PnL",
+    ]);
+    let mut total = Period::default();
+    for (start, period) in &periods {
+        table.add_row(row(Utc.timestamp_opt(*start as i64, 0).unwrap().format("%Y-%m-%d").to_string(), period));
+        total.claims += period.claims;
+        total.loans += period.loans;
+        total.gohm += period.gohm;
+        total.reward_usd += period.reward_usd;
+        total.gas_usd += period.gas_usd;
+    }
+    table.add_row(row("Total".to_string(), &total));
+
+    println!("Realized PnL of {} mined claims, valued at the prices when they were mined", entries.len());
+    println!("{table}");
+}
+
+fn row(label: String, period: &Period) -> Vec<String> {
+    vec![
+        label,
+        period.claims.to_string(),
+        period.loans.to_string(),
+        Amount(period.gohm).to_string(),
+        format!("{:.2} dollar", period.reward_usd),
+        format!("{:.2} dollar", period.gas_usd),
+        format!("{:+.2} dollar", period.reward_usd - period.gas_usd),
+    ]
+}
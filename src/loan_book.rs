@@ -0,0 +1,368 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::Bound,
+    sync::{Arc, Mutex},
+};
+
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use futures::stream::{self, StreamExt};
+
+use crate::{
+    auction::Auction,
+    bindings::cooler::Loan,
+    config::RewardTier,
+    gas_budget::to_ether,
+    reader::LoanReader,
+    strategy::{fetch_loan, LoanTarget, StrategyError},
+    types::LoanKey,
+};
+
+/// When loans pass their reward period target: the auction and the targets by collateral.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClaimTargets {
+    pub auction: Auction,
+    pub reward_period_target: u64,
+    pub reward_period_tiers: Vec<RewardTier>,
+}
+
+impl ClaimTargets {
+    /// Unix time at which `loan` passes its reward period target, None if it never does.
+    pub fn due_at<M>(&self, loan: &LoanTarget<M>) -> Option<u64> {
+        let target = RewardTier::target_for(&self.reward_period_tiers, loan.collateral, self.reward_period_target);
+        Some(loan.expiry.low_u64() + self.auction.claim_offset(target)?)
+    }
+}
+
+/// Tracked loans keyed by cooler and loan id, so events find their loan without a scan. Loans
+/// with collateral left are also indexed by expiry, so the defaulted ones are a range query
+/// instead of a filter over every loan ever made, and by the time they pass their reward period
+/// target, so what becomes claimable next is the first entry. Loans without collateral, repaid or
+/// claimed, can't change anymore and only their key is kept, so they are not fetched again. The
+/// same goes for loans below `min_collateral`, whose collateral can only shrink.
+#[derive(Debug)]
+pub struct LoanBook<M> {
+    loans: HashMap<LoanKey, LoanTarget<M>>,
+    /// Expiry and key of every loan that still has collateral.
+    by_expiry: BTreeSet<(U256, LoanKey)>,
+    /// Time it passes its reward period target and key of every loan that still has collateral
+    /// and ever does.
+    by_due: BTreeSet<(u64, LoanKey)>,
+    targets: ClaimTargets,
+    /// gOHM collateral below which loans are treated as closed.
+    min_collateral: f64,
+    /// Loans that were closed and pruned.
+    closed: HashSet<LoanKey>,
+    /// Loans that closed since the last `prune`.
+    closing: Vec<LoanKey>,
+    /// Bumped on every change to the loans, so a `LoanFeed` only republishes changed books.
+    revision: u64,
+}
+
+// Derived `Default` would require `M: Default`.
+impl<M> Default for LoanBook<M> {
+    fn default() -> Self {
+        Self {
+            loans: HashMap::new(),
+            by_expiry: BTreeSet::new(),
+            by_due: BTreeSet::new(),
+            targets: ClaimTargets::default(),
+            min_collateral: 0.0,
+            closed: HashSet::new(),
+            closing: vec![],
+            revision: 0,
+        }
+    }
+}
+
+impl<M: Middleware + 'static> LoanBook<M> {
+    pub fn len(&self) -> usize {
+        self.loans.len()
+    }
+
+    /// Whether the loan is tracked or was closed, either way it needs no fetching.
+    pub fn knows(&self, key: &LoanKey) -> bool {
+        self.loans.contains_key(key) || self.closed.contains(key)
+    }
+
+    /// Whether the loan is known to have no collateral left, or too little to track.
+    pub fn is_closed(&self, key: &LoanKey) -> bool {
+        self.closed.contains(key) || self.loans.get(key).is_some_and(|loan| is_dust(loan.collateral, self.min_collateral))
+    }
+
+    /// Treats loans with less than `min_collateral` gOHM as closed from now on. Loans already
+    /// tracked are kept until their next refresh.
+    pub fn set_min_collateral(&mut self, min_collateral: f64) {
+        self.min_collateral = min_collateral;
+    }
+
+    pub fn get(&self, key: &LoanKey) -> Option<&LoanTarget<M>> {
+        self.loans.get(key)
+    }
+
+    /// Every tracked loan, in no particular order. Loans closed since the last `prune` included.
+    pub fn iter(&self) -> impl Iterator<Item = &LoanTarget<M>> {
+        self.loans.values()
+    }
+
+    /// Keys of the closed loans that were pruned.
+    pub fn closed(&self) -> impl Iterator<Item = &LoanKey> {
+        self.closed.iter()
+    }
+
+    /// Loans with collateral left, soonest expiry first.
+    pub fn open(&self) -> impl Iterator<Item = &LoanTarget<M>> {
+        self.by_expiry.iter().map(|(_, key)| &self.loans[key])
+    }
+
+    /// Loans with collateral left that expired before `timestamp`, i.e. the claimable ones.
+    pub fn expired(&self, timestamp: U256) -> impl Iterator<Item = &LoanTarget<M>> {
+        self.by_expiry
+            .range(..(timestamp, (Address::zero(), U256::zero())))
+            .map(|(_, key)| &self.loans[key])
+    }
+
+    /// Loans with collateral left that passed their reward period target by `timestamp`.
+    pub fn due(&self, timestamp: u64) -> impl Iterator<Item = &LoanTarget<M>> {
+        self.by_due
+            .range(..=(timestamp, (Address::repeat_byte(0xff), U256::MAX)))
+            .map(|(_, key)| &self.loans[key])
+    }
+
+    /// First time after `timestamp` an open loan passes its reward period target.
+    pub fn next_due(&self, timestamp: u64) -> Option<u64> {
+        let after = (timestamp, (Address::repeat_byte(0xff), U256::MAX));
+        self.by_due
+            .range((Bound::Excluded(after), Bound::Unbounded))
+            .next()
+            .map(|(at, _)| *at)
+    }
+
+    /// Re-indexes the open loans by due time if `targets` changed.
+    pub fn set_targets(&mut self, targets: ClaimTargets) {
+        if targets == self.targets {
+            return;
+        }
+        self.targets = targets;
+        self.by_due = self
+            .by_expiry
+            .iter()
+            .filter_map(|(_, key)| Some((self.targets.due_at(&self.loans[key])?, *key)))
+            .collect();
+    }
+
+    /// Tracks `loan`, replacing the loan with the same key. Closed loans are only remembered.
+    pub fn insert(&mut self, loan: LoanTarget<M>) {
+        let key = (loan.cooler.address(), loan.loan_id);
+        self.unindex(&key);
+        self.revision += 1;
+        if is_dust(loan.collateral, self.min_collateral) {
+            self.loans.remove(&key);
+            self.closed.insert(key);
+            return;
+        }
+        self.index(&loan);
+        self.loans.insert(key, loan);
+    }
+
+    /// Marks a tracked loan as closed without reading it again, e.g. when a log shows its
+    /// collateral was claimed. It is dropped on the next `prune`.
+    pub fn close(&mut self, key: LoanKey) -> Option<&LoanTarget<M>> {
+        self.unindex(&key);
+        let loan = self.loans.get_mut(&key)?;
+        self.revision += 1;
+        loan.collateral = U256::zero();
+        self.closing.push(key);
+
+        Some(loan)
+    }
+
+    /// Remembers a loan as closed and stops tracking it, e.g. when restoring a snapshot.
+    pub fn forget_closed(&mut self, key: LoanKey) {
+        self.remove(&key);
+        self.closed.insert(key);
+    }
+
+    /// Drops the loans that closed since the last call. Returns how many.
+    pub fn prune(&mut self) -> usize {
+        let mut pruned = 0;
+        for key in std::mem::take(&mut self.closing) {
+            if self.loans.get(&key).is_some_and(|loan| is_dust(loan.collateral, self.min_collateral)) {
+                self.loans.remove(&key);
+                self.closed.insert(key);
+                pruned += 1;
+            }
+        }
+
+        pruned
+    }
+
+    pub fn remove(&mut self, key: &LoanKey) -> Option<LoanTarget<M>> {
+        self.unindex(key);
+        self.revision += 1;
+        self.loans.remove(key)
+    }
+
+    /// Replaces every loan with `loans` and the closed ones with `closed`, keeping the targets
+    /// and `min_collateral`.
+    fn replace(&mut self, loans: impl IntoIterator<Item = LoanTarget<M>>, closed: HashSet<LoanKey>) {
+        self.loans.clear();
+        self.by_expiry.clear();
+        self.by_due.clear();
+        self.closing.clear();
+        self.closed = closed;
+        for loan in loans {
+            self.insert(loan);
+        }
+    }
+
+    /// Re-reads the loan from its cooler through `reader`. None if the loan is not tracked.
+    pub async fn refresh(&mut self, key: LoanKey, reader: &dyn LoanReader) -> Result<Option<&LoanTarget<M>>, StrategyError> {
+        if !self.loans.contains_key(&key) {
+            return Ok(None);
+        }
+        let loan = fetch_loan(reader, key.0, key.1).await?;
+
+        Ok(self.set(key, &loan))
+    }
+
+    /// Re-reads the tracked loans among `keys`, up to `concurrency` at once. Results are in
+    /// the order of `keys`, untracked keys are left out.
+    pub async fn refresh_all(
+        &mut self,
+        keys: Vec<LoanKey>,
+        concurrency: usize,
+        reader: &dyn LoanReader,
+    ) -> Vec<(LoanKey, Result<(), StrategyError>)> {
+        let fetches = keys
+            .into_iter()
+            .filter(|key| self.loans.contains_key(key))
+            .map(|key| async move { (key, fetch_loan(reader, key.0, key.1).await) });
+        let fetched: Vec<_> = stream::iter(fetches).buffered(concurrency).collect().await;
+
+        let mut refreshed = Vec::with_capacity(fetched.len());
+        for (key, loan) in fetched {
+            if let Ok(loan) = &loan {
+                self.set(key, loan);
+            }
+            refreshed.push((key, loan.map(|_| ())));
+        }
+
+        refreshed
+    }
+
+    /// Updates a tracked loan from chain data, moving it in the indexes.
+    fn set(&mut self, key: LoanKey, fetched: &Loan) -> Option<&LoanTarget<M>> {
+        self.unindex(&key);
+        let loan = self.loans.get_mut(&key)?;
+        self.revision += 1;
+        loan.set(fetched);
+        if is_dust(loan.collateral, self.min_collateral) {
+            self.closing.push(key);
+        } else {
+            self.by_expiry.insert((loan.expiry, key));
+            if let Some(at) = self.targets.due_at(loan) {
+                self.by_due.insert((at, key));
+            }
+        }
+
+        Some(loan)
+    }
+
+    fn index(&mut self, loan: &LoanTarget<M>) {
+        let key = (loan.cooler.address(), loan.loan_id);
+        self.by_expiry.insert((loan.expiry, key));
+        if let Some(at) = self.targets.due_at(loan) {
+            self.by_due.insert((at, key));
+        }
+    }
+
+    fn unindex(&mut self, key: &LoanKey) {
+        if let Some(loan) = self.loans.get(key) {
+            self.by_expiry.remove(&(loan.expiry, *key));
+            if let Some(at) = self.targets.due_at(loan) {
+                self.by_due.remove(&(at, *key));
+            }
+        }
+    }
+}
+
+/// Loans of the first tenant's book, mirrored by the other tenants. Only the first one syncs
+/// and follows the loan events, the others price and claim from its copy, a block behind at most.
+#[derive(Debug)]
+pub struct LoanFeed<M> {
+    published: Arc<Mutex<Published<M>>>,
+    leads: bool,
+    /// Book revision last published when leading, feed revision last mirrored otherwise.
+    seen: Option<u64>,
+}
+
+#[derive(Debug)]
+struct Published<M> {
+    revision: u64,
+    loans: Vec<LoanTarget<M>>,
+    closed: HashSet<LoanKey>,
+}
+
+/// A feed to lead, the book publishing to it is the only one syncing.
+impl<M> Default for LoanFeed<M> {
+    fn default() -> Self {
+        Self {
+            published: Arc::new(Mutex::new(Published {
+                revision: 0,
+                loans: vec![],
+                closed: HashSet::new(),
+            })),
+            leads: true,
+            seen: None,
+        }
+    }
+}
+
+impl<M> LoanFeed<M> {
+    /// A handle mirroring what is published to this feed.
+    pub fn follower(&self) -> Self {
+        Self {
+            published: self.published.clone(),
+            leads: false,
+            seen: None,
+        }
+    }
+
+    pub fn leads(&self) -> bool {
+        self.leads
+    }
+}
+
+impl<M: Middleware + 'static> LoanFeed<M> {
+    /// Publishes the loans of `book` if it changed since the last call.
+    pub fn publish(&mut self, book: &LoanBook<M>) {
+        if self.seen == Some(book.revision) {
+            return;
+        }
+        let mut published = self.published.lock().unwrap();
+        published.revision += 1;
+        published.loans = book.iter().cloned().collect();
+        published.closed = book.closed.clone();
+        self.seen = Some(book.revision);
+    }
+
+    /// Replaces the loans of `book` with the published ones if they changed since the last
+    /// call. Returns whether they did.
+    pub fn mirror(&mut self, book: &mut LoanBook<M>) -> bool {
+        let published = self.published.lock().unwrap();
+        if self.seen == Some(published.revision) {
+            return false;
+        }
+        book.replace(published.loans.iter().cloned(), published.closed.clone());
+        self.seen = Some(published.revision);
+        true
+    }
+}
+
+/// Whether `collateral` is nothing or less than `min_collateral` gOHM.
+fn is_dust(collateral: U256, min_collateral: f64) -> bool {
+    collateral.is_zero() || to_ether(collateral) < min_collateral
+}
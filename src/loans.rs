@@ -0,0 +1,98 @@
+use anyhow::Result;
+use comfy_table::{presets::UTF8_FULL, Table};
+use ethers::{providers::Middleware, types::U256};
+use serde_json::json;
+
+use crate::{
+    cli::OutputFormat,
+    gas_budget::to_ether,
+    math::{Amount, Usd},
+    output::print_json,
+    strategy::LiquidationStrategy,
+};
+
+/// Syncs the loans and prints every open one, soonest expiry first, marking the ones the
+/// strategy would claim right now.
+pub async fn run<M: Middleware + 'static>(mut strategy: LiquidationStrategy<M>, output: OutputFormat) -> Result<()> {
+    strategy.set_loans().await?;
+    strategy.sync_head().await;
+    let gohm_price = strategy.oracle.gohm_price().await?;
+    let batch = strategy.claim_batch(gohm_price).await;
+    let timestamp = U256::from(strategy.head_timestamp);
+
+    let loans: Vec<_> = strategy.loans.open().collect();
+    let in_batch = |cooler, loan_id| batch.iter().any(|target| target.cooler.address() == cooler && target.loan_id == loan_id);
+    let defaulted = loans.iter().filter(|loan| loan.is_claimable(timestamp)).count();
+
+    if output == OutputFormat::Json {
+        let loans: Vec<_> = loans
+            .iter()
+            .map(|loan| {
+                let claimable = loan.is_claimable(timestamp);
+                let status = match (claimable, in_batch(loan.cooler.address(), loan.loan_id)) {
+                    (false, _) => "active",
+                    (true, true) => "claim_now",
+                    (true, false) => "defaulted",
+                };
+                json!({
+                    "cooler": loan.cooler.address(),
+                    "loan_id": loan.loan_id.to_string(),
+                    "collateral_gohm": to_ether(loan.collateral),
+                    "expiry": loan.expiry.as_u64(),
+                    "status": status,
+                    "reward_period_target": strategy.config.reward_period_target_for(loan.collateral),
+                    "reward_percentage": claimable.then(|| loan.calc_reward_percentage(&strategy.auction, timestamp).as_u64()),
+                    "reward_usd": claimable.then(|| loan.calc_rewards_in_dollar(&strategy.auction, timestamp, gohm_price).to_f64()),
+                })
+            })
+            .collect();
+        let batch_reward: Usd = batch
+            .iter()
+            .map(|loan| loan.calc_rewards_in_dollar(&strategy.auction, timestamp, gohm_price))
+            .sum();
+        return print_json(&json!({
+            "timestamp": strategy.head_timestamp,
+            "gohm_price": gohm_price.to_f64(),
+            "min_profit": strategy.config.min_profit.to_f64(),
+            "open": loans.len(),
+            "defaulted": defaulted,
+            "claim_now": batch.len(),
+            "claim_now_reward_usd": batch_reward.to_f64(),
+            "loans": loans,
+        }));
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_header(vec!["Cooler", "Loan ID", "Collateral", "Expiry", "Status", "Reward"]);
+    for loan in &loans {
+        let (status, reward) = if !loan.is_claimable(timestamp) {
+            ("active".to_string(), "-".to_string())
+        } else {
+            let status = if in_batch(loan.cooler.address(), loan.loan_id) { "claim now" } else { "defaulted" };
+            (
+                format!("{} ({}%)", status, loan.calc_reward_percentage(&strategy.auction, timestamp)),
+                format!("{} dollar", loan.calc_rewards_in_dollar(&strategy.auction, timestamp, gohm_price)),
+            )
+        };
+        table.add_row(vec![
+            format!("{:?}", loan.cooler.address()),
+            loan.loan_id.to_string(),
+            format!("{} gOHM", Amount(loan.collateral)),
+            loan.expiry.to_string(),
+            status,
+            reward,
+        ]);
+    }
+
+    println!("{}", table);
+    println!(
+        "{} open loans, {} defaulted, {} pass the thresholds",
+        loans.len(),
+        defaulted,
+        batch.len()
+    );
+
+    Ok(())
+}
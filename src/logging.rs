@@ -0,0 +1,148 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tracing::Subscriber;
+use tracing_appender::{non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::{fmt, fmt::MakeWriter, prelude::*, registry::LookupSpan, reload, EnvFilter, Layer, Registry};
+
+use crate::{config::Config, dashboard::UiMode};
+
+/// Name of the log files in `LOG_DIR`, suffixed with the date (and hour) of the period.
+const LOG_FILE_PREFIX: &str = "clearinghouse-bot.log";
+
+/// Filter used without `LOG_FILTER` at the default verbosity: the bot's own events, warnings
+/// of its libraries.
+const DEFAULT_FILTER: &str = "warn,olympusdao_liquidation_bot=info";
+
+/// How log events are written to stdout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// One JSON object per event with its fields flattened, for Loki or Elasticsearch.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format {}, expected text or json", value)),
+        }
+    }
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// When the log file in `LOG_DIR` is rolled over to a new one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    #[default]
+    Daily,
+    Hourly,
+    /// A single file that keeps growing.
+    Never,
+}
+
+impl FromStr for LogRotation {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "daily" => Ok(LogRotation::Daily),
+            "hourly" => Ok(LogRotation::Hourly),
+            "never" => Ok(LogRotation::Never),
+            _ => Err(format!("unknown log rotation {}, expected daily, hourly or never", value)),
+        }
+    }
+}
+
+impl Display for LogRotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogRotation::Daily => write!(f, "daily"),
+            LogRotation::Hourly => write!(f, "hourly"),
+            LogRotation::Never => write!(f, "never"),
+        }
+    }
+}
+
+/// Swaps the filter of the installed subscriber.
+pub type LogHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Installs a tracing subscriber printing to stdout in `log_format`, filtered by `log_filter`
+/// (`RUST_LOG` syntax, e.g. `warn,olympusdao_liquidation_bot::executor=debug`). Colors are
+/// left out with `ui = "headless"`. With
+/// `log_dir` the events are also written to a rolling file there, the returned guard
+/// flushes it and has to live as long as the bot.
+pub fn init(config: &Config) -> Result<(LogHandle, Option<WorkerGuard>)> {
+    let directives = config.log_filter.as_deref().unwrap_or(verbosity_filter(config.verbosity));
+    let (filter, handle) = reload::Layer::new(EnvFilter::try_new(directives)?);
+    let mut layers = vec![layer(config.log_format, std::io::stdout, config.ui != UiMode::Headless)];
+    let mut guard = None;
+    if let Some(dir) = &config.log_dir {
+        let appender = match config.log_rotation {
+            LogRotation::Daily => rolling::daily(dir, LOG_FILE_PREFIX),
+            LogRotation::Hourly => rolling::hourly(dir, LOG_FILE_PREFIX),
+            LogRotation::Never => rolling::never(dir, LOG_FILE_PREFIX),
+        };
+        let (writer, file_guard) = tracing_appender::non_blocking(appender);
+        layers.push(layer(config.log_format, writer, false));
+        guard = Some(file_guard);
+    }
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(layers)
+        .try_init()
+        .map_err(|err| anyhow!("could not install log subscriber: {}", err))?;
+
+    Ok((handle, guard))
+}
+
+/// Filter of a `verbosity`. Claims are printed by the executor whatever the filter, so quiet
+/// leaves them and the warnings; verbose adds the `block summary` debug events.
+fn verbosity_filter(verbosity: i8) -> &'static str {
+    match verbosity {
+        ..=-1 => "warn",
+        0 => DEFAULT_FILTER,
+        1 => "warn,olympusdao_liquidation_bot=debug",
+        _ => "info,olympusdao_liquidation_bot=trace",
+    }
+}
+
+fn layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().flatten_event(true).boxed(),
+    }
+}
+
+/// Replaces the filter directives while the bot is running.
+pub fn set_filter(handle: &LogHandle, directives: &str) -> Result<()> {
+    handle.reload(EnvFilter::try_new(directives)?)?;
+    Ok(())
+}
+
+/// The directives currently in effect.
+pub fn filter(handle: &LogHandle) -> Result<String> {
+    Ok(handle.with_current(|filter| filter.to_string())?)
+}
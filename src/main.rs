@@ -1,110 +1,609 @@
+mod abi_check;
+mod api;
+mod attestation;
+mod auction;
+mod backtest;
 mod bindings;
+mod bundle;
+mod chain_health;
+mod claim;
+mod cli;
+mod collectors;
+mod competition;
+mod config;
+mod control;
+mod dashboard;
+mod discovery;
+mod discord;
+mod email;
+mod executor;
+mod export;
+mod failover;
+mod forecast;
+mod gas;
+mod gas_budget;
+mod hooks;
+mod inspect;
+mod ledger;
+mod loan_book;
+mod loans;
+mod logging;
+mod math;
+mod metrics;
+mod mono_cooler;
+mod nonce;
+mod notify;
+mod oracle;
+mod output;
+mod pagerduty;
+mod pending;
+mod planner;
+mod policy;
+mod polling;
+mod preview;
+mod price_api;
+mod reader;
+mod reconcile;
+mod recorder;
+mod reorg;
+mod revert;
+mod report;
+mod schedule;
+mod signer;
+mod slack;
+mod state_snapshot;
+mod status;
+mod store;
+mod subgraph;
 mod strategy;
+mod swap;
 mod types;
+mod user_op;
 mod utils;
+mod webhook;
+mod whatif;
 
-use std::sync::Arc;
+use std::{
+    io::IsTerminal,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crate::bindings::clearinghouse;
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use api::{ApiState, SharedSnapshot};
+use attestation::SharedAttestation;
+use auction::Auction;
+use math::Amount;
 use artemis_core::{
-    collectors::{block_collector::BlockCollector, log_collector::LogCollector},
+    collectors::{
+        block_collector::{BlockCollector, NewBlock},
+        log_collector::LogCollector,
+    },
     engine::Engine,
-    executors::mempool_executor::MempoolExecutor,
-    types::{CollectorMap, ExecutorMap},
+    executors::mempool_executor::SubmitTxToMempool,
+    types::{Collector, CollectorMap, Executor, ExecutorMap},
 };
 use bindings::cooler_factory;
+use clap::Parser;
+use cli::{Cli, Command};
+use collectors::ChannelCollector;
+use competition::CompetitorCollector;
+use config::Config;
+use dashboard::UiMode;
 use dotenvy::dotenv;
+use executor::{ClaimExecutor, Submission};
+use nonce::NonceTracker;
+use gas_budget::GasTracker;
+use failover::FailoverClient;
+use metrics::RpcMetrics;
 use ethers::{
-    middleware::MiddlewareBuilder,
-    providers::{Provider, Ws},
-    signers::{LocalWallet, Signer},
-    types::Address,
+    middleware::{MiddlewareBuilder, SignerMiddleware},
+    providers::{Http, Middleware, Provider},
+    signers::Signer,
+    types::{Filter, Log},
 };
-use strategy::LiquidationStrategy;
-use tokio;
-use tracing::info;
-use types::{Action, Event};
+use discord::DiscordWebhook;
+use email::EmailNotifier;
+use hooks::Hooks;
+use loan_book::LoanFeed;
+use notify::{Notification, Notifier};
+use pagerduty::PagerDuty;
+use policy::ScriptPolicy;
+use polling::{BlockPoller, LogPoller, ReadTransport, RpcMode};
+use recorder::{Recorder, RecordingCollector};
+use schedule::{ExpiryCollector, ExpirySchedule};
+use signer::BotSigner;
+use slack::SlackWebhook;
+use store::LoanStore;
+use strategy::{InFlightClaims, LiquidationStrategy, SharedClaimAttempts};
+use tokio::sync::broadcast;
+use types::{Action, ClaimOutcome, ControlCommand, Event};
+use user_op::UserOpExecutor;
 use utils::greet;
+use webhook::WebhookNotifier;
+
+type ReaderClient = SignerMiddleware<Provider<FailoverClient<ReadTransport>>, BotSigner>;
+type SignerClient = SignerMiddleware<Provider<FailoverClient<Http>>, BotSigner>;
+type Session = (LiquidationStrategy<ReaderClient>, Arc<SignerClient>);
 
+/// Waits between reconnection attempts, doubling up to the max after each failure.
+const RECONNECT_MIN_BACKOFF: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Failed reconnects in a row after which the read endpoints are reported unreachable.
+const RPC_FAILURES_CRITICAL: u32 = 3;
+
+/// A keeper profile and the parts of it that live across reconnects.
+struct Tenant {
+    name: String,
+    config: Config,
+    /// Loaded once, a keystore would otherwise prompt for its password on every reconnect.
+    wallet: BotSigner,
+    notifier: Notifier,
+    claim_outcomes: broadcast::Sender<ClaimOutcome>,
+    /// Outlives reconnects like the outcomes, the executor and strategy share it.
+    gas_spent: GasTracker,
+    /// Shared by the claims and swaps sent from the wallet, across reconnects.
+    nonces: NonceTracker,
+    /// Failed and abandoned claims, so a reconnect doesn't retry loans given up on.
+    claim_attempts: SharedClaimAttempts,
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    greet();
+    let cli = Cli::parse();
+    let json_output = cli.command.as_ref().is_some_and(Command::json_output);
+    if json_output {
+        output::reserve_stdout()?;
+    }
     dotenv().ok();
 
-    let private_key = std::env::var("PRIVATE_KEY").expect("PRIVATE_KEY must be set");
-    let rpc_provider_read = std::env::var("RPC_PROVIDER_READ").expect("RPC_PROVIDER_READ must be set");
-    let rpc_provider_sign = std::env::var("RPC_PROVIDER_SIGN").expect("RPC_PROVIDER_SIGN must be set");
-    std::env::var("MIN_PROFIT").expect("MIN_PROFIT must be set");
+    let mut config = Config::load_network(cli.network.as_deref())?;
+    config.verbosity += cli.verbose as i8 - cli.quiet as i8;
+    if let Some(Command::Run { from_snapshot: Some(path), .. }) = &cli.command {
+        config.snapshot_path = Some(path.clone());
+    }
+    if config.ui != UiMode::Headless && !json_output {
+        greet();
+    }
+    if let Some(network) = &config.network {
+        println!("[NETWORK] Running against {}", network);
+    }
+    for var in &config.defaulted {
+        println!("[CONFIG] {} is not set, defaulting to 0", var);
+    }
+    discovery::resolve(&mut config).await?;
+    // Flushes the log file on exit.
+    let (log_handle, _log_guard) = logging::init(&config)?;
+    let snapshot = SharedSnapshot::default();
+    let notifier = Notifier::spawn(
+        Hooks::new(&config.hooks),
+        DiscordWebhook::new(&config.discord),
+        SlackWebhook::new(&config.slack),
+        EmailNotifier::new(&config.email)?,
+        PagerDuty::new(&config.pagerduty, "engine"),
+        WebhookNotifier::new(&config.webhook),
+    );
+    let store = config.database_path.as_deref().map(LoanStore::open).transpose()?.map(Arc::new);
+    let rpc_metrics = RpcMetrics::default();
+    // Outlive the engine so outcomes of claims sent before a reconnect still reach the strategy.
+    let mut tenants = vec![];
+    for (name, config) in config.profiles() {
+        let wallet = signer::load(&config).await?;
+        tenants.push(Tenant {
+            gas_spent: GasTracker::load(wallet.address(), store.clone())?,
+            nonces: NonceTracker::default(),
+            claim_attempts: SharedClaimAttempts::default(),
+            wallet,
+            notifier: Notifier::spawn(
+                Hooks::new(&config.hooks),
+                DiscordWebhook::new(&config.discord),
+                SlackWebhook::new(&config.slack),
+                EmailNotifier::new(&config.email)?,
+                PagerDuty::new(&config.pagerduty, &name),
+                WebhookNotifier::new(&config.webhook),
+            ),
+            claim_outcomes: broadcast::channel(16).0,
+            name,
+            config,
+        });
+    }
+    // Claims in flight are shared by the tenants, and like them outlive reconnects.
+    let in_flight = InFlightClaims::default();
+    let mut sessions = connect(&config, &tenants, &snapshot, &in_flight, &store, &rpc_metrics).await?;
 
-    let cooler_facrory_address: Address = std::env::var("COOLER_FACTORY_ADDRESS")
-        .expect("COOLER_FACTORY_ADDRESS must be set")
-        .parse()
-        .unwrap();
-    let clearinghouse_address: Address = std::env::var("CLEARINGHOUSE_ADDRESS")
-        .expect("CLEARINGHOUSE_ADDRESS must be set")
-        .parse()
-        .unwrap();
+    // One-off commands run as the first tenant.
+    let plain = match cli.command.unwrap_or(Command::Run { plain: false, from_snapshot: None }) {
+        Command::Run { plain, .. } => plain || config.ui != UiMode::Auto || !std::io::stdout().is_terminal(),
+        Command::Loans { output } => return loans::run(sessions.remove(0).0, output).await,
+        Command::Status { output } => {
+            let wallets: Vec<_> = sessions
+                .iter()
+                .map(|(strategy, client_signer)| (strategy.tenant.clone(), client_signer.address()))
+                .collect();
+            return status::run(&sessions[0].0, &wallets, output).await;
+        }
+        Command::Whatif => return whatif::run(sessions.remove(0).0).await,
+        Command::Preview => return preview::run(sessions.remove(0).0).await,
+        Command::Inspect { loan } => return inspect::run(sessions.remove(0).0, loan).await,
+        Command::Report { weekly } => return report::run(sessions.remove(0).0, weekly).await,
+        Command::Export { dir } => return export::run(sessions.remove(0).0, &dir).await,
+        Command::Snapshot { path } => return state_snapshot::run(sessions.remove(0).0, &path).await,
+        Command::Backtest {
+            from_block,
+            to_block,
+            step,
+            gas_per_loan,
+        } => return backtest::run(sessions.remove(0).0, from_block, to_block, step, gas_per_loan).await,
+        Command::Replay { recording } => return recorder::replay(sessions.remove(0).0, &recording).await,
+        Command::Claim {
+            cooler,
+            loan_id,
+            loans,
+            force,
+        } => {
+            let loans = cli::claim_targets(&cooler, &loan_id, &loans)?;
+            let (mut strategy, client_signer) = sessions.remove(0);
+            let notifier = strategy.notifier.clone();
+            return claim::run(&mut strategy, client_signer, notifier, &loans, force).await;
+        }
+    };
 
-    let mut engine: Engine<Event, Action> = Engine::default();
+    let strategy = &sessions[0].0;
+    abi_check::warn_on_drift(&strategy.clearinghouses, &strategy.cooler_factory, &notifier).await;
+    if config.account_abstraction.bundler_url.is_none() {
+        for (tenant, (_, client_signer)) in tenants.iter().zip(&sessions) {
+            match nonce::check(&**client_signer, client_signer.address(), &tenant.config.gas).await {
+                Ok(0) => {}
+                Ok(stale) => tenant.notifier.notify(Notification::Error {
+                    message: format!("{} transactions of tenant {} were pending at startup", stale, tenant.name),
+                }),
+                Err(err) => println!("[NONCE] Could not check tenant {}: {}", tenant.name, err),
+            }
+        }
+    }
+
+    let shared_config = Arc::new(config.clone());
+    let attestation = SharedAttestation::default();
+    if config.attestation.enabled {
+        // Signed with the first tenant's wallet, like the one-off commands.
+        tokio::spawn(attestation::publish(
+            tenants[0].wallet.clone(),
+            snapshot.clone(),
+            shared_config.clone(),
+            store.clone(),
+            attestation.clone(),
+            notifier.clone(),
+        ));
+    }
+
+    if let Some(api_listen_addr) = config.api_listen_addr.clone() {
+        let state = ApiState {
+            snapshot: snapshot.clone(),
+            config: shared_config.clone(),
+            metrics: rpc_metrics.clone(),
+            attestation: attestation.clone(),
+        };
+        let notifier = notifier.clone();
+        tokio::spawn(async move {
+            if let Err(err) = api::serve(api_listen_addr, state).await {
+                println!("API server stopped: {}", err);
+                notifier.notify(Notification::Error {
+                    message: format!("API server stopped: {}", err),
+                });
+            }
+        });
+    }
+
+    tokio::spawn(metrics::watch_quota(rpc_metrics.clone(), config.rpc_quota.clone(), notifier.clone()));
 
-    let ws = Ws::connect(rpc_provider_read).await?;
-    let provider_reader = Provider::new(ws);
-    let wallet: LocalWallet = private_key.parse().unwrap();
-    let address = wallet.address();
-    let client_reader = Arc::new(provider_reader.nonce_manager(address).with_signer(wallet.clone()));
+    let (control_commands, _) = broadcast::channel(16);
+    let recorder = config.record_path.as_deref().map(Recorder::open).transpose()?;
 
+    if let Some(control_socket) = config.control_socket.clone() {
+        let snapshot = snapshot.clone();
+        let control_commands = control_commands.clone();
+        let notifier = notifier.clone();
+        tokio::spawn(async move {
+            if let Err(err) = control::serve(control_socket, snapshot, control_commands, log_handle).await {
+                println!("Control socket stopped: {}", err);
+                notifier.notify(Notification::Error {
+                    message: format!("Control socket stopped: {}", err),
+                });
+            }
+        });
+    }
+
+    if !plain {
+        dashboard::start(snapshot.clone())?;
+    }
 
-    let client_signer = Arc::new((Provider::try_from(rpc_provider_sign)?).with_sender(address).with_signer(wallet));
+    // Every session gets a fresh connection and strategies; `sync_state` backfills the loans
+    // from the last checkpoint, so events missed while disconnected are picked up again.
+    let mut next_sessions = Some(sessions);
+    let mut backoff = RECONNECT_MIN_BACKOFF;
+    let mut reconnect_failures = 0;
+    loop {
+        let sessions = match next_sessions.take() {
+            Some(sessions) => sessions,
+            None => match connect(&config, &tenants, &snapshot, &in_flight, &store, &rpc_metrics).await {
+                Ok(sessions) => {
+                    println!("[WS] Reconnected");
+                    reconnect_failures = 0;
+                    notifier.notify(Notification::EngineResumed);
+                    sessions
+                }
+                Err(err) => {
+                    println!("[WS] Reconnect failed: {}, retrying in {:?}", err, backoff);
+                    reconnect_failures += 1;
+                    if reconnect_failures == RPC_FAILURES_CRITICAL {
+                        notifier.notify(Notification::RpcUnreachable {
+                            failures: reconnect_failures,
+                            error: err.to_string(),
+                        });
+                    }
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(RECONNECT_MAX_BACKOFF);
+                    continue;
+                }
+            },
+        };
 
-    let cooler_factory = cooler_factory::CoolerFactory::new(cooler_facrory_address, client_reader.clone());
-    let clearinghouse = clearinghouse::Clearinghouse::new(clearinghouse_address, client_reader.clone());
-    let strategy = LiquidationStrategy::new(client_reader.clone(), clearinghouse, cooler_factory.clone());
+        let started = Instant::now();
+        snapshot.write().await.connected = true;
+        let reason = run_engine(&config, &tenants, sessions, control_commands.clone(), recorder.clone(), !plain).await;
+        snapshot.write().await.connected = false;
+        if started.elapsed() > RECONNECT_MAX_BACKOFF {
+            backoff = RECONNECT_MIN_BACKOFF;
+        }
+
+        println!("[WS] Engine stopped: {}, reconnecting in {:?}", reason, backoff);
+        notifier.notify(Notification::EngineStopped {
+            reason: format!("{}, reconnecting", reason),
+        });
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(RECONNECT_MAX_BACKOFF);
+    }
+}
+
+/// Opens the websocket and signer connections and builds one strategy per tenant on top
+/// of them. All tenants share the read connection; unreachable read endpoints are skipped,
+/// the others are used as failovers.
+async fn connect(
+    config: &Config,
+    tenants: &[Tenant],
+    snapshot: &SharedSnapshot,
+    in_flight: &InFlightClaims,
+    store: &Option<Arc<LoanStore>>,
+    metrics: &RpcMetrics,
+) -> Result<Vec<Session>> {
+    let mut readers = vec![];
+    for url in config.read_endpoints() {
+        match ReadTransport::connect(&url, config.rpc_mode).await {
+            Ok(transport) => readers.push((url, transport)),
+            Err(err) => println!("[RPC] Could not connect to {}: {}", url, err),
+        }
+    }
+    if readers.is_empty() {
+        bail!("none of the read endpoints is reachable");
+    }
+
+    let provider_reader = Provider::new(FailoverClient::new(readers, metrics.clone()));
+    if let Some(chain_id) = config.chain_id {
+        check_chain_id(&provider_reader, chain_id, "read").await?;
+    }
+    let wallet = tenants[0].wallet.clone();
+    // Nothing is sent through the reader, nonces are handed out by each tenant's `NonceTracker`.
+    let client_reader = Arc::new(provider_reader.with_signer(wallet));
+
+    let current = clearinghouse::Clearinghouse::new(config.clearinghouse_address, client_reader.clone());
+    let auction = match Auction::fetch(&current).await {
+        Ok(auction) => {
+            println!("[AUCTION] MAX_REWARD of the clearinghouse is {} gOHM", Amount(auction.max_reward));
+            auction
+        }
+        Err(err) => {
+            println!("[AUCTION] {}, using the default auction parameters", err);
+            Auction::default()
+        }
+    };
+
+    // The first tenant syncs the loans and each later one gets a handle mirroring them.
+    let mut loan_feed = LoanFeed::default();
+    let mut sessions: Vec<Session> = vec![];
+    for tenant in tenants {
+        let signers = tenant
+            .config
+            .sign_endpoints()
+            .into_iter()
+            .map(|url| Ok((url.clone(), url.parse::<Http>()?)))
+            .collect::<Result<Vec<_>>>()?;
+        let wallet = tenant.wallet.clone();
+        let mut transport = FailoverClient::new(signers, metrics.clone());
+        if tenant.config.submission.mode == Submission::Private {
+            let url = &tenant.config.submission.private_rpc_url;
+            transport = transport.with_private(url.clone(), url.parse::<Http>()?);
+        }
+        let provider_signer = Provider::new(transport);
+        if let Some(chain_id) = config.chain_id {
+            check_chain_id(&provider_signer, chain_id, "sign").await?;
+        }
+        let client_signer = Arc::new(provider_signer.with_sender(wallet.address()).with_signer(wallet));
+
+        let cooler_factory = cooler_factory::CoolerFactory::new(config.cooler_factory_address, client_reader.clone());
+        let clearinghouses = config
+            .clearinghouses()
+            .into_iter()
+            .map(|address| clearinghouse::Clearinghouse::new(address, client_reader.clone()))
+            .collect();
+        let policy = tenant.config.policy_script.as_deref().map(ScriptPolicy::load).transpose()?;
+        let follower = loan_feed.follower();
+        let mut strategy = LiquidationStrategy::new(
+            client_reader.clone(),
+            clearinghouses,
+            cooler_factory,
+            tenant.config.clone(),
+            policy,
+            store.clone(),
+            snapshot.clone(),
+            tenant.notifier.clone(),
+        )
+        .with_tenant(tenant.name.clone(), in_flight.clone())
+        .with_loan_feed(std::mem::replace(&mut loan_feed, follower))
+        .with_claim_attempts(tenant.claim_attempts.clone())
+        .with_gas_tracker(tenant.gas_spent.clone())
+        .with_wallet(tenant.wallet.address())
+        .with_auction(auction);
+        // Prices come from the same sources for every tenant, one cache fetches them once.
+        if let Some((lead, _)) = sessions.first() {
+            strategy.oracle = lead.oracle.clone();
+        }
+        sessions.push((strategy, client_signer));
+    }
+
+    Ok(sessions)
+}
+
+/// Fails unless the `kind` endpoints serve `expected`, so a fork profile can't send claims
+/// to mainnet by mistake, or the other way around.
+async fn check_chain_id<M: Middleware>(client: &M, expected: u64, kind: &str) -> Result<()> {
+    let chain_id = client
+        .get_chainid()
+        .await
+        .map_err(|err| anyhow!("could not read the chain id of the {} endpoint: {}", kind, err))?;
+    if chain_id != expected.into() {
+        bail!("the {} endpoint serves chain {}, expected {}", kind, chain_id, expected);
+    }
+    Ok(())
+}
+
+/// Subscribes to the logs matching `filter`, or polls for them with `RPC_MODE=http-poll`.
+fn log_collector(config: &Config, client: &Arc<ReaderClient>, filter: Filter) -> Box<dyn Collector<Log>> {
+    match config.rpc_mode {
+        RpcMode::Ws => Box::new(LogCollector::new(client.clone(), filter)),
+        RpcMode::HttpPoll => Box::new(LogPoller::new(client.clone(), filter, config.poll_interval())),
+    }
+}
+
+/// Runs the engine until one of its tasks stops, e.g. because the websocket dropped and
+/// the subscriptions ended. Returns why it stopped.
+async fn run_engine(
+    config: &Config,
+    tenants: &[Tenant],
+    sessions: Vec<Session>,
+    control_commands: broadcast::Sender<ControlCommand>,
+    recorder: Option<Recorder>,
+    dashboard: bool,
+) -> String {
+    let mut engine: Engine<Event, Action> = Engine::default();
+    let client_reader = sessions[0].0.client.clone();
+    let cooler_factory = sessions[0].0.cooler_factory.clone();
 
     let new_loan_event = cooler_factory.clear_request_filter();
-    let new_loan_collector = LogCollector::new(client_reader.clone(), new_loan_event.filter);
-    let new_loan_collector = CollectorMap::new(Box::new(new_loan_collector), Event::NewLoan);
+    let new_loan_collector = log_collector(config, &client_reader, new_loan_event.filter);
+    let new_loan_collector = CollectorMap::new(new_loan_collector, Event::NewLoan);
 
     let repay_loan_event = cooler_factory.repay_loan_filter();
-    let repay_loan_collector = LogCollector::new(client_reader.clone(), repay_loan_event.filter);
-    let repay_loan_collector = CollectorMap::new(Box::new(repay_loan_collector), Event::RepayLoan);
+    let repay_loan_collector = log_collector(config, &client_reader, repay_loan_event.filter);
+    let repay_loan_collector = CollectorMap::new(repay_loan_collector, Event::RepayLoan);
 
     let extend_loan_event = cooler_factory.extend_loan_filter();
-    let extend_loan_collector = LogCollector::new(client_reader.clone(), extend_loan_event.filter);
-    let extend_loan_collector =
-        CollectorMap::new(Box::new(extend_loan_collector), Event::ExtendLoan);
+    let extend_loan_collector = log_collector(config, &client_reader, extend_loan_event.filter);
+    let extend_loan_collector = CollectorMap::new(extend_loan_collector, Event::ExtendLoan);
 
     let default_loan_event = cooler_factory.default_loan_filter();
-    let default_loan_collector = LogCollector::new(client_reader.clone(), default_loan_event.filter);
-    let default_loan_collector =
-        CollectorMap::new(Box::new(default_loan_collector), Event::DefaultLoan);
+    let default_loan_collector = log_collector(config, &client_reader, default_loan_event.filter);
+    let default_loan_collector = CollectorMap::new(default_loan_collector, Event::DefaultLoan);
 
-    let block_collector = Box::new(BlockCollector::new(client_reader.clone()));
+    let block_collector: Box<dyn Collector<NewBlock>> = match config.rpc_mode {
+        RpcMode::Ws => Box::new(BlockCollector::new(client_reader.clone())),
+        RpcMode::HttpPoll => Box::new(BlockPoller::new(client_reader.clone(), config.poll_interval())),
+    };
     let block_collector = CollectorMap::new(block_collector, Event::NewBlock);
 
-    let executor = Box::new(MempoolExecutor::new(client_signer.clone()));
-    let executor = ExecutorMap::new(executor, |action| match action {
-        Action::SubmitTx(tx) => Some(tx),
-    });
-
-    engine.add_collector(Box::new(repay_loan_collector));
-    engine.add_collector(Box::new(extend_loan_collector));
-    engine.add_collector(Box::new(default_loan_collector));
-    engine.add_collector(Box::new(block_collector));
-    engine.add_collector(Box::new(new_loan_collector));
-    engine.add_strategy(Box::new(strategy));
-    engine.add_executor(Box::new(executor));
-
-    if let Ok(mut set) = engine.run().await {
-        while let Some(res) = set.join_next().await {
-            if res.is_err() {
-                return Result::Err(anyhow::Error::msg(res.err().unwrap()));
-            } else {
-                info!("res: {:?}", res);
+    let control_collector = ChannelCollector::new(control_commands);
+    let control_collector = CollectorMap::new(Box::new(control_collector), Event::Control);
+
+    let expiry_schedule = ExpirySchedule::default();
+    let expiry_collector = ExpiryCollector::new(expiry_schedule.clone());
+    let expiry_collector = CollectorMap::new(Box::new(expiry_collector), Event::ExpiryDue);
+
+    let mut collectors: Vec<Box<dyn Collector<Event>>> = vec![
+        Box::new(repay_loan_collector),
+        Box::new(extend_loan_collector),
+        Box::new(default_loan_collector),
+        Box::new(block_collector),
+        Box::new(new_loan_collector),
+        Box::new(control_collector),
+        Box::new(expiry_collector),
+    ];
+
+    if config.competition.monitor {
+        let clearinghouses = sessions[0].0.clearinghouses.iter().map(|clearinghouse| clearinghouse.address()).collect();
+        let wallets = tenants.iter().map(|tenant| tenant.wallet.address()).collect();
+        let competitor_collector = CompetitorCollector::new(client_reader.clone(), clearinghouses, wallets);
+        let competitor_collector = CollectorMap::new(Box::new(competitor_collector), Event::CompetitorClaim);
+        collectors.push(Box::new(competitor_collector));
+    }
+
+    // Strategies see every event but only act on their own tenant's claim outcomes, and
+    // each executor only sends its own tenant's transactions.
+    for (tenant, (mut strategy, client_signer)) in tenants.iter().zip(sessions) {
+        strategy.dashboard = dashboard;
+        strategy.expiry_schedule = expiry_schedule.clone();
+        let name = tenant.name.clone();
+        let claim_outcome_collector = ChannelCollector::new(tenant.claim_outcomes.clone());
+        let claim_outcome_collector = CollectorMap::new(Box::new(claim_outcome_collector), move |outcome| {
+            Event::ClaimOutcome(name.clone(), outcome)
+        });
+
+        let notifier = tenant.notifier.clone();
+        let claim_outcomes = tenant.claim_outcomes.clone();
+        let executor: Box<dyn Executor<SubmitTxToMempool>> = if config.account_abstraction.bundler_url.is_some() {
+            let wallet = client_signer.signer().clone();
+            match UserOpExecutor::new(client_reader.clone(), &config.account_abstraction, wallet, notifier, claim_outcomes) {
+                Ok(executor) => Box::new(executor),
+                Err(err) => return err.to_string(),
             }
+        } else {
+            Box::new(ClaimExecutor::new(
+                client_signer,
+                &tenant.config,
+                strategy.store.clone(),
+                tenant.gas_spent.clone(),
+                tenant.nonces.clone(),
+                // Shares the strategy's price cache.
+                strategy.oracle.clone(),
+                notifier,
+                claim_outcomes,
+            ))
+        };
+        let name = tenant.name.clone();
+        let executor = ExecutorMap::new(executor, move |action| match action {
+            Action::SubmitTx(tenant, tx) if tenant == name => Some(tx),
+            Action::SubmitTx(..) => None,
+        });
+
+        collectors.push(Box::new(claim_outcome_collector));
+        engine.add_strategy(Box::new(strategy));
+        engine.add_executor(Box::new(executor));
+    }
+
+    for collector in collectors {
+        match &recorder {
+            Some(recorder) => engine.add_collector(Box::new(RecordingCollector::new(collector, recorder.clone()))),
+            None => engine.add_collector(collector),
         }
     }
 
-    Ok(())
+    let mut set = match engine.run().await {
+        Ok(set) => set,
+        Err(err) => return err.to_string(),
+    };
+    // Dropping the set aborts the remaining tasks.
+    match set.join_next().await {
+        Some(Err(err)) => err.to_string(),
+        Some(Ok(())) => "a collector stream ended".to_string(),
+        None => "no tasks running".to_string(),
+    }
 }
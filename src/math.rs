@@ -0,0 +1,300 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Sub},
+    str::FromStr,
+};
+
+use ethers::types::U256;
+use serde::{de, Deserialize, Deserializer};
+
+/// Decimals of `Usd` and `Price`, the same as gOHM and ETH amounts in wei.
+pub const DECIMALS: usize = 18;
+
+fn one() -> U256 {
+    U256::exp10(DECIMALS)
+}
+
+/// Parses a non-negative decimal like "12.50" into an integer scaled by 10^18.
+fn parse_fixed(value: &str) -> Result<U256, String> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !digits(whole) || !digits(fraction) || fraction.len() > DECIMALS {
+        return Err(format!("{} is not a decimal number with up to {} decimals", value, DECIMALS));
+    }
+    let parse = |part: &str| match part {
+        "" => Ok(U256::zero()),
+        part => U256::from_dec_str(part).map_err(|_| format!("{} is too large", value)),
+    };
+    let fraction = parse(&format!("{:0<width$}", fraction, width = DECIMALS))?;
+    parse(whole)?
+        .checked_mul(one())
+        .and_then(|whole| whole.checked_add(fraction))
+        .ok_or_else(|| format!("{} is too large", value))
+}
+
+/// Writes a value scaled by 10^18 with the formatter's precision, `default_precision` decimals
+/// without one, rounded down. A width right-aligns it.
+fn fmt_fixed(value: U256, default_precision: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let precision = f.precision().unwrap_or(default_precision).min(DECIMALS);
+    let whole = value / one();
+    let fixed = match precision {
+        0 => whole.to_string(),
+        _ => {
+            let fraction = (value % one()) / U256::exp10(DECIMALS - precision);
+            format!("{}.{:0>width$}", whole, fraction.as_u64(), width = precision)
+        }
+    };
+    write!(f, "{:>width$}", fixed, width = f.width().unwrap_or(0))
+}
+
+/// Config values may be written as numbers, `12.5`, or as strings, `"12.50"`.
+fn deserialize_fixed<'de, D: Deserializer<'de>, T: FromStr<Err = String>>(deserializer: D) -> Result<T, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Int(u64),
+        Float(f64),
+        Str(String),
+    }
+
+    let value = match Raw::deserialize(deserializer)? {
+        Raw::Int(value) => value.to_string(),
+        Raw::Float(value) => value.to_string(),
+        Raw::Str(value) => value,
+    };
+    value.parse().map_err(de::Error::custom)
+}
+
+/// A dollar amount with 18 decimals, so rewards, gas costs and the profit target compare
+/// without rounding to whole dollars.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Usd(U256);
+
+impl Usd {
+    pub fn zero() -> Self {
+        Self(U256::zero())
+    }
+
+    pub fn from_dollars(dollars: u64) -> Self {
+        Self(U256::from(dollars) * one())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+
+    /// `percent` of the amount, rounded down.
+    pub fn percent(self, percent: u64) -> Self {
+        Self(self.0 * percent / 100)
+    }
+
+    /// The amount times `factor`, to six decimals of the factor. Negative factors are zero.
+    pub fn scale(self, factor: f64) -> Self {
+        Self(self.0 * U256::from((factor.max(0.0) * 1e6).round() as u128) / 1_000_000)
+    }
+
+    /// Approximate value, for display and JSON.
+    pub fn to_f64(self) -> f64 {
+        self.0.as_u128() as f64 / 1e18
+    }
+}
+
+impl Add for Usd {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Usd {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Usd {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl Sum for Usd {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl FromStr for Usd {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        parse_fixed(value.strip_prefix('$').unwrap_or(value)).map(Self)
+    }
+}
+
+impl fmt::Display for Usd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_fixed(self.0, 2, f)
+    }
+}
+
+impl<'de> Deserialize<'de> for Usd {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_fixed(deserializer)
+    }
+}
+
+/// Dollar price of one gOHM or ETH with 18 decimals. Converts between dollars and token
+/// amounts in wei, which have 18 decimals as well.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Price(U256);
+
+impl Price {
+    /// A price from the oracle, to nine decimals as an `f64` doesn't hold more digits
+    /// anyway. Negative and NaN prices are zero.
+    pub fn from_f64(price: f64) -> Self {
+        Self(U256::from((price.max(0.0) * 1e9).round() as u128) * U256::exp10(9))
+    }
+
+    /// Dollar value of `amount` wei of the token.
+    pub fn value(self, amount: U256) -> Usd {
+        Usd(amount * self.0 / one())
+    }
+
+    /// Wei of the token worth `usd`, zero at a zero price.
+    pub fn amount(self, usd: Usd) -> U256 {
+        if self.0.is_zero() {
+            return U256::zero();
+        }
+        usd.0 * one() / self.0
+    }
+
+    /// Approximate price, for display and JSON.
+    pub fn to_f64(self) -> f64 {
+        self.0.as_u128() as f64 / 1e18
+    }
+}
+
+impl FromStr for Price {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        parse_fixed(value.strip_prefix('$').unwrap_or(value)).map(Self)
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_fixed(self.0, 2, f)
+    }
+}
+
+/// A gOHM or ETH amount in wei, displayed in whole tokens: `{}` to four decimals, `{:.2}`
+/// to two. Collateral and balances print as `1.2500 gOHM` instead of raw wei.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Amount(pub U256);
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_fixed(self.0, 4, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ether(value: &str) -> U256 {
+        ethers::utils::parse_ether(value).unwrap()
+    }
+
+    #[test]
+    fn parses_decimals() {
+        assert_eq!("12.50".parse::<Usd>().unwrap(), Usd::from_dollars(25).percent(50));
+        assert_eq!("$12".parse::<Usd>().unwrap(), Usd::from_dollars(12));
+        assert_eq!(".5".parse::<Usd>().unwrap(), Usd::from_dollars(1).percent(50));
+        assert_eq!("0.000000000000000001".parse::<Usd>().unwrap(), Usd(U256::one()));
+        for invalid in ["", ".", "-1", "1.2.3", "12,5", "1e3", "0.0000000000000000001"] {
+            assert!(invalid.parse::<Usd>().is_err(), "{} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn displays_with_precision() {
+        let usd: Usd = "1234.5678".parse().unwrap();
+        assert_eq!(usd.to_string(), "1234.56");
+        assert_eq!(format!("{:.4}", usd), "1234.5678");
+        assert_eq!(format!("{:.0}", usd), "1234");
+        assert_eq!(format!("{:>9.1}", usd), "   1234.5");
+        assert_eq!(Usd::from_dollars(3).to_string(), "3.00");
+        assert_eq!("0.05".parse::<Usd>().unwrap().to_string(), "0.05");
+    }
+
+    #[test]
+    fn deserializes_numbers_and_strings() {
+        #[derive(Deserialize)]
+        struct Config {
+            a: Usd,
+            b: Usd,
+            c: Usd,
+        }
+        let config: Config = toml::from_str("a = 12\nb = 12.5\nc = \"12.50\"").unwrap();
+        assert_eq!(config.a, Usd::from_dollars(12));
+        assert_eq!(config.b, "12.5".parse().unwrap());
+        assert_eq!(config.c, config.b);
+        assert!(toml::from_str::<Config>("a = -1\nb = 0\nc = 0").is_err());
+    }
+
+    #[test]
+    fn converts_between_dollars_and_wei() {
+        let price = Price::from_f64(2000.0);
+        assert_eq!(price.value(ether("1.5")), Usd::from_dollars(3000));
+        assert_eq!(price.value(U256::exp10(13)), "0.02".parse().unwrap());
+        assert_eq!(price.amount(Usd::from_dollars(3000)), ether("1.5"));
+        assert_eq!(Price::default().amount(Usd::from_dollars(1)), U256::zero());
+    }
+
+    #[test]
+    fn keeps_cents_of_small_amounts() {
+        // 150k gas at 10 gwei and $2500 ETH, which truncated to whole dollars was $3.
+        let gas_cost = Price::from_f64(2500.0).value(U256::from(150_000u64) * U256::exp10(10));
+        assert_eq!(gas_cost, "3.75".parse().unwrap());
+        assert!(gas_cost > Usd::from_dollars(3));
+    }
+
+    #[test]
+    fn reads_oracle_prices() {
+        assert_eq!(Price::from_f64(2843.25), "2843.25".parse().unwrap());
+        assert_eq!(Price::from_f64(-1.0), Price::default());
+        assert_eq!(Price::from_f64(f64::NAN), Price::default());
+        assert_eq!(format!("{:.2}", Price::from_f64(2843.25)), "2843.25");
+    }
+
+    #[test]
+    fn displays_token_amounts() {
+        assert_eq!(Amount(ether("1.25")).to_string(), "1.2500");
+        assert_eq!(format!("{:.2}", Amount(ether("0.123456"))), "0.12");
+        assert_eq!(format!("{:.6}", Amount(U256::from(123_456_789_000_000u64))), "0.000123");
+        assert_eq!(format!("{:.0}", Amount(ether("12.9"))), "12");
+        assert_eq!(format!("{:>8}", Amount(U256::zero())), "  0.0000");
+    }
+
+    #[test]
+    fn sums_and_subtracts() {
+        let total: Usd = ["0.10", "0.20", "0.30"].iter().map(|value| value.parse::<Usd>().unwrap()).sum();
+        assert_eq!(total, "0.6".parse().unwrap());
+        assert_eq!(total.saturating_sub(Usd::from_dollars(1)), Usd::zero());
+        assert_eq!(Usd::from_dollars(10) - total, "9.4".parse().unwrap());
+        assert_eq!(Usd::from_dollars(10).percent(15), "1.5".parse().unwrap());
+    }
+}
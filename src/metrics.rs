@@ -0,0 +1,115 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use reqwest::Url;
+
+use crate::{
+    config::RpcQuotaConfig,
+    notify::{Notification, Notifier},
+};
+
+const MONTH: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// Usage is only projected once this much of it has been seen, startup sync is bursty.
+const MIN_PROJECTION_WINDOW: Duration = Duration::from_secs(60 * 60);
+const QUOTA_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// JSON-RPC requests sent since startup, by endpoint host and method. Shared by every
+/// connection, so the counts survive reconnects.
+#[derive(Debug, Clone)]
+pub struct RpcMetrics {
+    counts: Arc<Mutex<BTreeMap<(String, String), u64>>>,
+    started_at: Instant,
+}
+
+impl Default for RpcMetrics {
+    fn default() -> Self {
+        Self {
+            counts: Arc::default(),
+            started_at: Instant::now(),
+        }
+    }
+}
+
+impl RpcMetrics {
+    pub fn record(&self, endpoint: &str, method: &str) {
+        let key = (endpoint_label(endpoint), method.to_string());
+        *self.counts.lock().unwrap().entry(key).or_default() += 1;
+    }
+
+    /// Requests per endpoint host.
+    pub fn by_endpoint(&self) -> BTreeMap<String, u64> {
+        let mut totals = BTreeMap::new();
+        for ((endpoint, _), count) in self.counts.lock().unwrap().iter() {
+            *totals.entry(endpoint.clone()).or_default() += count;
+        }
+        totals
+    }
+
+    /// Requests a month at the rate seen so far, once there is enough history.
+    pub fn projected_monthly(&self, requests: u64) -> Option<u64> {
+        let elapsed = self.started_at.elapsed();
+        if elapsed < MIN_PROJECTION_WINDOW {
+            return None;
+        }
+        Some((requests as f64 * MONTH.as_secs_f64() / elapsed.as_secs_f64()) as u64)
+    }
+
+    /// The counters in the Prometheus text format.
+    pub fn prometheus(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP rpc_requests_total JSON-RPC requests sent, by endpoint host and method.\n");
+        out.push_str("# TYPE rpc_requests_total counter\n");
+        for ((endpoint, method), count) in self.counts.lock().unwrap().iter() {
+            let _ = writeln!(out, "rpc_requests_total{{endpoint=\"{}\",method=\"{}\"}} {}", endpoint, method, count);
+        }
+        out.push_str("# HELP rpc_requests_projected_monthly Requests a month at the current rate, by endpoint host.\n");
+        out.push_str("# TYPE rpc_requests_projected_monthly gauge\n");
+        for (endpoint, requests) in self.by_endpoint() {
+            if let Some(projected) = self.projected_monthly(requests) {
+                let _ = writeln!(out, "rpc_requests_projected_monthly{{endpoint=\"{}\"}} {}", endpoint, projected);
+            }
+        }
+        out
+    }
+}
+
+/// Warns once per endpoint when its projected monthly requests reach `warn_percent` of the quota.
+pub async fn watch_quota(metrics: RpcMetrics, quota: RpcQuotaConfig, notifier: Notifier) {
+    let Some(monthly_requests) = quota.monthly_requests else {
+        return;
+    };
+    let limit = monthly_requests * quota.warn_percent / 100;
+    let mut warned = HashSet::new();
+    loop {
+        tokio::time::sleep(QUOTA_CHECK_INTERVAL).await;
+        for (endpoint, requests) in metrics.by_endpoint() {
+            let Some(projected) = metrics.projected_monthly(requests) else {
+                continue;
+            };
+            if projected < limit || !warned.insert(endpoint.clone()) {
+                continue;
+            }
+            let message = format!(
+                "{} is on track for {} requests this month, {}% of the {} quota",
+                endpoint,
+                projected,
+                projected * 100 / monthly_requests.max(1),
+                monthly_requests
+            );
+            println!("[RPC] {}", message);
+            notifier.notify(Notification::Error { message });
+        }
+    }
+}
+
+/// Endpoint urls often carry the API key, only the host is shown.
+fn endpoint_label(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string())
+}
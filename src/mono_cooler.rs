@@ -0,0 +1,105 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use anyhow::Result;
+use ethers::{
+    abi::AbiDecode,
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+};
+
+use crate::{
+    bindings::mono_cooler::{BatchLiquidateCall, MonoCooler},
+    strategy::query_logs,
+    types::LoanKey,
+};
+
+/// Accounts checked per `computeLiquidity` call.
+const LIQUIDITY_CHUNK_SIZE: usize = 100;
+
+/// Positions in the Cooler V2 contract. Unlike a V1 cooler loan, a V2 position is an account of
+/// the one MonoCooler contract, liquidated with `batchLiquidate` once its LTV passed the
+/// liquidation LTV instead of being claimed after an expiry.
+#[derive(Debug)]
+pub struct MonoCoolerPositions<M> {
+    pub contract: MonoCooler<M>,
+    /// Accounts that ever borrowed, found through the `Borrow` logs.
+    pub accounts: BTreeSet<Address>,
+    /// Block the `Borrow` logs were read up to.
+    pub synced_block: u64,
+}
+
+/// An account past the liquidation LTV.
+#[derive(Debug, Clone, Copy)]
+pub struct Liquidatable {
+    pub account: Address,
+    /// gOHM collateral (wei) the liquidator receives.
+    pub incentive: U256,
+}
+
+impl<M: Middleware + 'static> MonoCoolerPositions<M> {
+    /// Positions of the contract at `address`, discovered from `start_block` on.
+    pub fn new(address: Address, client: Arc<M>, start_block: u64) -> Self {
+        Self {
+            contract: MonoCooler::new(address, client),
+            accounts: BTreeSet::new(),
+            synced_block: start_block.saturating_sub(1),
+        }
+    }
+
+    /// Adds the accounts that borrowed up to `to_block`.
+    pub async fn sync(&mut self, log_block_range: Option<u64>, to_block: u64) -> Result<()> {
+        if to_block <= self.synced_block {
+            return Ok(());
+        }
+        let contract = &self.contract;
+        let borrows = query_logs(log_block_range, self.synced_block + 1, to_block, || contract.borrow_filter()).await?;
+        self.accounts.extend(borrows.into_iter().map(|borrow| borrow.on_behalf_of));
+        self.synced_block = to_block;
+        Ok(())
+    }
+
+    /// Accounts past the liquidation LTV.
+    pub async fn liquidatable(&self) -> Result<Vec<Liquidatable>> {
+        let accounts: Vec<Address> = self.accounts.iter().copied().collect();
+        let mut liquidatable = vec![];
+        for chunk in accounts.chunks(LIQUIDITY_CHUNK_SIZE) {
+            let statuses = self.contract.compute_liquidity(chunk.to_vec()).call().await?;
+            liquidatable.extend(
+                chunk
+                    .iter()
+                    .zip(statuses)
+                    // (collateral, debt, LTV, delegated, past liquidation LTV, past origination LTV, incentive)
+                    .filter(|(_, (_, _, _, _, exceeded_liquidation_ltv, _, _))| *exceeded_liquidation_ltv)
+                    .map(|(account, (_, _, _, _, _, _, incentive))| Liquidatable {
+                        account: *account,
+                        incentive: U256::from(incentive),
+                    }),
+            );
+        }
+        Ok(liquidatable)
+    }
+
+    pub fn liquidate_tx(&self, accounts: &[Address]) -> TypedTransaction {
+        self.contract.batch_liquidate(accounts.to_vec()).tx
+    }
+
+    /// Key the position of `account` is reserved and retried under, next to the V1 loans.
+    pub fn key(&self, account: Address) -> LoanKey {
+        position_key(self.contract.address(), account)
+    }
+}
+
+fn position_key(mono_cooler: Address, account: Address) -> LoanKey {
+    (mono_cooler, U256::from_big_endian(account.as_bytes()))
+}
+
+/// Keys of the positions a `batchLiquidate` transaction liquidates.
+pub fn liquidated_positions(tx: &TypedTransaction) -> Vec<LoanKey> {
+    let Some(mono_cooler) = tx.to().and_then(|to| to.as_address()).copied() else {
+        return vec![];
+    };
+    tx.data()
+        .and_then(|data| BatchLiquidateCall::decode(data).ok())
+        .map(|call| call.accounts.into_iter().map(|account| position_key(mono_cooler, account)).collect())
+        .unwrap_or_default()
+}
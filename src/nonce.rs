@@ -0,0 +1,98 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockNumber, Eip1559TransactionRequest, U256},
+};
+use tracing::{info, warn};
+
+use crate::{config::GasConfig, gas};
+
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Next nonce of a wallet, shared by everything sending from it. Follows the pending count,
+/// but stays ahead of the transactions this process sent, which a lagging endpoint may not
+/// report yet. After a failure it is resynced to the pending count again, so a dropped
+/// transaction doesn't leave a gap every later one queues behind.
+#[derive(Debug, Clone, Default)]
+pub struct NonceTracker {
+    next: Arc<Mutex<Option<U256>>>,
+}
+
+impl NonceTracker {
+    /// Reserves the next nonce of `address`.
+    pub async fn next<M: Middleware>(&self, client: &M, address: Address) -> Result<U256> {
+        let pending = pending_count(client, address).await?;
+        let mut next = self.next.lock().unwrap();
+        let nonce = next.map_or(pending, |next| next.max(pending));
+        *next = Some(nonce + 1);
+
+        Ok(nonce)
+    }
+
+    /// Forgets the nonces handed out, the next one is the pending count again. A nonce the node
+    /// just `rejected` as used is skipped even if the count doesn't reflect it yet.
+    pub async fn resync<M: Middleware>(&self, client: &M, address: Address, rejected: Option<U256>) -> Result<()> {
+        let pending = pending_count(client, address).await?;
+        let next = rejected.map_or(pending, |rejected| pending.max(rejected + 1));
+        info!(?address, %next, "resynced nonce");
+        *self.next.lock().unwrap() = Some(next);
+
+        Ok(())
+    }
+}
+
+/// Node errors caused by a nonce that is already used or taken by a pending transaction.
+pub fn is_nonce_error(err: &str) -> bool {
+    let err = err.to_lowercase();
+    ["nonce too low", "replacement transaction underpriced", "already known", "nonce has already been used"]
+        .iter()
+        .any(|message| err.contains(message))
+}
+
+/// Startup check for transactions a previous run left pending. Every later transaction queues
+/// behind them, so with `cancel_stale_nonces` they are replaced with empty transfers at the
+/// current fees plus `fee_bump_percent`. Returns how many nonces were pending.
+pub async fn check<M: Middleware>(client: &M, address: Address, config: &GasConfig) -> Result<u64> {
+    let latest = client
+        .get_transaction_count(address, Some(BlockNumber::Latest.into()))
+        .await
+        .map_err(|err| anyhow!("{}", err))?;
+    let pending = pending_count(client, address).await?;
+    if pending <= latest {
+        return Ok(0);
+    }
+
+    let stale = (pending - latest).as_u64();
+    warn!(stale, ?address, from = %latest, to = %(pending - 1), "transactions pending from before");
+    if !config.cancel_stale_nonces {
+        return Ok(stale);
+    }
+
+    let fees = gas::fees(client, config, None, TRANSFER_GAS.into()).await?;
+    let bump = U256::from(100 + config.fee_bump_percent);
+    for nonce in latest.as_u64()..pending.as_u64() {
+        let cancel = Eip1559TransactionRequest::new()
+            .from(address)
+            .to(address)
+            .value(0)
+            .nonce(nonce)
+            .gas(TRANSFER_GAS)
+            .max_fee_per_gas(fees.max_fee_per_gas * bump / 100)
+            .max_priority_fee_per_gas(fees.max_priority_fee_per_gas * bump / 100);
+        match client.send_transaction(cancel, None).await {
+            Ok(sent) => info!(nonce, tx_hash = ?*sent, "cancelling stale nonce"),
+            Err(err) => warn!(nonce, %err, "could not cancel stale nonce"),
+        }
+    }
+
+    Ok(stale)
+}
+
+async fn pending_count<M: Middleware>(client: &M, address: Address) -> Result<U256> {
+    client
+        .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+        .await
+        .map_err(|err| anyhow!("could not get the pending nonce: {}", err))
+}
@@ -0,0 +1,243 @@
+use std::time::Instant;
+
+use ethers::types::{Address, H256, U256, U64};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinSet,
+};
+
+use crate::{
+    discord::DiscordWebhook, email::EmailNotifier, hooks::Hooks, pagerduty::PagerDuty, slack::SlackWebhook,
+    webhook::WebhookNotifier,
+};
+
+/// `ClaimFailed` error of a claim that was mined but reverted.
+pub const REVERTED: &str = "transaction reverted";
+
+/// Something the operator may want to hear about. Serialized as JSON for hooks and webhooks.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notification {
+    ClaimSubmitted {
+        tx_hash: H256,
+    },
+    ClaimMined {
+        tx_hash: H256,
+        block_number: Option<U64>,
+        gas_used: Option<U256>,
+    },
+    ClaimFailed {
+        tx_hash: Option<H256>,
+        error: String,
+    },
+    /// A tracked loan passed its expiry at the head, its reward auction started.
+    LoanExpired {
+        cooler: Address,
+        loan_id: U256,
+        collateral_gohm: f64,
+        expiry: u64,
+    },
+    /// A tracked loan of at least `expiry_warning.min_collateral` expires in `expires_in_secs`.
+    LoanExpiring {
+        cooler: Address,
+        loan_id: U256,
+        collateral_gohm: f64,
+        expiry: u64,
+        expires_in_secs: u64,
+    },
+    EnginePaused {
+        reason: String,
+    },
+    /// The engine stopped and is being restarted, claiming is paused until it is.
+    EngineStopped {
+        reason: String,
+    },
+    EngineResumed,
+    /// The read endpoints failed `failures` reconnects in a row.
+    RpcUnreachable {
+        failures: u32,
+        error: String,
+    },
+    LowBalance {
+        wallet: Address,
+        balance_eth: f64,
+        threshold_eth: f64,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// How urgent a notification is, used to route it to a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+    /// The bot can't claim until someone steps in.
+    Critical,
+}
+
+impl Notification {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Notification::ClaimSubmitted { .. } => "claim_submitted",
+            Notification::ClaimMined { .. } => "claim_mined",
+            Notification::ClaimFailed { .. } => "claim_failed",
+            Notification::LoanExpired { .. } => "loan_expired",
+            Notification::LoanExpiring { .. } => "loan_expiring",
+            Notification::EnginePaused { .. } => "engine_paused",
+            Notification::EngineStopped { .. } => "engine_stopped",
+            Notification::EngineResumed => "engine_resumed",
+            Notification::RpcUnreachable { .. } => "rpc_unreachable",
+            Notification::LowBalance { .. } => "low_balance",
+            Notification::Error { .. } => "error",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Notification::ClaimSubmitted { .. } => "Claim submitted",
+            Notification::ClaimMined { .. } => "Claim mined",
+            Notification::ClaimFailed { .. } => "Claim failed",
+            Notification::LoanExpired { .. } => "Loan expired",
+            Notification::LoanExpiring { .. } => "Loan expiring soon",
+            Notification::EnginePaused { .. } => "Engine paused",
+            Notification::EngineStopped { .. } => "Engine stopped",
+            Notification::EngineResumed => "Engine resumed",
+            Notification::RpcUnreachable { .. } => "RPC unreachable",
+            Notification::LowBalance { .. } => "Low wallet balance",
+            Notification::Error { .. } => "Error",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Notification::ClaimSubmitted { .. }
+            | Notification::ClaimMined { .. }
+            | Notification::LoanExpired { .. }
+            | Notification::LoanExpiring { .. }
+            | Notification::EngineResumed => Severity::Info,
+            Notification::EnginePaused { .. } => Severity::Warning,
+            Notification::ClaimFailed { error, .. } if error == REVERTED => Severity::Critical,
+            Notification::ClaimFailed { .. } | Notification::Error { .. } => Severity::Error,
+            Notification::EngineStopped { .. } | Notification::RpcUnreachable { .. } | Notification::LowBalance { .. } => {
+                Severity::Critical
+            }
+        }
+    }
+
+    /// The payload as name/value pairs, without the event type.
+    pub fn fields(&self) -> Vec<(String, String)> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map
+                .into_iter()
+                .filter(|(key, value)| key != "event" && !value.is_null())
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(value) => value,
+                        value => value.to_string(),
+                    };
+                    (key, value)
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+enum Message {
+    Notify(Notification),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Handle to the notification task. Sending never blocks the caller; the task starts the hooks
+/// for each notification in the background, posts it to Slack, emails the critical ones, pages or resolves
+/// PagerDuty incidents, queues it for the outbound webhooks and hands it to the Discord webhook,
+/// which posts bursts as digests.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    sender: mpsc::UnboundedSender<Message>,
+}
+
+impl Notifier {
+    pub fn spawn(
+        hooks: Hooks,
+        mut discord: Option<DiscordWebhook>,
+        slack: Option<SlackWebhook>,
+        email: Option<EmailNotifier>,
+        mut pagerduty: Option<PagerDuty>,
+        webhook: Option<WebhookNotifier>,
+    ) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut hook_runs = JoinSet::new();
+            loop {
+                // Forget the hooks that finished.
+                while hook_runs.try_join_next().is_some() {}
+                let delivery = discord.as_mut().and_then(DiscordWebhook::next_delivery);
+                tokio::select! {
+                    message = receiver.recv() => match message {
+                        Some(Message::Notify(notification)) => {
+                            hooks.spawn(&notification, &mut hook_runs);
+                            if let Some(slack) = &slack {
+                                slack.post(&notification).await;
+                            }
+                            if let Some(email) = email.as_ref().filter(|_| notification.severity() == Severity::Critical) {
+                                email.send(&notification).await;
+                            }
+                            if let Some(pagerduty) = &mut pagerduty {
+                                pagerduty.handle(&notification).await;
+                            }
+                            if let Some(webhook) = &webhook {
+                                webhook.send(&notification);
+                            }
+                            if let Some(discord) = &mut discord {
+                                discord.push(notification);
+                            }
+                        }
+                        Some(Message::Flush(done)) => {
+                            if let Some(discord) = &mut discord {
+                                discord.deliver().await;
+                            }
+                            while hook_runs.join_next().await.is_some() {}
+                            let _ = done.send(());
+                        }
+                        None => break,
+                    },
+                    _ = sleep_until(delivery), if delivery.is_some() => {
+                        if let Some(discord) = &mut discord {
+                            discord.deliver().await;
+                        }
+                    }
+                }
+            }
+            if let Some(discord) = &mut discord {
+                discord.deliver().await;
+            }
+            while hook_runs.join_next().await.is_some() {}
+        });
+
+        Self { sender }
+    }
+
+    pub fn notify(&self, notification: Notification) {
+        let _ = self.sender.send(Message::Notify(notification));
+    }
+
+    /// Waits until everything sent before this call has been delivered.
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.sender.send(Message::Flush(done)).is_ok() {
+            let _ = wait.await;
+        }
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    if let Some(deadline) = deadline {
+        tokio::time::sleep_until(deadline.into()).await;
+    }
+}
@@ -0,0 +1,522 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use ethers::{
+    contract::abigen,
+    providers::Middleware,
+    types::{Address, I256},
+};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::{
+    config::{Config, CustomPriceConfig, PriceApiConfig, StalePriceConfig},
+    math::Price,
+    price_api::PriceApi,
+    utils::get_sys_time_in_secs,
+};
+
+abigen!(
+    AggregatorV3,
+    r#"[
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound)
+    ]"#
+);
+
+abigen!(
+    GOhm,
+    r#"[
+        function index() external view returns (uint256)
+    ]"#
+);
+
+const ETH_USD_FEED: &str = "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419";
+const OHM_ETH_FEED: &str = "0x9a72298ae3886221820B1c878d12D872087D3a23";
+pub const GOHM: &str = "0x0ab87046fBb341D058F17CBC4c1133F25a20a52f";
+/// CoinGecko ids of the tokens, which every source is asked by.
+pub const GOHM_ID: &str = "governance-ohm";
+pub const ETH_ID: &str = "ethereum";
+/// Feeds older than this are treated as down. OHM/ETH has a 24h heartbeat.
+const MAX_FEED_AGE: u64 = 25 * 60 * 60;
+/// Cached prices older than this many TTLs are not served anymore, the caller waits for a fresh one.
+const MAX_STALE_TTLS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    #[default]
+    DefiLlama,
+    Chainlink,
+    CoinGecko,
+    /// A JSON endpoint of our own, see `CustomPriceConfig`.
+    Custom,
+    /// DefiLlama, CoinGecko and Chainlink at once, see `PriceAggregator`.
+    Median,
+}
+
+/// Sources queried by `PriceSource::Median`.
+const MEDIAN_SOURCES: [PriceSource; 3] = [PriceSource::DefiLlama, PriceSource::CoinGecko, PriceSource::Chainlink];
+
+impl FromStr for PriceSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "defillama" => Ok(PriceSource::DefiLlama),
+            "chainlink" => Ok(PriceSource::Chainlink),
+            "coingecko" => Ok(PriceSource::CoinGecko),
+            "custom" => Ok(PriceSource::Custom),
+            "median" => Ok(PriceSource::Median),
+            _ => Err(format!(
+                "unknown price source {}, expected defillama, chainlink, coingecko, custom or median",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PriceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceSource::DefiLlama => write!(f, "DefiLlama"),
+            PriceSource::Chainlink => write!(f, "Chainlink"),
+            PriceSource::CoinGecko => write!(f, "CoinGecko"),
+            PriceSource::Custom => write!(f, "custom"),
+            PriceSource::Median => write!(f, "median"),
+        }
+    }
+}
+
+/// Combines the quotes of several sources into one price: the median of the quotes within
+/// `max_deviation_percent` of the median of all quotes. At least two quotes have to agree,
+/// so a single wrong source can neither move the price nor be the only one left.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceAggregator {
+    pub max_deviation_percent: u64,
+}
+
+impl PriceAggregator {
+    pub fn aggregate(&self, token: &str, quotes: &[(PriceSource, f64)]) -> Result<f64> {
+        let Some(center) = median(quotes.iter().map(|(_, price)| *price).collect()) else {
+            bail!("no source returned a {} price", token);
+        };
+        let max_deviation = center * self.max_deviation_percent as f64 / 100.0;
+        let (agreeing, outliers): (Vec<_>, Vec<_>) =
+            quotes.iter().partition(|(_, price)| (price - center).abs() <= max_deviation);
+        for (source, price) in &outliers {
+            warn!(
+                %token,
+                %source,
+                price,
+                median = center,
+                max_deviation_percent = self.max_deviation_percent,
+                "ignoring outlier price"
+            );
+        }
+        if agreeing.len() < 2 {
+            bail!("fewer than two sources agree on the {} price", token);
+        }
+
+        Ok(median(agreeing.iter().map(|(_, price)| *price).collect()).unwrap_or(center))
+    }
+}
+
+fn median(mut prices: Vec<f64>) -> Option<f64> {
+    if prices.is_empty() {
+        return None;
+    }
+    prices.sort_by(f64::total_cmp);
+    let middle = prices.len() / 2;
+    Some(if prices.len().is_multiple_of(2) {
+        (prices[middle - 1] + prices[middle]) / 2.0
+    } else {
+        prices[middle]
+    })
+}
+
+/// What to do when the only price available is older than `stale_price.max_age_secs`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StalePricePolicy {
+    /// Fail the lookup, so no claims go out until a fresh price arrives.
+    #[default]
+    Pause,
+    /// Use the stale price moved against us by `haircut_percent`: gOHM lower, ETH higher.
+    Haircut,
+    /// Use the stale price as is and log a warning.
+    Warn,
+}
+
+impl FromStr for StalePricePolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "pause" => Ok(StalePricePolicy::Pause),
+            "haircut" => Ok(StalePricePolicy::Haircut),
+            "warn" => Ok(StalePricePolicy::Warn),
+            _ => Err(format!("unknown stale price policy {}, expected pause, haircut or warn", value)),
+        }
+    }
+}
+
+impl fmt::Display for StalePricePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StalePricePolicy::Pause => write!(f, "pause"),
+            StalePricePolicy::Haircut => write!(f, "haircut"),
+            StalePricePolicy::Warn => write!(f, "warn"),
+        }
+    }
+}
+
+/// Dollar prices of tokens, by their CoinGecko id.
+#[async_trait]
+pub trait PriceOracle: Debug + Send + Sync {
+    async fn price_usd(&self, token: &str) -> Result<Price>;
+
+    async fn gohm_price(&self) -> Result<Price> {
+        self.price_usd(GOHM_ID).await
+    }
+
+    async fn eth_price(&self) -> Result<Price> {
+        self.price_usd(ETH_ID).await
+    }
+}
+
+/// Current prices from the DefiLlama API.
+#[derive(Debug, Clone)]
+pub struct DefiLlama {
+    api: PriceApi,
+}
+
+impl DefiLlama {
+    pub fn new(config: &PriceApiConfig) -> Self {
+        Self {
+            api: PriceApi::new("DefiLlama", config),
+        }
+    }
+
+    /// Price of `token` at a past unix `timestamp`.
+    pub async fn price_at(&self, token: &str, timestamp: u64) -> Result<Price> {
+        let url = format!("https://coins.llama.fi/prices/historical/{}/coingecko:{}", timestamp, token);
+        let payload = self.api.get_json(&url).await?;
+        let price = payload["coins"][format!("coingecko:{}", token)]["price"]
+            .as_f64()
+            .ok_or_else(|| anyhow!("no {} price at {} in DefiLlama response", token, timestamp))?;
+        Ok(Price::from_f64(price))
+    }
+}
+
+#[async_trait]
+impl PriceOracle for DefiLlama {
+    async fn price_usd(&self, token: &str) -> Result<Price> {
+        let url = format!("https://coins.llama.fi/prices/current/coingecko:{}", token);
+        let payload = self.api.get_json(&url).await?;
+        let price = payload["coins"][format!("coingecko:{}", token)]["price"]
+            .as_f64()
+            .ok_or_else(|| anyhow!("no {} price in DefiLlama response", token))?;
+        Ok(Price::from_f64(price))
+    }
+}
+
+/// Prices from the CoinGecko API.
+#[derive(Debug, Clone)]
+pub struct CoinGecko {
+    api: PriceApi,
+}
+
+impl CoinGecko {
+    pub fn new(config: &PriceApiConfig) -> Self {
+        Self {
+            api: PriceApi::new("CoinGecko", config),
+        }
+    }
+}
+
+#[async_trait]
+impl PriceOracle for CoinGecko {
+    async fn price_usd(&self, token: &str) -> Result<Price> {
+        let url = format!("https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies=usd", token);
+        let payload = self.api.get_json(&url).await?;
+        let price = payload[token]["usd"]
+            .as_f64()
+            .ok_or_else(|| anyhow!("no {} price in CoinGecko response", token))?;
+        Ok(Price::from_f64(price))
+    }
+}
+
+/// Prices from a JSON endpoint of our own. `{token}` in the url and the pointer is replaced
+/// by the CoinGecko id of the token.
+#[derive(Debug, Clone)]
+pub struct CustomUrl {
+    api: PriceApi,
+    url: String,
+    pointer: String,
+}
+
+impl CustomUrl {
+    pub fn new(config: &CustomPriceConfig, price_api: &PriceApiConfig) -> Self {
+        Self {
+            api: PriceApi::new("custom price API", price_api),
+            url: config.url.clone(),
+            pointer: config.pointer.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl PriceOracle for CustomUrl {
+    async fn price_usd(&self, token: &str) -> Result<Price> {
+        let payload = self.api.get_json(&self.url.replace("{token}", token)).await?;
+        let pointer = self.pointer.replace("{token}", token);
+        let price = match payload.pointer(&pointer) {
+            Some(serde_json::Value::Number(price)) => price.as_f64(),
+            Some(serde_json::Value::String(price)) => price.parse().ok(),
+            _ => None,
+        };
+        price
+            .map(Price::from_f64)
+            .ok_or_else(|| anyhow!("no {} price at {} in the custom price API response", token, pointer))
+    }
+}
+
+/// Prices from the Chainlink OHM/ETH and ETH/USD feeds, gOHM through its index.
+#[derive(Debug)]
+pub struct Chainlink<M> {
+    client: Arc<M>,
+}
+
+impl<M> Chainlink<M> {
+    pub fn new(client: Arc<M>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> PriceOracle for Chainlink<M> {
+    async fn price_usd(&self, token: &str) -> Result<Price> {
+        let price = match token {
+            ETH_ID => self.feed(ETH_USD_FEED, 8).await?,
+            GOHM_ID => {
+                let ohm_eth = self.feed(OHM_ETH_FEED, 18).await?;
+                let eth_usd = self.feed(ETH_USD_FEED, 8).await?;
+                let index = GOhm::new(GOHM.parse::<Address>()?, self.client.clone()).index().call().await?;
+                ohm_eth * eth_usd * index.as_u128() as f64 / 1e9
+            }
+            _ => bail!("no Chainlink feed for {}", token),
+        };
+        Ok(Price::from_f64(price))
+    }
+}
+
+impl<M: Middleware + 'static> Chainlink<M> {
+    async fn feed(&self, address: &str, decimals: i32) -> Result<f64> {
+        let feed = AggregatorV3::new(address.parse::<Address>()?, self.client.clone());
+        let (_, answer, _, updated_at, _) = feed.latest_round_data().call().await?;
+        if answer <= I256::zero() {
+            bail!("feed {} returned {}", address, answer);
+        }
+        if get_sys_time_in_secs().saturating_sub(updated_at.as_u64()) > MAX_FEED_AGE {
+            bail!("feed {} is stale, last update at {}", address, updated_at);
+        }
+
+        Ok(answer.into_raw().as_u128() as f64 / 10f64.powi(decimals))
+    }
+}
+
+/// The oracle of a single source, reading the on-chain feeds through `client`.
+pub fn source<M: Middleware + 'static>(kind: PriceSource, client: Arc<M>, config: &Config) -> Arc<dyn PriceOracle> {
+    match kind {
+        PriceSource::DefiLlama => Arc::new(DefiLlama::new(&config.price_api)),
+        PriceSource::CoinGecko => Arc::new(CoinGecko::new(&config.price_api)),
+        PriceSource::Custom => Arc::new(CustomUrl::new(&config.custom_price, &config.price_api)),
+        PriceSource::Chainlink => Arc::new(Chainlink::new(client)),
+        PriceSource::Median => Arc::new(Median {
+            sources: MEDIAN_SOURCES.map(|kind| (kind, source(kind, client.clone(), config))).to_vec(),
+            aggregator: PriceAggregator {
+                max_deviation_percent: config.price_max_deviation_percent(),
+            },
+        }),
+    }
+}
+
+/// Queries every source concurrently and aggregates the ones that answered.
+#[derive(Debug)]
+pub struct Median {
+    sources: Vec<(PriceSource, Arc<dyn PriceOracle>)>,
+    aggregator: PriceAggregator,
+}
+
+#[async_trait]
+impl PriceOracle for Median {
+    async fn price_usd(&self, token: &str) -> Result<Price> {
+        let results = futures::future::join_all(self.sources.iter().map(|(_, oracle)| oracle.price_usd(token))).await;
+        let mut quotes = vec![];
+        for ((source, _), result) in self.sources.iter().zip(results) {
+            match result {
+                Ok(price) => quotes.push((*source, price.to_f64())),
+                Err(err) => warn!(%source, %token, %err, "price source failed"),
+            }
+        }
+        self.aggregator.aggregate(token, &quotes).map(Price::from_f64)
+    }
+}
+
+/// Dollar prices of gOHM and ETH. Asks the configured source first and falls back to
+/// another one when it fails. `PriceSource::Median` asks all of them at once instead.
+///
+/// Prices are cached for `ttl`. An expired price is still returned while a fresh one is fetched
+/// in the background, so callers only wait on a fetch when there is no usable price at all.
+/// When both sources fail, a cached price past `stale.max_age_secs` is handled by `stale.policy`.
+#[derive(Debug, Clone)]
+pub struct CachedOracle {
+    /// Asked in order until one answers.
+    sources: Vec<(PriceSource, Arc<dyn PriceOracle>)>,
+    ttl: Duration,
+    stale: StalePriceConfig,
+    cache: Arc<Mutex<HashMap<String, CachedPrice>>>,
+}
+
+#[derive(Debug)]
+struct CachedPrice {
+    price: Price,
+    fetched_at: Instant,
+    refreshing: bool,
+}
+
+impl CachedOracle {
+    pub fn new(sources: Vec<(PriceSource, Arc<dyn PriceOracle>)>, ttl: Duration, stale: StalePriceConfig) -> Self {
+        Self {
+            sources,
+            ttl,
+            stale,
+            cache: Arc::default(),
+        }
+    }
+
+    /// The oracle configured by `price_source` and its settings.
+    pub fn from_config<M: Middleware + 'static>(client: Arc<M>, config: &Config) -> Self {
+        let sources = match config.price_source {
+            PriceSource::DefiLlama => vec![PriceSource::DefiLlama, PriceSource::Chainlink],
+            PriceSource::Median => vec![PriceSource::Median],
+            primary => vec![primary, PriceSource::DefiLlama],
+        };
+        let sources = sources
+            .into_iter()
+            .map(|kind| (kind, source(kind, client.clone(), config)))
+            .collect();
+        Self::new(sources, config.price_cache_ttl(), config.stale_price.clone())
+    }
+
+    async fn cached_price(&self, token: &str) -> Result<Price> {
+        let cached = match self.cache.lock().unwrap().get_mut(token) {
+            Some(entry) => {
+                let age = entry.fetched_at.elapsed();
+                if age < self.ttl {
+                    return Ok(entry.price);
+                }
+                let in_background = age < self.ttl * MAX_STALE_TTLS;
+                if in_background && !entry.refreshing {
+                    entry.refreshing = true;
+                    let oracle = self.clone();
+                    let token = token.to_string();
+                    tokio::spawn(async move { oracle.refresh(&token).await });
+                }
+                Some((entry.price, age, in_background))
+            }
+            None => None,
+        };
+
+        let (price, age) = match cached {
+            Some((price, age, true)) => (price, age),
+            _ => match self.price(token).await {
+                Ok(price) => {
+                    self.store(token, price);
+                    return Ok(price);
+                }
+                Err(err) => match cached {
+                    Some((price, age, _)) => {
+                        warn!(%token, %err, "no fresh price, using the cached one");
+                        (price, age)
+                    }
+                    None => return Err(err),
+                },
+            },
+        };
+        self.stale_price(token, price, age)
+    }
+
+    /// Applies the stale price policy to a cached price of the given age.
+    fn stale_price(&self, token: &str, price: Price, age: Duration) -> Result<Price> {
+        if age.as_secs() <= self.stale.max_age_secs {
+            return Ok(price);
+        }
+        match self.stale.policy {
+            StalePricePolicy::Pause => bail!("{} price is {}s old, waiting for a fresh one", token, age.as_secs()),
+            StalePricePolicy::Haircut => {
+                let haircut = self.stale.haircut_percent as f64 / 100.0;
+                let adjusted = match token {
+                    ETH_ID => price.to_f64() * (1.0 + haircut),
+                    _ => price.to_f64() * (1.0 - haircut),
+                };
+                let adjusted = Price::from_f64(adjusted);
+                warn!(%token, age_secs = age.as_secs(), %adjusted, %price, "stale price, applying the haircut");
+                Ok(adjusted)
+            }
+            StalePricePolicy::Warn => {
+                warn!(%token, age_secs = age.as_secs(), "stale price, using it anyway");
+                Ok(price)
+            }
+        }
+    }
+
+    async fn refresh(&self, token: &str) {
+        match self.price(token).await {
+            Ok(price) => self.store(token, price),
+            Err(err) => {
+                warn!(%token, %err, "background price refresh failed");
+                if let Some(entry) = self.cache.lock().unwrap().get_mut(token) {
+                    entry.refreshing = false;
+                }
+            }
+        }
+    }
+
+    fn store(&self, token: &str, price: Price) {
+        let entry = CachedPrice {
+            price,
+            fetched_at: Instant::now(),
+            refreshing: false,
+        };
+        self.cache.lock().unwrap().insert(token.to_string(), entry);
+    }
+
+    async fn price(&self, token: &str) -> Result<Price> {
+        let mut sources = self.sources.iter().peekable();
+        while let Some((source, oracle)) = sources.next() {
+            match oracle.price_usd(token).await {
+                Ok(price) => return Ok(price),
+                Err(err) => match sources.peek() {
+                    Some((fallback, _)) => warn!(%source, %token, %err, %fallback, "price source failed, trying the next one"),
+                    None => return Err(err),
+                },
+            }
+        }
+        bail!("no price source configured")
+    }
+}
+
+#[async_trait]
+impl PriceOracle for CachedOracle {
+    async fn price_usd(&self, token: &str) -> Result<Price> {
+        self.cached_price(token).await
+    }
+}
@@ -0,0 +1,38 @@
+use std::{
+    fs::File,
+    io::Write,
+    os::fd::FromRawFd,
+    sync::{Mutex, OnceLock},
+};
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+/// The real stdout once `reserve_stdout` moved everything else to stderr.
+static STDOUT: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Points stdout at stderr and keeps the real stdout for `print_json`, so the log lines and
+/// messages printed on the way don't end up in the JSON a script reads.
+pub fn reserve_stdout() -> Result<()> {
+    // SAFETY: plain fd juggling on fds we own; the file takes ownership of the duplicate.
+    let stdout = unsafe {
+        let stdout = libc::dup(libc::STDOUT_FILENO);
+        if stdout < 0 || libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+            bail!("could not redirect stdout: {}", std::io::Error::last_os_error());
+        }
+        File::from_raw_fd(stdout)
+    };
+    let _ = STDOUT.set(Mutex::new(stdout));
+
+    Ok(())
+}
+
+/// Writes `value` as one line of JSON to the reserved stdout, or stdout if none was reserved.
+pub fn print_json(value: &Value) -> Result<()> {
+    match STDOUT.get() {
+        Some(stdout) => writeln!(stdout.lock().unwrap(), "{}", value)?,
+        None => println!("{}", value),
+    }
+
+    Ok(())
+}
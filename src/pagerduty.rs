@@ -0,0 +1,125 @@
+use std::{collections::HashSet, time::Duration};
+
+use reqwest::Client;
+use serde_json::{json, Map, Value};
+
+use crate::{config::PagerDutyConfig, notify::Notification};
+
+const EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+/// Events are awaited by the notification task, so a hung request can't hold up the next page longer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Incidents, also the suffix of their dedup keys.
+const ENGINE_STOPPED: &str = "engine_stopped";
+const RPC_UNREACHABLE: &str = "rpc_unreachable";
+const CLAIM_FAILURES: &str = "claim_failures";
+
+/// Triggers PagerDuty incidents through the Events API v2 for conditions the bot can't get out
+/// of by itself, and resolves them once it did: the engine stopping or losing its read
+/// endpoints until it resumes, and `claim_failures` failed claims in a row until one is mined.
+pub struct PagerDuty {
+    client: Client,
+    routing_key: String,
+    source: String,
+    claim_failures: u32,
+    /// Claims failed since the last mined one.
+    failed_claims: u32,
+    /// Incidents triggered and not resolved yet.
+    open: HashSet<&'static str>,
+}
+
+impl PagerDuty {
+    /// None without `routing_key`. `source` tells the incidents of the engine and each tenant apart.
+    pub fn new(config: &PagerDutyConfig, source: &str) -> Option<Self> {
+        Some(Self {
+            client: Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            routing_key: config.routing_key.clone()?,
+            source: format!("olympusdao-clearinghouse-bot/{}", source),
+            claim_failures: config.claim_failures,
+            failed_claims: 0,
+            open: HashSet::new(),
+        })
+    }
+
+    pub async fn handle(&mut self, notification: &Notification) {
+        match notification {
+            Notification::EngineStopped { reason } => {
+                self.trigger(ENGINE_STOPPED, format!("Engine stopped: {}", reason), notification).await;
+            }
+            Notification::RpcUnreachable { failures, error } => {
+                let summary = format!("RPC unreachable after {} reconnects: {}", failures, error);
+                self.trigger(RPC_UNREACHABLE, summary, notification).await;
+            }
+            Notification::ClaimFailed { error, .. } => {
+                self.failed_claims += 1;
+                if self.failed_claims == self.claim_failures {
+                    let summary = format!("{} claims failed in a row, last: {}", self.failed_claims, error);
+                    self.trigger(CLAIM_FAILURES, summary, notification).await;
+                }
+            }
+            Notification::ClaimMined { .. } => {
+                self.failed_claims = 0;
+                self.resolve(CLAIM_FAILURES).await;
+            }
+            Notification::EngineResumed => {
+                self.resolve(ENGINE_STOPPED).await;
+                self.resolve(RPC_UNREACHABLE).await;
+            }
+            _ => {}
+        }
+    }
+
+    async fn trigger(&mut self, incident: &'static str, summary: String, notification: &Notification) {
+        let details: Map<String, Value> =
+            notification.fields().into_iter().map(|(name, value)| (name, Value::String(value))).collect();
+        let event = json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            "dedup_key": self.dedup_key(incident),
+            "payload": {
+                // The API rejects longer summaries.
+                "summary": summary.chars().take(1024).collect::<String>(),
+                "source": self.source,
+                "severity": "critical",
+                "custom_details": details,
+            },
+        });
+        if self.send(incident, event).await {
+            self.open.insert(incident);
+        }
+    }
+
+    /// Resolves `incident` if it was triggered.
+    async fn resolve(&mut self, incident: &'static str) {
+        if !self.open.contains(incident) {
+            return;
+        }
+        let event = json!({
+            "routing_key": self.routing_key,
+            "event_action": "resolve",
+            "dedup_key": self.dedup_key(incident),
+        });
+        if self.send(incident, event).await {
+            self.open.remove(incident);
+        }
+    }
+
+    fn dedup_key(&self, incident: &str) -> String {
+        format!("{}/{}", self.source, incident)
+    }
+
+    async fn send(&self, incident: &str, event: Value) -> bool {
+        let result = self
+            .client
+            .post(EVENTS_URL)
+            .json(&event)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(err) = &result {
+            let action = event["event_action"].as_str().unwrap_or_default();
+            println!("[PAGERDUTY] Failed to {} {}: {}", action, incident, err);
+        }
+        result.is_ok()
+    }
+}
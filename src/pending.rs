@@ -0,0 +1,179 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Eip1559TransactionRequest, TransactionReceipt, H256,
+        U256, U64,
+    },
+    utils::parse_units,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::GasConfig,
+    gas::{self, Fees},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(12);
+const TRANSFER_GAS: u64 = 21_000;
+/// Failed checks in a row after which a claim is given up on.
+pub const MAX_POLL_FAILURES: u32 = 5;
+
+/// How a submitted claim ended.
+#[derive(Debug)]
+pub enum Resolution {
+    /// The claim or one of its replacements was included. It can still have reverted.
+    Mined(TransactionReceipt),
+    /// The fee ceiling was reached and the nonce was freed with an empty transfer.
+    Cancelled(TransactionReceipt),
+    /// The nonce was used by a transaction we don't know about.
+    NonceTaken,
+    /// The node forgot the transaction and escalation is disabled.
+    Dropped,
+}
+
+/// Follows a sent claim until it is resolved. When it isn't mined within
+/// `stuck_after_blocks`, it is rebroadcast with the same nonce and fees bumped by
+/// `fee_bump_percent`, until the next bump would exceed `max_fee_gwei`. From there it is
+/// either cancelled or left waiting, depending on `cancel_at_ceiling`. A failed RPC request
+/// is retried on the next poll, only `MAX_POLL_FAILURES` in a row give up on the claim.
+pub async fn watch<M: Middleware>(
+    client: Arc<M>,
+    gas: GasConfig,
+    tx: TypedTransaction,
+    tx_hash: H256,
+) -> Result<Resolution> {
+    let mut watch = Watch {
+        from: *tx.from().ok_or_else(|| anyhow!("claim has no sender"))?,
+        nonce: *tx.nonce().ok_or_else(|| anyhow!("claim has no nonce"))?,
+        ceiling: parse_units(gas.max_fee_gwei, "gwei")?.into(),
+        tx,
+        tx_hash,
+        hashes: vec![tx_hash],
+        cancel_hash: None,
+        nonce_taken: false,
+        last_sent: block_number(&*client).await.ok(),
+    };
+    let mut failures = 0;
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        match watch.poll(&*client, &gas).await {
+            Ok(Some(resolution)) => return Ok(resolution),
+            Ok(None) => failures = 0,
+            Err(err) => {
+                failures += 1;
+                if failures == MAX_POLL_FAILURES {
+                    return Err(err.context(format!("claim unresolved after {} failed checks", failures)));
+                }
+                warn!(%err, failures, "could not check the pending claim, retrying");
+            }
+        }
+    }
+}
+
+/// A claim being followed and the replacements sent for it.
+struct Watch {
+    from: Address,
+    nonce: U256,
+    ceiling: U256,
+    tx: TypedTransaction,
+    tx_hash: H256,
+    hashes: Vec<H256>,
+    cancel_hash: Option<H256>,
+    nonce_taken: bool,
+    /// Block the last transaction for the nonce went out at, unknown until it could be read.
+    last_sent: Option<U64>,
+}
+
+impl Watch {
+    /// Checks the claim once, replacing or cancelling it when it is stuck. None while it is pending.
+    async fn poll<M: Middleware>(&mut self, client: &M, gas: &GasConfig) -> Result<Option<Resolution>> {
+        for hash in &self.hashes {
+            let receipt = client.get_transaction_receipt(*hash).await.map_err(|err| anyhow!("{}", err))?;
+            if let Some(receipt) = receipt {
+                return Ok(Some(match self.cancel_hash {
+                    Some(cancel_hash) if cancel_hash == *hash => Resolution::Cancelled(receipt),
+                    _ => Resolution::Mined(receipt),
+                }));
+            }
+        }
+
+        // The receipt can lag the nonce by a block, so only give up the second time.
+        let next_nonce = client.get_transaction_count(self.from, None).await.map_err(|err| anyhow!("{}", err))?;
+        debug!(hashes = ?self.hashes, nonce = %self.nonce, %next_nonce, "claim still pending");
+        if next_nonce > self.nonce {
+            if self.nonce_taken {
+                return Ok(Some(Resolution::NonceTaken));
+            }
+            self.nonce_taken = true;
+            return Ok(None);
+        }
+
+        if gas.stuck_after_blocks == 0 {
+            let known = client.get_transaction(self.tx_hash).await.map_err(|err| anyhow!("{}", err))?;
+            return Ok(known.is_none().then_some(Resolution::Dropped));
+        }
+
+        let block = block_number(client).await?;
+        let last_sent = *self.last_sent.get_or_insert(block);
+        debug!(%block, %last_sent, "checking whether the claim is stuck");
+        if self.cancel_hash.is_some() || block < last_sent + gas.stuck_after_blocks {
+            return Ok(None);
+        }
+
+        let fees = bumped(current_fees(&self.tx)?, gas.fee_bump_percent);
+        if fees.max_fee_per_gas <= self.ceiling {
+            info!(
+                blocks = %(block - last_sent),
+                max_fee_per_gas = %fees.max_fee_per_gas,
+                "claim not mined, replacing it"
+            );
+            let tx = gas::with_fees(self.tx.clone(), fees);
+            let hash = *client.send_transaction(tx.clone(), None).await.map_err(|err| anyhow!("{}", err))?;
+            info!(?hash, "replacement submitted");
+            self.tx = tx;
+            self.hashes.push(hash);
+        } else if gas.cancel_at_ceiling {
+            warn!(nonce = %self.nonce, "claim reached the fee ceiling, cancelling it");
+            let cancel = Eip1559TransactionRequest::new()
+                .from(self.from)
+                .to(self.from)
+                .value(0)
+                .nonce(self.nonce)
+                .gas(TRANSFER_GAS)
+                .max_fee_per_gas(fees.max_fee_per_gas)
+                .max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
+            let hash = *client.send_transaction(cancel, None).await.map_err(|err| anyhow!("{}", err))?;
+            info!(?hash, "cancellation submitted");
+            self.cancel_hash = Some(hash);
+            self.hashes.push(hash);
+        } else {
+            return Ok(None);
+        }
+        self.last_sent = Some(block);
+        Ok(None)
+    }
+}
+
+async fn block_number<M: Middleware>(client: &M) -> Result<U64> {
+    client.get_block_number().await.map_err(|err| anyhow!("could not get block number: {}", err))
+}
+
+fn current_fees(tx: &TypedTransaction) -> Result<Fees> {
+    match tx {
+        TypedTransaction::Eip1559(tx) => Ok(Fees {
+            max_fee_per_gas: tx.max_fee_per_gas.unwrap_or_default(),
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas.unwrap_or_default(),
+        }),
+        _ => Err(anyhow!("only EIP-1559 claims can be replaced")),
+    }
+}
+
+fn bumped(fees: Fees, percent: u64) -> Fees {
+    Fees {
+        max_fee_per_gas: fees.max_fee_per_gas * (100 + percent) / 100,
+        max_priority_fee_per_gas: fees.max_priority_fee_per_gas * (100 + percent) / 100,
+    }
+}
@@ -0,0 +1,132 @@
+use std::cmp::Reverse;
+
+use ethers::{providers::Middleware, types::U256};
+
+use crate::{
+    auction::Auction,
+    config::ConsolidationConfig,
+    math::{Price, Usd},
+    strategy::LoanTarget,
+};
+
+/// Intrinsic gas of a transaction, paid once per batch.
+pub const TX_BASE_GAS: u64 = 21_000;
+
+/// Gas cost of a claim batch, split into the part paid once and the part paid per loan.
+#[derive(Debug, Clone, Copy)]
+pub struct GasModel {
+    pub per_loan: U256,
+    pub gas_price: U256,
+    pub eth_price: Price,
+}
+
+impl GasModel {
+    /// Derives the per loan gas from the estimate for a batch of `loans`.
+    pub fn from_estimate(gas_estimate: U256, loans: usize, gas_price: U256, eth_price: Price) -> Self {
+        let per_loan = gas_estimate.saturating_sub(TX_BASE_GAS.into()) / loans.max(1);
+        Self {
+            per_loan,
+            gas_price,
+            eth_price,
+        }
+    }
+
+    /// Gas cost one more loan adds to a batch.
+    pub fn marginal_cost_dollar(&self) -> Usd {
+        self.eth_price.value(self.per_loan * self.gas_price)
+    }
+
+    pub fn cost_dollar(&self, loans: usize) -> Usd {
+        if loans == 0 {
+            return Usd::zero();
+        }
+        self.eth_price.value((U256::from(TX_BASE_GAS) + self.per_loan * loans) * self.gas_price)
+    }
+}
+
+#[derive(Debug)]
+pub enum Plan {
+    ClaimNow,
+    /// Hold the ready loans until `until` and claim them together with `joining` more loans.
+    Wait { until: u64, joining: usize, gain: Usd },
+}
+
+/// Sorts the batch by reward and keeps the loans whose reward pays for the gas they add.
+/// Returns the kept loans and the ones left out.
+pub fn select<M: Middleware + 'static>(
+    mut batch: Vec<LoanTarget<M>>,
+    auction: &Auction,
+    now: u64,
+    gohm_price: Price,
+    gas: GasModel,
+) -> (Vec<LoanTarget<M>>, Vec<LoanTarget<M>>) {
+    let reward = |loan: &LoanTarget<M>| loan.calc_rewards_in_dollar(auction, now.into(), gohm_price);
+    batch.sort_by_key(|loan| Reverse(reward(loan)));
+    let marginal_cost = gas.marginal_cost_dollar();
+    let keep = batch.iter().take_while(|loan| reward(loan) > marginal_cost).count();
+    let left_out = batch.split_off(keep);
+    (batch, left_out)
+}
+
+/// Unix time at which a loan passes the reward period target.
+pub fn ready_at(auction: &Auction, expiry: U256, reward_period_target: u64) -> u64 {
+    expiry.as_u64() + auction.offset_of(reward_period_target) + 1
+}
+
+/// Decides whether claiming `ready` now, and `upcoming` in a second batch once they pass
+/// the target, earns less than waiting and claiming all of them in one batch. Upcoming
+/// loans have to pass the target within the window, and no ready loan may be held past
+/// `max_reward_percent` of its auction, where competitors are likely to take it.
+/// `reward_period_target` gives the target of each loan.
+#[allow(clippy::too_many_arguments)]
+pub fn plan<M: Middleware + 'static>(
+    ready: &[LoanTarget<M>],
+    upcoming: &[&LoanTarget<M>],
+    auction: &Auction,
+    now: u64,
+    gohm_price: Price,
+    gas: GasModel,
+    reward_period_target: impl Fn(&LoanTarget<M>) -> u64,
+    config: &ConsolidationConfig,
+) -> Plan {
+    let upcoming: Vec<&LoanTarget<M>> = upcoming
+        .iter()
+        .copied()
+        .filter(|loan| {
+            let ready_at = ready_at(auction, loan.expiry, reward_period_target(loan));
+            ready_at > now && ready_at <= now + config.window_secs
+        })
+        .collect();
+    let Some(until) = upcoming.iter().map(|loan| ready_at(auction, loan.expiry, reward_period_target(loan))).max() else {
+        return Plan::ClaimNow;
+    };
+    let too_risky = ready
+        .iter()
+        .any(|loan| auction.reward_percentage(loan.expiry, until.into()) > config.max_reward_percent.into());
+    if too_risky {
+        return Plan::ClaimNow;
+    }
+
+    let reward = |loan: &LoanTarget<M>, timestamp: u64| loan.calc_rewards_in_dollar(auction, timestamp.into(), gohm_price);
+    let claim_now = ready.iter().map(|loan| reward(loan, now)).sum::<Usd>()
+        + upcoming.iter().map(|loan| reward(loan, until)).sum::<Usd>();
+    let claim_now_cost = gas.cost_dollar(ready.len()) + gas.cost_dollar(upcoming.len());
+    let wait = ready
+        .iter()
+        .chain(upcoming.iter().copied())
+        .map(|loan| reward(loan, until))
+        .sum::<Usd>();
+    let wait_cost = gas.cost_dollar(ready.len() + upcoming.len());
+
+    let claim_now = claim_now.saturating_sub(claim_now_cost);
+    let wait = wait.saturating_sub(wait_cost);
+    if wait > claim_now {
+        Plan::Wait {
+            until,
+            joining: upcoming.len(),
+            gain: wait - claim_now,
+        }
+    } else {
+        Plan::ClaimNow
+    }
+}
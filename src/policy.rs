@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use ethers::types::{Address, U256};
+use rhai::{Engine, Map, Scope, AST};
+
+use crate::math::Usd;
+
+/// Operations one `should_claim` call may run, so a runaway loop fails instead of stalling the strategy.
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_EXPR_DEPTH: usize = 64;
+
+/// Loan fields exposed to policy scripts.
+#[derive(Debug, Clone)]
+pub struct ClaimCandidate {
+    pub cooler: Address,
+    pub loan_id: U256,
+    pub collateral: U256,
+    pub expiry: U256,
+    pub reward_percentage: U256,
+    pub reward_usd: Usd,
+    pub timestamp: u64,
+}
+
+/// User supplied Rhai script deciding per loan whether it may be claimed.
+/// The script must define `fn should_claim(loan)` returning a bool.
+pub struct ScriptPolicy {
+    engine: Engine,
+    ast: AST,
+}
+
+impl std::fmt::Debug for ScriptPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptPolicy").finish_non_exhaustive()
+    }
+}
+
+impl ScriptPolicy {
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+        engine.set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH);
+        let ast = engine
+            .compile_file(path.into())
+            .map_err(|err| anyhow!("failed to compile policy {}: {}", path.display(), err))?;
+
+        if !ast.iter_functions().any(|f| f.name == "should_claim" && f.params.len() == 1) {
+            return Err(anyhow!("policy {} must define fn should_claim(loan)", path.display()));
+        }
+
+        Ok(Self { engine, ast })
+    }
+
+    pub fn should_claim(&self, candidate: &ClaimCandidate) -> Result<bool> {
+        let mut loan = Map::new();
+        loan.insert("cooler".into(), format!("{:?}", candidate.cooler).into());
+        loan.insert("loan_id".into(), (candidate.loan_id.low_u64() as i64).into());
+        loan.insert("collateral".into(), (candidate.collateral.low_u128() as f64 / 1e18).into());
+        loan.insert("expiry".into(), (candidate.expiry.low_u64() as i64).into());
+        loan.insert("reward_percentage".into(), (candidate.reward_percentage.low_u64() as i64).into());
+        loan.insert("reward_usd".into(), candidate.reward_usd.to_f64().into());
+        loan.insert("timestamp".into(), (candidate.timestamp as i64).into());
+
+        self.engine
+            .call_fn::<bool>(&mut Scope::new(), &self.ast, "should_claim", (loan,))
+            .map_err(|err| anyhow!("policy should_claim failed: {}", err))
+    }
+}
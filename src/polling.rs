@@ -0,0 +1,200 @@
+use std::{
+    fmt::{self, Debug},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use artemis_core::{
+    collectors::block_collector::NewBlock,
+    types::{Collector, CollectorStream},
+};
+use async_trait::async_trait;
+use ethers::{
+    providers::{Http, JsonRpcClient, Middleware, ProviderError, PubsubClient, Ws},
+    types::{Filter, Log, U256, U64},
+};
+use futures::stream::{self, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// How the read endpoints deliver new blocks and logs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RpcMode {
+    /// Subscriptions over a websocket.
+    #[default]
+    Ws,
+    /// Polling over HTTPS, for plans without websocket access.
+    HttpPoll,
+}
+
+impl FromStr for RpcMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "ws" => Ok(RpcMode::Ws),
+            "http-poll" => Ok(RpcMode::HttpPoll),
+            _ => Err(format!("unknown rpc mode {}, expected ws or http-poll", value)),
+        }
+    }
+}
+
+impl fmt::Display for RpcMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcMode::Ws => write!(f, "ws"),
+            RpcMode::HttpPoll => write!(f, "http-poll"),
+        }
+    }
+}
+
+/// Transport of a read endpoint, a websocket or plain HTTP depending on the `RpcMode`.
+/// Subscriptions fail over HTTP, the pollers below stand in for them.
+#[derive(Debug)]
+pub enum ReadTransport {
+    Ws(Ws),
+    Http(Http),
+}
+
+impl ReadTransport {
+    pub async fn connect(url: &str, mode: RpcMode) -> Result<Self> {
+        match mode {
+            RpcMode::Ws => Ok(ReadTransport::Ws(Ws::connect(url).await?)),
+            RpcMode::HttpPoll => Ok(ReadTransport::Http(url.parse()?)),
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for ReadTransport {
+    type Error = ProviderError;
+
+    async fn request<A, R>(&self, method: &str, params: A) -> Result<R, Self::Error>
+    where
+        A: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            ReadTransport::Ws(ws) => ws.request(method, params).await.map_err(Into::into),
+            ReadTransport::Http(http) => http.request(method, params).await.map_err(Into::into),
+        }
+    }
+}
+
+impl PubsubClient for ReadTransport {
+    type NotificationStream = <Ws as PubsubClient>::NotificationStream;
+
+    fn subscribe<I: Into<U256>>(&self, id: I) -> Result<Self::NotificationStream, Self::Error> {
+        match self {
+            ReadTransport::Ws(ws) => ws.subscribe(id).map_err(Into::into),
+            ReadTransport::Http(_) => Err(ProviderError::CustomError("subscriptions need a websocket endpoint".to_string())),
+        }
+    }
+
+    fn unsubscribe<I: Into<U256>>(&self, id: I) -> Result<(), Self::Error> {
+        match self {
+            ReadTransport::Ws(ws) => ws.unsubscribe(id).map_err(Into::into),
+            ReadTransport::Http(_) => Ok(()),
+        }
+    }
+}
+
+/// Emits every new block by polling the head each `interval`, like `BlockCollector` does
+/// with a subscription.
+pub struct BlockPoller<M> {
+    client: Arc<M>,
+    interval: Duration,
+}
+
+impl<M: Middleware> BlockPoller<M> {
+    pub fn new(client: Arc<M>, interval: Duration) -> Self {
+        Self { client, interval }
+    }
+
+    /// Blocks after `last` up to the head, and the head.
+    async fn new_blocks(&self, last: U64) -> Result<(Vec<NewBlock>, U64)> {
+        let head = self.client.get_block_number().await.map_err(|err| anyhow!("{}", err))?;
+        let mut blocks = vec![];
+        for number in last.as_u64() + 1..=head.as_u64() {
+            let block = self
+                .client
+                .get_block(number)
+                .await
+                .map_err(|err| anyhow!("{}", err))?
+                .ok_or_else(|| anyhow!("block {} not found", number))?;
+            if let (Some(hash), Some(number)) = (block.hash, block.number) {
+                blocks.push(NewBlock { hash, number });
+            }
+        }
+
+        Ok((blocks, head))
+    }
+}
+
+#[async_trait]
+impl<M: Middleware> Collector<NewBlock> for BlockPoller<M> {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, NewBlock>> {
+        let head = self.client.get_block_number().await.map_err(|err| anyhow!("{}", err))?;
+        let stream = stream::unfold(head, move |mut last| async move {
+            loop {
+                tokio::time::sleep(self.interval).await;
+                match self.new_blocks(last).await {
+                    Ok((blocks, head)) if !blocks.is_empty() => return Some((stream::iter(blocks), head)),
+                    // A lagging fallback endpoint may be behind the last block seen.
+                    Ok((_, head)) => last = last.max(head),
+                    // Blocks fetched before the error come again on the next poll.
+                    Err(err) => println!("[RPC] Polling blocks after {} failed: {}", last, err),
+                }
+            }
+        })
+        .flatten();
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Emits the logs matching `filter` by querying the blocks since the last poll each
+/// `interval`, like `LogCollector` does with a subscription.
+pub struct LogPoller<M> {
+    client: Arc<M>,
+    filter: Filter,
+    interval: Duration,
+}
+
+impl<M: Middleware> LogPoller<M> {
+    pub fn new(client: Arc<M>, filter: Filter, interval: Duration) -> Self {
+        Self { client, filter, interval }
+    }
+
+    /// Logs from `from` up to the head, and the block to continue from.
+    async fn new_logs(&self, from: U64) -> Result<(Vec<Log>, U64)> {
+        let head = self.client.get_block_number().await.map_err(|err| anyhow!("{}", err))?;
+        if head < from {
+            return Ok((vec![], from));
+        }
+        let filter = self.filter.clone().from_block(from).to_block(head);
+        let logs = self.client.get_logs(&filter).await.map_err(|err| anyhow!("{}", err))?;
+
+        Ok((logs, head + 1))
+    }
+}
+
+#[async_trait]
+impl<M: Middleware> Collector<Log> for LogPoller<M> {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, Log>> {
+        let head = self.client.get_block_number().await.map_err(|err| anyhow!("{}", err))?;
+        let stream = stream::unfold(head + 1, move |mut from| async move {
+            loop {
+                tokio::time::sleep(self.interval).await;
+                match self.new_logs(from).await {
+                    Ok((logs, next)) if !logs.is_empty() => return Some((stream::iter(logs), next)),
+                    Ok((_, next)) => from = next,
+                    Err(err) => println!("[RPC] Polling logs from block {} failed: {}", from, err),
+                }
+            }
+        })
+        .flatten();
+        Ok(Box::pin(stream))
+    }
+}
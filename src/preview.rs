@@ -0,0 +1,55 @@
+use anyhow::Result;
+use comfy_table::{presets::UTF8_FULL, Table};
+use ethers::{abi::AbiDecode, providers::Middleware, types::U256};
+
+use crate::{bindings::clearinghouse::ClaimDefaultedCall, strategy::LiquidationStrategy};
+
+/// Prints the `claimDefaulted` transactions the strategy would send for the current
+/// candidate batch, one per clearinghouse, without submitting them.
+pub async fn run<M: Middleware + 'static>(mut strategy: LiquidationStrategy<M>) -> Result<()> {
+    strategy.set_loans().await?;
+    strategy.sync_head().await;
+
+    let gohm_price = strategy.oracle.gohm_price().await?;
+    let batch = strategy.claim_batch(gohm_price).await;
+    if batch.is_empty() {
+        println!("No loans pass the reward period target and policy right now.");
+        return Ok(());
+    }
+
+    let timestamp = U256::from(strategy.head_timestamp);
+    for (clearinghouse, batch) in strategy.by_clearinghouse(batch) {
+        let tx = strategy.claim_tx(&clearinghouse, &batch);
+        let calldata = tx.data().cloned().unwrap_or_default();
+        let decoded = ClaimDefaultedCall::decode(&calldata)?;
+
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .set_header(vec!["#", "Cooler", "Loan ID", "Reward"]);
+        for (i, (cooler, loan_id)) in decoded.coolers.iter().zip(decoded.loans.iter()).enumerate() {
+            let reward = batch
+                .iter()
+                .find(|loan| loan.cooler.address() == *cooler && loan.loan_id == *loan_id)
+                .map(|loan| loan.calc_rewards_in_dollar(&strategy.auction, timestamp, gohm_price))
+                .unwrap_or_default();
+            table.add_row(vec![
+                i.to_string(),
+                format!("{:?}", cooler),
+                loan_id.to_string(),
+                format!("{} dollar", reward),
+            ]);
+        }
+
+        println!("Target:   {:?}", clearinghouse.address());
+        println!("Function: claimDefaulted(address[],uint256[])");
+        println!("Calldata: {}", calldata);
+        match strategy.client.estimate_gas(&tx, None).await {
+            Ok(gas) => println!("Gas:      {}", gas),
+            Err(err) => println!("Gas:      estimation failed: {}", err),
+        }
+        println!("{}", table);
+    }
+
+    Ok(())
+}
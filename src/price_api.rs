@@ -0,0 +1,135 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use reqwest::{header::RETRY_AFTER, Client, StatusCode};
+use serde_json::Value;
+
+use crate::config::PriceApiConfig;
+
+/// Delay before the first retry, doubled after every failed attempt.
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+/// A 429 asking to wait longer than this fails the lookup, so the oracle falls back to another source.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// State of an API shared by all of its clients. Every strategy and executor has its own oracle,
+/// but the APIs count all of their requests against the same address, so the limit is kept per
+/// API for the whole process.
+#[derive(Debug)]
+struct Connection {
+    http: Client,
+    schedule: Mutex<Schedule>,
+}
+
+#[derive(Debug)]
+struct Schedule {
+    /// Earliest time the next request may go out.
+    next_request: Instant,
+    /// Until when the last 429 asked us to stay away.
+    retry_after: Instant,
+}
+
+static CONNECTIONS: LazyLock<Mutex<HashMap<&'static str, Arc<Connection>>>> = LazyLock::new(Mutex::default);
+
+/// HTTP client of a price API. Requests are spaced to stay under `requests_per_minute`, time out
+/// after `timeout_secs` and are retried with backoff on timeouts, server errors and 429s. A 429
+/// holds back every request to the API for its `Retry-After`.
+#[derive(Debug, Clone)]
+pub struct PriceApi {
+    name: &'static str,
+    connection: Arc<Connection>,
+    timeout: Duration,
+    min_interval: Duration,
+    max_retries: u32,
+}
+
+impl PriceApi {
+    pub fn new(name: &'static str, config: &PriceApiConfig) -> Self {
+        let connection = CONNECTIONS
+            .lock()
+            .unwrap()
+            .entry(name)
+            .or_insert_with(|| {
+                Arc::new(Connection {
+                    http: Client::new(),
+                    schedule: Mutex::new(Schedule {
+                        next_request: Instant::now(),
+                        retry_after: Instant::now(),
+                    }),
+                })
+            })
+            .clone();
+        Self {
+            name,
+            connection,
+            timeout: Duration::from_secs(config.timeout_secs),
+            min_interval: match config.requests_per_minute {
+                0 => Duration::ZERO,
+                limit => Duration::from_secs(60) / limit,
+            },
+            max_retries: config.max_retries,
+        }
+    }
+
+    pub async fn get_json(&self, url: &str) -> Result<Value> {
+        let mut backoff = RETRY_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            self.wait_turn().await?;
+            let error = match self.connection.http.get(url).timeout(self.timeout).send().await {
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    let retry_after = response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| value.parse().ok())
+                        .map_or(backoff, Duration::from_secs);
+                    self.hold_off(retry_after);
+                    if retry_after > MAX_RETRY_AFTER {
+                        bail!("{} rate limited us for {}s", self.name, retry_after.as_secs());
+                    }
+                    anyhow!("rate limited")
+                }
+                Ok(response) if response.status().is_server_error() => anyhow!("responded with {}", response.status()),
+                Ok(response) => return Ok(response.error_for_status()?.json().await?),
+                Err(err) if err.is_timeout() || err.is_connect() => anyhow!(err),
+                Err(err) => return Err(err.into()),
+            };
+            if attempt == self.max_retries {
+                bail!("{} {} after {} attempts", self.name, error, attempt + 1);
+            }
+            attempt += 1;
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    /// Reserves the next slot the rate limit lets out and waits for it. Fails at once while a
+    /// 429 holds the API back for longer than `MAX_RETRY_AFTER`, so callers fall back instead.
+    async fn wait_turn(&self) -> Result<()> {
+        let slot = {
+            let mut schedule = self.connection.schedule.lock().unwrap();
+            let now = Instant::now();
+            let banned = schedule.retry_after.saturating_duration_since(now);
+            if banned > MAX_RETRY_AFTER {
+                bail!("{} rate limited us for another {}s", self.name, banned.as_secs());
+            }
+            let slot = schedule.next_request.max(now);
+            schedule.next_request = slot + self.min_interval;
+            slot
+        };
+        tokio::time::sleep_until(slot.into()).await;
+        Ok(())
+    }
+
+    /// Holds back every request to the API for `delay`.
+    fn hold_off(&self, delay: Duration) {
+        let mut schedule = self.connection.schedule.lock().unwrap();
+        let until = Instant::now() + delay;
+        schedule.retry_after = schedule.retry_after.max(until);
+        schedule.next_request = schedule.next_request.max(until);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{fmt::Debug, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, U256},
+};
+
+use crate::{
+    bindings::cooler::{Cooler, Loan},
+    config::GasConfig,
+    gas, revert,
+    strategy::with_retries,
+};
+
+/// Why a read failed: the call reverted, or the endpoint couldn't be reached.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ReadError {
+    #[error("{0}")]
+    Reverted(String),
+    #[error("{0}")]
+    Rpc(String),
+}
+
+/// Reads loans from their coolers.
+#[async_trait]
+pub trait LoanReader: Debug + Send + Sync {
+    /// The loan as stored in the cooler, or the reason the read failed.
+    async fn get_loan(&self, cooler: Address, loan_id: U256) -> Result<Loan, ReadError>;
+
+    /// Owner of the cooler, or the reason the read failed.
+    async fn get_owner(&self, cooler: Address) -> Result<Address, String>;
+}
+
+/// Simulates and prices claim transactions.
+#[async_trait]
+pub trait GasEstimator: Debug + Send + Sync {
+    /// Runs `tx` against the latest state, the error is the revert reason.
+    async fn simulate(&self, tx: &TypedTransaction) -> Result<(), String>;
+
+    /// Gas `tx` uses, the error is the revert reason.
+    async fn estimate_gas(&self, tx: &TypedTransaction) -> Result<U256, String>;
+
+    /// Base fee of the head and the priority fee we'd bid for `gas_usage`, without a profit share.
+    async fn fees(&self, config: &GasConfig, gas_usage: U256) -> Result<(U256, U256)>;
+}
+
+/// `LoanReader` and `GasEstimator` reading from the chain through `client`.
+#[derive(Debug)]
+pub struct ChainReader<M> {
+    client: Arc<M>,
+}
+
+impl<M> ChainReader<M> {
+    pub fn new(client: Arc<M>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> LoanReader for ChainReader<M> {
+    async fn get_loan(&self, cooler: Address, loan_id: U256) -> Result<Loan, ReadError> {
+        let cooler = Cooler::new(cooler, self.client.clone());
+        with_retries(|| {
+            let call = cooler.get_loan(loan_id);
+            async move { call.call().await }
+        })
+        .await
+        .map_err(|err| match err.as_revert() {
+            Some(_) => ReadError::Reverted(revert::contract_reason(&err)),
+            None => ReadError::Rpc(err.to_string()),
+        })
+    }
+
+    async fn get_owner(&self, cooler: Address) -> Result<Address, String> {
+        let cooler = Cooler::new(cooler, self.client.clone());
+        with_retries(|| {
+            let call = cooler.owner();
+            async move { call.call().await }
+        })
+        .await
+        .map_err(|err| revert::contract_reason(&err))
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> GasEstimator for ChainReader<M> {
+    async fn simulate(&self, tx: &TypedTransaction) -> Result<(), String> {
+        self.client.call(tx, None).await.map(|_| ()).map_err(|err| revert::reason(&err))
+    }
+
+    async fn estimate_gas(&self, tx: &TypedTransaction) -> Result<U256, String> {
+        self.client.estimate_gas(tx, None).await.map_err(|err| revert::reason(&err))
+    }
+
+    async fn fees(&self, config: &GasConfig, gas_usage: U256) -> Result<(U256, U256)> {
+        let base_fee = gas::base_fee(&*self.client).await?;
+        let priority_fee = gas::priority_fee(&*self.client, config, None, gas_usage).await?;
+        Ok((base_fee, priority_fee))
+    }
+}
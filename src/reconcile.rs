@@ -0,0 +1,49 @@
+use std::{collections::HashSet, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use ethers::providers::Middleware;
+use indicatif::ProgressBar;
+
+use crate::{
+    bindings::{
+        cooler::Loan,
+        cooler_factory::{ClearRequestFilter, CoolerFactory},
+    },
+    strategy::{fetch_loans, query_logs},
+};
+
+/// The loans as chain state has them, read in the background for the strategy to diff
+/// against the ones it tracks.
+#[derive(Debug)]
+pub struct Reconciliation {
+    /// Head the `ClearRequest` logs were read up to.
+    pub to_block: u64,
+    pub loans: Vec<(ClearRequestFilter, Loan)>,
+}
+
+/// Re-reads the `open` loans and the ones requested since `from_block` from chain. Loans that
+/// are closed already can't change anymore, so the open ones and the new ones are all of them.
+pub async fn derive<M: Middleware + 'static>(
+    client: Arc<M>,
+    factory: CoolerFactory<M>,
+    log_block_range: Option<u64>,
+    open: Vec<ClearRequestFilter>,
+    from_block: u64,
+) -> Result<Reconciliation> {
+    let to_block = client
+        .get_block_number()
+        .await
+        .map_err(|err| anyhow!("could not get block number: {}", err))?
+        .as_u64();
+    let requested = query_logs(log_block_range, from_block, to_block, || factory.clear_request_filter()).await?;
+
+    let mut seen = HashSet::new();
+    let requests: Vec<ClearRequestFilter> = open
+        .into_iter()
+        .chain(requested)
+        .filter(|request| seen.insert((request.cooler, request.loan_id)))
+        .collect();
+    let loans = fetch_loans(client, requests, &ProgressBar::hidden()).await?;
+
+    Ok(Reconciliation { to_block, loans })
+}
@@ -0,0 +1,181 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use artemis_core::{
+    collectors::block_collector::NewBlock,
+    types::{Collector, CollectorStream, Strategy},
+};
+use async_trait::async_trait;
+use ethers::{
+    providers::Middleware,
+    types::{Log, H256, U64},
+};
+use serde::{Deserialize, Serialize};
+use tokio_stream::StreamExt;
+
+use crate::{
+    config::HooksConfig,
+    hooks::Hooks,
+    notify::Notifier,
+    strategy::LiquidationStrategy,
+    types::{Action, ClaimOutcome, CompetitorClaim, ControlCommand, Event},
+    utils::get_sys_time_in_secs,
+};
+
+/// One line of a recording.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Recording {
+    /// Unix time the bot received the event.
+    pub received_at: u64,
+    pub event: RecordedEvent,
+}
+
+/// Serializable mirror of [`Event`].
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    NewBlock { hash: H256, number: U64 },
+    NewLoan(Log),
+    RepayLoan(Log),
+    ExtendLoan(Log),
+    DefaultLoan(Log),
+    ClaimOutcome(String, ClaimOutcome),
+    Control(ControlCommand),
+    CompetitorClaim(CompetitorClaim),
+    ExpiryDue(u64),
+}
+
+impl From<&Event> for RecordedEvent {
+    fn from(event: &Event) -> Self {
+        match event.clone() {
+            Event::NewBlock(block) => RecordedEvent::NewBlock {
+                hash: block.hash,
+                number: block.number,
+            },
+            Event::NewLoan(log) => RecordedEvent::NewLoan(log),
+            Event::RepayLoan(log) => RecordedEvent::RepayLoan(log),
+            Event::ExtendLoan(log) => RecordedEvent::ExtendLoan(log),
+            Event::DefaultLoan(log) => RecordedEvent::DefaultLoan(log),
+            Event::ClaimOutcome(tenant, outcome) => RecordedEvent::ClaimOutcome(tenant, outcome),
+            Event::Control(command) => RecordedEvent::Control(command),
+            Event::CompetitorClaim(claim) => RecordedEvent::CompetitorClaim(claim),
+            Event::ExpiryDue(at) => RecordedEvent::ExpiryDue(at),
+        }
+    }
+}
+
+impl From<RecordedEvent> for Event {
+    fn from(event: RecordedEvent) -> Self {
+        match event {
+            RecordedEvent::NewBlock { hash, number } => Event::NewBlock(NewBlock { hash, number }),
+            RecordedEvent::NewLoan(log) => Event::NewLoan(log),
+            RecordedEvent::RepayLoan(log) => Event::RepayLoan(log),
+            RecordedEvent::ExtendLoan(log) => Event::ExtendLoan(log),
+            RecordedEvent::DefaultLoan(log) => Event::DefaultLoan(log),
+            RecordedEvent::ClaimOutcome(tenant, outcome) => Event::ClaimOutcome(tenant, outcome),
+            RecordedEvent::Control(command) => Event::Control(command),
+            RecordedEvent::CompetitorClaim(claim) => Event::CompetitorClaim(claim),
+            RecordedEvent::ExpiryDue(at) => Event::ExpiryDue(at),
+        }
+    }
+}
+
+/// Appends every event to a JSON lines file. Cheap to clone, all clones write to the same file.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    file: Arc<Mutex<File>>,
+}
+
+impl Recorder {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("could not open recording {}", path.display()))?;
+        println!("Recording events to {}", path.display());
+
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    fn record(&self, event: &Event) {
+        let recording = Recording {
+            received_at: get_sys_time_in_secs(),
+            event: event.into(),
+        };
+        let result = serde_json::to_string(&recording)
+            .map_err(anyhow::Error::from)
+            .and_then(|line| Ok(writeln!(self.file.lock().unwrap(), "{}", line)?));
+        if let Err(err) = result {
+            println!("[RECORD] Could not record event: {}", err);
+        }
+    }
+}
+
+/// Passes the events of another collector through unchanged, recording each one.
+pub struct RecordingCollector {
+    inner: Box<dyn Collector<Event>>,
+    recorder: Recorder,
+}
+
+impl RecordingCollector {
+    pub fn new(inner: Box<dyn Collector<Event>>, recorder: Recorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+#[async_trait]
+impl Collector<Event> for RecordingCollector {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, Event>> {
+        let stream = self.inner.get_event_stream().await?;
+        let recorder = self.recorder.clone();
+        Ok(Box::pin(stream.map(move |event| {
+            recorder.record(&event);
+            event
+        })))
+    }
+}
+
+/// Feeds a recording through the strategy in order, the way the engine would, and prints
+/// the claims it would have sent. Loans are synced first as on startup. Nothing is sent,
+/// stored or notified.
+pub async fn replay<M: Middleware + 'static>(mut strategy: LiquidationStrategy<M>, path: &Path) -> Result<()> {
+    strategy.store = None;
+    strategy.notifier = Notifier::spawn(Hooks::new(&HooksConfig::default()), None, None, None, None, None);
+    strategy.sync_state().await?;
+
+    let file = File::open(path).with_context(|| format!("could not open recording {}", path.display()))?;
+    let (mut events, mut claims) = (0, 0);
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let recording: Recording =
+            serde_json::from_str(&line).with_context(|| format!("invalid event on line {}", i + 1))?;
+        events += 1;
+
+        for action in strategy.process_event(recording.event.into()).await {
+            let Action::SubmitTx(tenant, tx) = action;
+            claims += 1;
+            println!(
+                "[REPLAY] Line {} (received at {}): {} would send a claim to {:?}, calldata {}",
+                i + 1,
+                recording.received_at,
+                tenant,
+                tx.tx.to(),
+                tx.tx.data().cloned().unwrap_or_default()
+            );
+        }
+    }
+
+    println!("Replayed {} events, {} claims would have been sent", events, claims);
+    Ok(())
+}
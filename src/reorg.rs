@@ -0,0 +1,77 @@
+use std::collections::{BTreeMap, HashSet};
+
+use ethers::types::H256;
+
+use crate::types::LoanKey;
+
+/// Heads kept to find the fork point. A deeper reorg is treated as reaching the oldest one.
+const HISTORY: u64 = 64;
+
+/// Remembers the hashes of recent heads and the loans touched by logs in each block, so the
+/// loans can be re-fetched when a reorg drops the blocks their logs were in.
+#[derive(Debug, Default)]
+pub struct ReorgTracker {
+    hashes: BTreeMap<u64, H256>,
+    touched: BTreeMap<u64, HashSet<LoanKey>>,
+    /// Loans that couldn't be read when revalidated, tried again on the next block.
+    retries: HashSet<LoanKey>,
+}
+
+/// Blocks dropped by a reorg and the loans whose logs were in them.
+#[derive(Debug)]
+pub struct Reorg {
+    /// First block that was replaced.
+    pub fork: u64,
+    /// Number of blocks the old branch had from the fork on.
+    pub depth: u64,
+    pub loans: HashSet<LoanKey>,
+}
+
+impl ReorgTracker {
+    /// Notes that a log in `block` changed `loan`.
+    pub fn touch(&mut self, block: u64, loan: LoanKey) {
+        self.touched.entry(block).or_default().insert(loan);
+    }
+
+    /// Revalidates `loan` on the next block, its read failed.
+    pub fn retry(&mut self, loan: LoanKey) {
+        self.retries.insert(loan);
+    }
+
+    pub fn take_retries(&mut self) -> HashSet<LoanKey> {
+        std::mem::take(&mut self.retries)
+    }
+
+    /// Whether the head replaces a recorded block instead of extending the recorded chain.
+    pub fn is_reorg(&self, number: u64, hash: H256, parent_hash: H256) -> bool {
+        self.hashes.get(&number).is_some_and(|known| *known != hash)
+            || self
+                .hashes
+                .get(&number.saturating_sub(1))
+                .is_some_and(|known| *known != parent_hash)
+    }
+
+    /// Recorded blocks below `number`, newest first, to compare against the canonical chain.
+    pub fn known_below(&self, number: u64) -> Vec<(u64, H256)> {
+        self.hashes.range(..number).rev().map(|(number, hash)| (*number, *hash)).collect()
+    }
+
+    /// Forgets the blocks from `fork` on and returns what they touched.
+    pub fn rewind(&mut self, fork: u64) -> Reorg {
+        let old_head = self.hashes.keys().next_back().copied().unwrap_or(fork);
+        self.hashes.split_off(&fork);
+        let loans = self.touched.split_off(&fork).into_values().flatten().collect();
+        Reorg {
+            fork,
+            depth: (old_head + 1).saturating_sub(fork),
+            loans,
+        }
+    }
+
+    pub fn record(&mut self, number: u64, hash: H256) {
+        self.hashes.insert(number, hash);
+        let oldest = number.saturating_sub(HISTORY);
+        self.hashes = self.hashes.split_off(&oldest);
+        self.touched = self.touched.split_off(&oldest);
+    }
+}
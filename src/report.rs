@@ -0,0 +1,123 @@
+use anyhow::{anyhow, Result};
+use comfy_table::{presets::UTF8_FULL, Table};
+use ethers::{providers::Middleware, types::U256};
+use tracing::info;
+
+use crate::{
+    auction::Auction,
+    ledger,
+    math::Usd,
+    store::StoredClaim,
+    strategy::LiquidationStrategy,
+};
+
+const BLOCK_TIME: u64 = 12;
+
+#[derive(Debug, Default)]
+pub struct Outcome {
+    pub loans: usize,
+    pub rewards_usd: Usd,
+}
+
+/// Realized keeper rewards next to what two fixed strategies would have earned on the same
+/// recorded claims. Rewards are gross, gas is not included.
+#[derive(Debug, Default)]
+pub struct EfficiencyReport {
+    pub claims_seen: usize,
+    pub realized: Outcome,
+    /// Claim every loan in the first block after expiry, beating everyone else to it.
+    pub at_expiry: Outcome,
+    /// Only claim at the full reward, which means only the loans nobody claimed earlier.
+    pub full_reward: Outcome,
+}
+
+impl EfficiencyReport {
+    pub fn from_claims(claims: &[StoredClaim], auction: &Auction) -> Self {
+        let mut report = Self {
+            claims_seen: claims.len(),
+            ..Default::default()
+        };
+
+        for claim in claims {
+            if claim.ours {
+                report.realized.loans += 1;
+                report.realized.rewards_usd += auction.rewards_in_dollar(
+                    claim.collateral,
+                    claim.expiry,
+                    claim.claimed_at.into(),
+                    claim.gohm_price,
+                );
+            }
+
+            report.at_expiry.loans += 1;
+            report.at_expiry.rewards_usd += auction.rewards_in_dollar(
+                claim.collateral,
+                claim.expiry,
+                claim.expiry + BLOCK_TIME,
+                claim.gohm_price,
+            );
+
+            let full_reward_at = claim.expiry + auction.duration;
+            if U256::from(claim.claimed_at) >= full_reward_at {
+                report.full_reward.loans += 1;
+                report.full_reward.rewards_usd +=
+                    auction.rewards_in_dollar(claim.collateral, claim.expiry, full_reward_at, claim.gohm_price);
+            }
+        }
+
+        report
+    }
+
+    pub fn print(&self) {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .set_header(vec!["Strategy", "Loans", "Rewards", "Realized vs. strategy"]);
+        for (name, outcome) in [
+            ("Realized (configured thresholds)", &self.realized),
+            ("Claim at expiry", &self.at_expiry),
+            ("Wait for 100%", &self.full_reward),
+        ] {
+            let diff = if self.realized.rewards_usd >= outcome.rewards_usd {
+                format!("+{}", self.realized.rewards_usd - outcome.rewards_usd)
+            } else {
+                format!("-{}", outcome.rewards_usd - self.realized.rewards_usd)
+            };
+            table.add_row(vec![
+                name.to_string(),
+                outcome.loans.to_string(),
+                format!("{} dollar", outcome.rewards_usd),
+                format!("{} dollar", diff),
+            ]);
+        }
+
+        println!("Capital efficiency over {} recorded claims (gross, gas not included)", self.claims_seen);
+        println!("{table}");
+    }
+
+    /// The report as one log event, for `ui = "headless"`.
+    pub fn log(&self) {
+        info!(
+            claims = self.claims_seen,
+            realized_loans = self.realized.loans,
+            realized_usd = %self.realized.rewards_usd,
+            at_expiry_usd = %self.at_expiry.rewards_usd,
+            full_reward_usd = %self.full_reward.rewards_usd,
+            "capital efficiency"
+        );
+    }
+}
+
+/// Prints the capital efficiency report from the claims recorded in the database, then
+/// the realized PnL of our mined claims per day or week.
+pub async fn run<M: Middleware + 'static>(strategy: LiquidationStrategy<M>, weekly: bool) -> Result<()> {
+    let store = strategy
+        .store
+        .as_ref()
+        .ok_or_else(|| anyhow!("the report needs DATABASE_PATH to be set"))?;
+    EfficiencyReport::from_claims(&store.load_claims()?, &strategy.auction).print();
+    println!();
+    ledger::print_pnl(&store.load_ledger()?, weekly);
+
+    Ok(())
+}
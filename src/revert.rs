@@ -0,0 +1,38 @@
+use ethers::{
+    abi::AbiDecode,
+    contract::ContractError,
+    providers::{Middleware, MiddlewareError},
+    utils::hex,
+};
+
+use crate::bindings::{clearinghouse::ClearinghouseErrors, cooler::CoolerErrors};
+
+/// Readable reason of a failed `eth_call` or `eth_estimateGas`. Custom errors of the
+/// Clearinghouse and of the Cooler it calls into are decoded, other revert data is shown as hex.
+pub fn reason<E: MiddlewareError>(err: &E) -> String {
+    match err.as_error_response().and_then(|response| response.as_revert_data()) {
+        Some(data) => decode(&data),
+        None => err.to_string(),
+    }
+}
+
+/// Like `reason`, for calls through the contract bindings.
+pub fn contract_reason<M: Middleware>(err: &ContractError<M>) -> String {
+    match err.as_revert() {
+        Some(data) => decode(data),
+        None => err.to_string(),
+    }
+}
+
+fn decode(data: &[u8]) -> String {
+    if let Ok(err) = ClearinghouseErrors::decode(data) {
+        return format!("{:?}", err);
+    }
+    if let Ok(err) = CoolerErrors::decode(data) {
+        return format!("{:?}", err);
+    }
+    if data.is_empty() {
+        return "reverted without data".to_string();
+    }
+    format!("reverted with 0x{}", hex::encode(data))
+}
@@ -0,0 +1,73 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use artemis_core::types::{Collector, CollectorStream};
+use async_trait::async_trait;
+use futures::stream;
+use tokio::sync::Notify;
+
+use crate::utils::get_sys_time_in_secs;
+
+/// Unix times loans pass the reward period target, shared between the strategies that fill it
+/// and the `ExpiryCollector` that wakes them.
+#[derive(Debug, Clone, Default)]
+pub struct ExpirySchedule {
+    times: Arc<Mutex<BTreeSet<u64>>>,
+    changed: Arc<Notify>,
+}
+
+impl ExpirySchedule {
+    /// Adds a wakeup at `at`. Times in the past fire right away.
+    pub fn schedule(&self, at: u64) {
+        let mut times = self.times.lock().unwrap();
+        let earliest = times.first().is_none_or(|first| at < *first);
+        if times.insert(at) && earliest {
+            self.changed.notify_one();
+        }
+    }
+
+    /// Waits for the next wakeup and returns its time. Wakeups due at once are merged into one.
+    async fn next_due(&self) -> u64 {
+        loop {
+            let now = get_sys_time_in_secs();
+            let next = self.times.lock().unwrap().first().copied();
+            match next {
+                Some(at) if at <= now => {
+                    let mut times = self.times.lock().unwrap();
+                    *times = times.split_off(&(now + 1));
+                    return at;
+                }
+                Some(at) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(Duration::from_secs(at - now)) => {}
+                        _ = self.changed.notified() => {}
+                    }
+                }
+                None => self.changed.notified().await,
+            }
+        }
+    }
+}
+
+/// Emits the time of each wakeup in the `ExpirySchedule` once it is due.
+pub struct ExpiryCollector {
+    schedule: ExpirySchedule,
+}
+
+impl ExpiryCollector {
+    pub fn new(schedule: ExpirySchedule) -> Self {
+        Self { schedule }
+    }
+}
+
+#[async_trait]
+impl Collector<u64> for ExpiryCollector {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, u64>> {
+        let stream = stream::unfold(&self.schedule, |schedule| async move { Some((schedule.next_due().await, schedule)) });
+        Ok(Box::pin(stream))
+    }
+}
@@ -0,0 +1,166 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use eth_keystore::KeystoreError;
+use ethers::{
+    signers::{AwsSigner, AwsSignerError, LocalWallet, Signer, WalletError},
+    types::{transaction::eip2718::TypedTransaction, transaction::eip712::Eip712, Address, Signature},
+};
+use rusoto_core::Region;
+use rusoto_kms::KmsClient;
+use serde::Deserialize;
+
+use crate::config::Config;
+
+/// Chain id the signers are created with, like a parsed `LocalWallet`.
+const CHAIN_ID: u64 = 1;
+
+/// Where the key that signs claims lives.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerType {
+    /// `PRIVATE_KEY` in the config or env.
+    #[default]
+    PrivateKey,
+    /// An encrypted JSON keystore (`KEYSTORE_PATH`), unlocked with `KEYSTORE_PASSWORD` or a prompt.
+    Keystore,
+    /// An AWS KMS key (`AWS_KMS_KEY_ID`), the private key never leaves KMS.
+    Aws,
+}
+
+impl FromStr for SignerType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "private_key" => Ok(SignerType::PrivateKey),
+            "keystore" => Ok(SignerType::Keystore),
+            "aws" => Ok(SignerType::Aws),
+            _ => Err(format!("unknown signer type {}, expected private_key, keystore or aws", value)),
+        }
+    }
+}
+
+impl fmt::Display for SignerType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerType::PrivateKey => write!(f, "private_key"),
+            SignerType::Keystore => write!(f, "keystore"),
+            SignerType::Aws => write!(f, "aws"),
+        }
+    }
+}
+
+/// The configured signer. An enum rather than a type parameter, so the clients in `main`
+/// keep one concrete type whichever backend signs.
+#[derive(Debug, Clone)]
+pub enum BotSigner {
+    Local(LocalWallet),
+    Aws(AwsSigner),
+}
+
+/// Creates the signer of `config`. KMS keys are looked up once to derive the address, with
+/// the region and credentials taken from the usual AWS env vars and profiles.
+pub async fn load(config: &Config) -> Result<BotSigner> {
+    match config.signer_type {
+        SignerType::PrivateKey => Ok(BotSigner::Local(config.private_key.parse()?)),
+        SignerType::Keystore => {
+            let path = config.keystore_path.clone().unwrap_or_default();
+            let wallet = decrypt_keystore(&path, config.keystore_password.clone())?;
+            println!("[SIGNER] Unlocked keystore {} ({:?})", path.display(), wallet.address());
+            Ok(BotSigner::Local(wallet))
+        }
+        SignerType::Aws => {
+            let key_id = config.aws_kms_key_id.clone().unwrap_or_default();
+            let kms = KmsClient::new(Region::default());
+            let signer = AwsSigner::new(kms, &key_id, CHAIN_ID)
+                .await
+                .map_err(|err| anyhow!("could not load KMS key {}: {}", key_id, err))?;
+            println!("[SIGNER] Signing with KMS key {} ({:?})", key_id, signer.address());
+            Ok(BotSigner::Aws(signer))
+        }
+    }
+}
+
+/// Decrypts the keystore at `path`, asking for the password on the terminal if none is given.
+fn decrypt_keystore(path: &Path, password: Option<String>) -> Result<LocalWallet> {
+    if !path.is_file() {
+        bail!("keystore {} does not exist", path.display());
+    }
+    let password = match password {
+        Some(password) => password,
+        None => rpassword::prompt_password(format!("Password for {}: ", path.display()))
+            .map_err(|err| anyhow!("could not read the keystore password: {}", err))?,
+    };
+    LocalWallet::decrypt_keystore(path, password).map_err(|err| match err {
+        WalletError::EthKeystoreError(KeystoreError::MacMismatch) => {
+            anyhow!("wrong password for keystore {}", path.display())
+        }
+        err => anyhow!("could not decrypt keystore {}: {}", path.display(), err),
+    })
+}
+
+#[derive(Debug)]
+pub enum BotSignerError {
+    Local(WalletError),
+    Aws(AwsSignerError),
+}
+
+impl fmt::Display for BotSignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BotSignerError::Local(err) => write!(f, "{}", err),
+            BotSignerError::Aws(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for BotSignerError {}
+
+#[async_trait]
+impl Signer for BotSigner {
+    type Error = BotSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        match self {
+            BotSigner::Local(signer) => signer.sign_message(message).await.map_err(BotSignerError::Local),
+            BotSigner::Aws(signer) => signer.sign_message(message).await.map_err(BotSignerError::Aws),
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            BotSigner::Local(signer) => signer.sign_transaction(tx).await.map_err(BotSignerError::Local),
+            BotSigner::Aws(signer) => signer.sign_transaction(tx).await.map_err(BotSignerError::Aws),
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        match self {
+            BotSigner::Local(signer) => signer.sign_typed_data(payload).await.map_err(BotSignerError::Local),
+            BotSigner::Aws(signer) => signer.sign_typed_data(payload).await.map_err(BotSignerError::Aws),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            BotSigner::Local(signer) => signer.address(),
+            BotSigner::Aws(signer) => signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            BotSigner::Local(signer) => signer.chain_id(),
+            BotSigner::Aws(signer) => signer.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            BotSigner::Local(signer) => BotSigner::Local(signer.with_chain_id(chain_id)),
+            BotSigner::Aws(signer) => BotSigner::Aws(signer.with_chain_id(chain_id)),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use reqwest::Client;
+use serde_json::json;
+
+use crate::{
+    config::SlackConfig,
+    notify::{Notification, Severity},
+};
+
+/// Posts are awaited by the notification task, so a slow Slack can't hold up the other channels longer.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts notifications to Slack incoming webhooks, routed by severity: claims and other
+/// informational events to `webhook_url`, pauses, low balances, failures and errors to
+/// `alert_webhook_url` if one is set.
+pub struct SlackWebhook {
+    client: Client,
+    url: Option<String>,
+    alert_url: Option<String>,
+    events: Vec<String>,
+}
+
+impl SlackWebhook {
+    pub fn new(config: &SlackConfig) -> Option<Self> {
+        if config.webhook_url.is_none() && config.alert_webhook_url.is_none() {
+            return None;
+        }
+        Some(Self {
+            client: Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            url: config.webhook_url.clone(),
+            alert_url: config.alert_webhook_url.clone(),
+            events: config.events.clone(),
+        })
+    }
+
+    /// Channel `notification` goes to, None if it isn't posted.
+    fn route(&self, notification: &Notification) -> Option<&str> {
+        if !self.events.is_empty() && !self.events.iter().any(|event| event == notification.name()) {
+            return None;
+        }
+        match notification.severity() {
+            Severity::Info => self.url.as_deref(),
+            Severity::Warning | Severity::Error | Severity::Critical => {
+                self.alert_url.as_deref().or(self.url.as_deref())
+            }
+        }
+    }
+
+    pub async fn post(&self, notification: &Notification) {
+        let Some(url) = self.route(notification) else {
+            return;
+        };
+        let result = self
+            .client
+            .post(url)
+            .json(&json!({ "text": text(notification) }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(err) = result {
+            println!("[SLACK] Failed to send {}: {}", notification.name(), err);
+        }
+    }
+}
+
+/// The title in bold and a line per field, in Slack's mrkdwn.
+fn text(notification: &Notification) -> String {
+    let icon = match notification.severity() {
+        Severity::Info => ":information_source:",
+        Severity::Warning => ":warning:",
+        Severity::Error | Severity::Critical => ":rotating_light:",
+    };
+    let mut text = format!("{} *{}*", icon, notification.title());
+    for (name, value) in notification.fields() {
+        text.push_str(&format!("\n*{}:* `{}`", name, value));
+    }
+    text
+}
@@ -0,0 +1,103 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use ethers::{providers::Middleware, types::Address};
+use serde::{Deserialize, Serialize};
+
+use crate::{store::StoredLoan, strategy::LiquidationStrategy, types::LoanKey, utils::get_sys_time_in_secs};
+
+/// Bumped whenever the format changes, older bots refuse newer snapshots instead of misreading them.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// The loans the strategy tracks at a block, enough to boot without scanning the chain from the
+/// start. Only the blocks after `block` are read from the logs on boot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub version: u32,
+    pub chain_id: u64,
+    pub cooler_factory: Address,
+    /// Block the loans were read at.
+    pub block: u64,
+    pub taken_at: u64,
+    pub loans: Vec<StoredLoan>,
+    /// Loans without collateral left, kept so they are not fetched again.
+    pub closed: Vec<LoanKey>,
+}
+
+impl StateSnapshot {
+    pub fn take<M: Middleware + 'static>(strategy: &LiquidationStrategy<M>, chain_id: u64) -> Self {
+        let mut loans: Vec<_> = strategy.loans.iter().map(|loan| loan.to_stored()).collect();
+        loans.sort_by_key(|loan| (loan.cooler, loan.loan_id));
+        let mut closed: Vec<_> = strategy.loans.closed().copied().collect();
+        closed.sort();
+        Self {
+            version: SNAPSHOT_VERSION,
+            chain_id,
+            cooler_factory: strategy.cooler_factory.address(),
+            block: strategy.reconciled_block,
+            taken_at: get_sys_time_in_secs(),
+            loans,
+            closed,
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("could not open snapshot {}", path.display()))?;
+        let snapshot: Self = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("could not read snapshot {}", path.display()))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            bail!(
+                "snapshot {} is version {}, this bot reads version {}",
+                path.display(),
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Writes the snapshot next to `path` and moves it over `path`, so a crash never leaves half of one.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let partial = path.with_extension("partial");
+        let mut writer = BufWriter::new(File::create(&partial).with_context(|| format!("could not write {}", partial.display()))?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()?;
+        fs::rename(&partial, path).with_context(|| format!("could not write {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Fails unless the snapshot was taken on the same chain and Cooler factory.
+    pub fn check(&self, chain_id: u64, cooler_factory: Address) -> Result<()> {
+        if self.chain_id != chain_id {
+            bail!("snapshot is from chain {}, connected to chain {}", self.chain_id, chain_id);
+        }
+        if self.cooler_factory != cooler_factory {
+            bail!("snapshot is of cooler factory {:?}, configured is {:?}", self.cooler_factory, cooler_factory);
+        }
+
+        Ok(())
+    }
+}
+
+/// Syncs the loans and writes them as a snapshot to `path`.
+pub async fn run<M: Middleware + 'static>(mut strategy: LiquidationStrategy<M>, path: &Path) -> Result<()> {
+    strategy.set_loans().await?;
+    let chain_id = strategy.client.get_chainid().await?.as_u64();
+    let snapshot = StateSnapshot::take(&strategy, chain_id);
+    snapshot.write(path)?;
+    println!(
+        "[SNAPSHOT] Wrote {} loans and {} closed ones at block {} to {}",
+        snapshot.loans.len(),
+        snapshot.closed.len(),
+        snapshot.block,
+        path.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use comfy_table::{presets::UTF8_FULL, Table};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+    utils::format_units,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    cli::OutputFormat,
+    gas,
+    gas_budget::{to_ether, GasTracker},
+    math::Amount,
+    output::print_json,
+    strategy::LiquidationStrategy,
+};
+
+/// Prints prices, current gas and the balance of every wallet the bot signs with, and the
+/// gas each spent when a database is configured.
+pub async fn run<M: Middleware + 'static>(
+    strategy: &LiquidationStrategy<M>,
+    wallets: &[(String, Address)],
+    output: OutputFormat,
+) -> Result<()> {
+    let client = &*strategy.client;
+    let gohm_price = strategy.oracle.gohm_price().await;
+    let eth_price = strategy.oracle.eth_price().await;
+    let base_fee = gas::base_fee(client).await?;
+    let priority_fee = gas::priority_fee(client, &strategy.config.gas, None, U256::zero()).await?;
+    let block = client.get_block_number().await.map_err(|err| anyhow!("{}", err))?;
+    let budget = strategy.config.gas.daily_budget_eth;
+
+    // Balance, nonce and the gas spent today and in total, if recorded.
+    let mut rows = vec![];
+    for (tenant, address) in wallets {
+        let balance = client.get_balance(*address, None).await.map_err(|err| anyhow!("{}", err))?;
+        let nonce = client.get_transaction_count(*address, None).await.map_err(|err| anyhow!("{}", err))?;
+        let spent = match &strategy.store {
+            Some(store) => {
+                let spent = GasTracker::load(*address, Some(store.clone()))?;
+                Some((spent.today(), spent.total()))
+            }
+            None => None,
+        };
+        rows.push((tenant, address, balance, nonce, spent));
+    }
+
+    if output == OutputFormat::Json {
+        let wallets: Vec<Value> = rows
+            .iter()
+            .map(|(tenant, address, balance, nonce, spent)| {
+                json!({
+                    "tenant": tenant,
+                    "address": address,
+                    "balance_eth": to_ether(*balance),
+                    "nonce": nonce.as_u64(),
+                    "gas_today_eth": spent.map(|(today, _)| to_ether(today)),
+                    "gas_total_eth": spent.map(|(_, total)| to_ether(total)),
+                })
+            })
+            .collect();
+        return print_json(&json!({
+            "gohm_price": gohm_price.as_ref().ok().map(|price| price.to_f64()),
+            "eth_price": eth_price.as_ref().ok().map(|price| price.to_f64()),
+            "base_fee_gwei": format_units(base_fee, "gwei")?.parse::<f64>()?,
+            "priority_fee_gwei": format_units(priority_fee, "gwei")?.parse::<f64>()?,
+            "priority_fee": strategy.config.gas.priority_fee.to_string(),
+            "block": block.as_u64(),
+            "gas_daily_budget_eth": budget,
+            "wallets": wallets,
+        }));
+    }
+
+    match gohm_price {
+        Ok(price) => println!("gOHM price:   {:.2} dollar", price),
+        Err(err) => println!("gOHM price:   unavailable ({})", err),
+    }
+    match eth_price {
+        Ok(price) => println!("ETH price:    {:.2} dollar", price),
+        Err(err) => println!("ETH price:    unavailable ({})", err),
+    }
+    println!("Base fee:     {} gwei", format_units(base_fee, "gwei")?);
+    println!(
+        "Priority fee: {} gwei ({})",
+        format_units(priority_fee, "gwei")?,
+        strategy.config.gas.priority_fee
+    );
+    println!("Block:        {}", block);
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_header(vec!["Tenant", "Wallet", "Balance", "Nonce", "Gas today", "Gas total"]);
+    for (tenant, address, balance, nonce, spent) in rows {
+        let (today, total) = match spent {
+            Some((today, total)) => {
+                let today = match budget {
+                    Some(budget) => format!("{} of {} ETH", Amount(today), budget),
+                    None => format!("{} ETH", Amount(today)),
+                };
+                (today, format!("{} ETH", Amount(total)))
+            }
+            None => ("-".to_string(), "-".to_string()),
+        };
+        table.add_row(vec![
+            tenant.clone(),
+            format!("{:?}", address),
+            format!("{} ETH", Amount(balance)),
+            nonce.to_string(),
+            today,
+            total,
+        ]);
+    }
+    println!("{}", table);
+
+    Ok(())
+}
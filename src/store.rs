@@ -0,0 +1,334 @@
+use std::{path::Path, sync::Mutex};
+
+use anyhow::{anyhow, Result};
+use ethers::types::{Address, U256};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::{ledger::LedgerEntry, math::Price};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredLoan {
+    pub cooler: Address,
+    /// Lender of the loan, zero for rows written before it was stored.
+    pub clearinghouse: Address,
+    pub req_id: U256,
+    pub loan_id: U256,
+    pub collateral: U256,
+    pub expiry: U256,
+}
+
+/// A defaulted loan claimed by anyone, kept to compare our claim timing against baselines.
+#[derive(Debug, Clone)]
+pub struct StoredClaim {
+    pub cooler: Address,
+    pub loan_id: U256,
+    pub collateral: U256,
+    pub expiry: U256,
+    pub claimed_at: u64,
+    pub gohm_price: Price,
+    pub ours: bool,
+}
+
+/// SQLite backed store for discovered loans and the last processed block.
+#[derive(Debug)]
+pub struct LoanStore {
+    conn: Mutex<Connection>,
+}
+
+impl LoanStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS loans (
+                cooler TEXT NOT NULL,
+                loan_id TEXT NOT NULL,
+                req_id TEXT NOT NULL,
+                collateral TEXT NOT NULL,
+                expiry TEXT NOT NULL,
+                clearinghouse TEXT,
+                PRIMARY KEY (cooler, loan_id)
+            );
+            CREATE TABLE IF NOT EXISTS claims (
+                cooler TEXT NOT NULL,
+                loan_id TEXT NOT NULL,
+                collateral TEXT NOT NULL,
+                expiry TEXT NOT NULL,
+                claimed_at INTEGER NOT NULL,
+                gohm_price TEXT NOT NULL,
+                ours INTEGER NOT NULL,
+                PRIMARY KEY (cooler, loan_id)
+            );
+            CREATE TABLE IF NOT EXISTS ledger (
+                tx_hash TEXT PRIMARY KEY,
+                sender TEXT NOT NULL,
+                block INTEGER NOT NULL,
+                recorded_at INTEGER NOT NULL,
+                loans TEXT NOT NULL,
+                gohm_received TEXT NOT NULL,
+                gas_paid TEXT NOT NULL,
+                gohm_price REAL NOT NULL,
+                eth_price REAL NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS gas_spent (
+                sender TEXT NOT NULL,
+                day INTEGER NOT NULL,
+                wei TEXT NOT NULL,
+                PRIMARY KEY (sender, day)
+            );
+            CREATE TABLE IF NOT EXISTS checkpoint (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                block INTEGER NOT NULL
+            );",
+        )?;
+        // Databases from before multi-clearinghouse support lack the lender.
+        if conn.prepare("SELECT clearinghouse FROM loans LIMIT 0").is_err() {
+            conn.execute_batch("ALTER TABLE loans ADD COLUMN clearinghouse TEXT")?;
+        }
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn load_loans(&self) -> Result<Vec<StoredLoan>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT cooler, req_id, loan_id, collateral, expiry, clearinghouse FROM loans")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        })?;
+
+        let mut loans = vec![];
+        for row in rows {
+            let (cooler, req_id, loan_id, collateral, expiry, clearinghouse) = row?;
+            loans.push(StoredLoan {
+                cooler: cooler.parse()?,
+                clearinghouse: clearinghouse.map(|address| address.parse()).transpose()?.unwrap_or_default(),
+                req_id: parse_u256(&req_id)?,
+                loan_id: parse_u256(&loan_id)?,
+                collateral: parse_u256(&collateral)?,
+                expiry: parse_u256(&expiry)?,
+            });
+        }
+
+        Ok(loans)
+    }
+
+    pub fn upsert_loan(&self, loan: &StoredLoan) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO loans (cooler, loan_id, req_id, collateral, expiry, clearinghouse) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (cooler, loan_id) DO UPDATE SET collateral = excluded.collateral, expiry = excluded.expiry,
+                clearinghouse = excluded.clearinghouse",
+            params![
+                format!("{:?}", loan.cooler),
+                loan.loan_id.to_string(),
+                loan.req_id.to_string(),
+                loan.collateral.to_string(),
+                loan.expiry.to_string(),
+                format!("{:?}", loan.clearinghouse),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Forgets a loan whose request was dropped by a reorg.
+    pub fn delete_loan(&self, cooler: Address, loan_id: U256) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "DELETE FROM loans WHERE cooler = ?1 AND loan_id = ?2",
+            params![format!("{:?}", cooler), loan_id.to_string()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Records a claim. A claim that is already known is only ever upgraded to ours.
+    pub fn record_claim(&self, claim: &StoredClaim) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO claims (cooler, loan_id, collateral, expiry, claimed_at, gohm_price, ours)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (cooler, loan_id) DO UPDATE SET ours = MAX(ours, excluded.ours)",
+            params![
+                format!("{:?}", claim.cooler),
+                claim.loan_id.to_string(),
+                claim.collateral.to_string(),
+                claim.expiry.to_string(),
+                claim.claimed_at as i64,
+                // Every decimal, rows written before were in whole dollars and parse the same.
+                format!("{:.18}", claim.gohm_price),
+                claim.ours,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn load_claims(&self) -> Result<Vec<StoredClaim>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT cooler, loan_id, collateral, expiry, claimed_at, gohm_price, ours FROM claims")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, bool>(6)?,
+            ))
+        })?;
+
+        let mut claims = vec![];
+        for row in rows {
+            let (cooler, loan_id, collateral, expiry, claimed_at, gohm_price, ours) = row?;
+            claims.push(StoredClaim {
+                cooler: cooler.parse()?,
+                loan_id: parse_u256(&loan_id)?,
+                collateral: parse_u256(&collateral)?,
+                expiry: parse_u256(&expiry)?,
+                claimed_at: claimed_at as u64,
+                gohm_price: gohm_price
+                    .parse()
+                    .map_err(|err| anyhow!("invalid stored price {}: {}", gohm_price, err))?,
+                ours,
+            });
+        }
+
+        Ok(claims)
+    }
+
+    pub fn record_ledger_entry(&self, entry: &LedgerEntry) -> Result<()> {
+        let loans = entry
+            .loans
+            .iter()
+            .map(|(cooler, loan_id)| format!("{:?}:{}", cooler, loan_id))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO ledger
+                (tx_hash, sender, block, recorded_at, loans, gohm_received, gas_paid, gohm_price, eth_price)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                format!("{:?}", entry.tx_hash),
+                format!("{:?}", entry.sender),
+                entry.block as i64,
+                entry.recorded_at as i64,
+                loans,
+                entry.gohm_received.to_string(),
+                entry.gas_paid.to_string(),
+                entry.gohm_price,
+                entry.eth_price,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn load_ledger(&self) -> Result<Vec<LedgerEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT tx_hash, sender, block, recorded_at, loans, gohm_received, gas_paid, gohm_price, eth_price
+             FROM ledger ORDER BY recorded_at",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, f64>(7)?,
+                row.get::<_, f64>(8)?,
+            ))
+        })?;
+
+        let mut entries = vec![];
+        for row in rows {
+            let (tx_hash, sender, block, recorded_at, loans, gohm_received, gas_paid, gohm_price, eth_price) = row?;
+            let loans = loans
+                .split(',')
+                .filter(|loan| !loan.is_empty())
+                .map(|loan| {
+                    let (cooler, loan_id) =
+                        loan.split_once(':').ok_or_else(|| anyhow!("invalid stored loan {}", loan))?;
+                    Ok((cooler.parse()?, parse_u256(loan_id)?))
+                })
+                .collect::<Result<_>>()?;
+            entries.push(LedgerEntry {
+                tx_hash: tx_hash.parse()?,
+                sender: sender.parse()?,
+                block: block as u64,
+                recorded_at: recorded_at as u64,
+                loans,
+                gohm_received: parse_u256(&gohm_received)?,
+                gas_paid: parse_u256(&gas_paid)?,
+                gohm_price,
+                eth_price,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Wei `sender` spent on gas per day since the epoch.
+    pub fn load_gas_spent(&self, sender: Address) -> Result<Vec<(u64, U256)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT day, wei FROM gas_spent WHERE sender = ?1")?;
+        let rows = stmt.query_map(params![format!("{:?}", sender)], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut spent = vec![];
+        for row in rows {
+            let (day, wei) = row?;
+            spent.push((day as u64, parse_u256(&wei)?));
+        }
+
+        Ok(spent)
+    }
+
+    pub fn set_gas_spent(&self, sender: Address, day: u64, wei: U256) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO gas_spent (sender, day, wei) VALUES (?1, ?2, ?3)
+             ON CONFLICT (sender, day) DO UPDATE SET wei = excluded.wei",
+            params![format!("{:?}", sender), day as i64, wei.to_string()],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn checkpoint(&self) -> Result<Option<u64>> {
+        let block = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT block FROM checkpoint WHERE id = 0", [], |row| row.get::<_, i64>(0))
+            .optional()?;
+
+        Ok(block.map(|block| block as u64))
+    }
+
+    pub fn set_checkpoint(&self, block: u64) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO checkpoint (id, block) VALUES (0, ?1)
+             ON CONFLICT (id) DO UPDATE SET block = excluded.block",
+            params![block as i64],
+        )?;
+
+        Ok(())
+    }
+}
+
+fn parse_u256(value: &str) -> Result<U256> {
+    U256::from_dec_str(value).map_err(|err| anyhow!("invalid stored number {}: {}", value, err))
+}
@@ -1,47 +1,299 @@
 use crate::{
+    api::{BlockSummary, LoanSnapshot, SharedSnapshot, TenantSnapshot},
+    auction::Auction,
+    chain_health::{ChainMonitor, HealthChange},
+    competition::CompetitionResponse,
     bindings::{
         clearinghouse::{ClaimDefaultedCall, Clearinghouse},
-        cooler::Cooler,
+        cooler::{Cooler, Loan},
         cooler_factory::{
             ClearRequestFilter, CoolerFactory, DefaultLoanFilter, ExtendLoanFilter, RepayLoanFilter,
         },
     },
-    utils::{get_sys_time_in_secs, get_token_price, greet},
+    config::Config,
+    dashboard::UiMode,
+    logging::LogFormat,
+    math::{Amount, Price, Usd},
+    export,
+    gas::{self, PriorityFee},
+    gas_budget::{to_ether, GasTracker},
+    loan_book::{ClaimTargets, LoanBook, LoanFeed},
+    mono_cooler::{Liquidatable, MonoCoolerPositions},
+    notify::{Notification, Notifier},
+    policy::{ClaimCandidate, ScriptPolicy},
+    report::EfficiencyReport,
+    store::{LoanStore, StoredClaim, StoredLoan},
+    oracle::{CachedOracle, PriceOracle},
+    planner::{self, GasModel, Plan},
+    reader::{ChainReader, GasEstimator, LoanReader, ReadError},
+    reconcile::{self, Reconciliation},
+    reorg::ReorgTracker,
+    schedule::ExpirySchedule,
+    state_snapshot::StateSnapshot,
+    subgraph::{self, SyncSource},
+    utils::get_sys_time_in_secs,
+};
+use anyhow::{anyhow, Result};
+use artemis_core::{
+    collectors::block_collector::NewBlock,
+    executors::mempool_executor::{GasBidInfo, SubmitTxToMempool},
+    types::Strategy,
 };
-use anyhow::Result;
-use artemis_core::{executors::mempool_executor::SubmitTxToMempool, types::Strategy};
 use async_trait::async_trait;
 use comfy_table::{presets::UTF8_FULL, Attribute, Cell, Color, Table};
-use ethers::{contract::parse_log, providers::Middleware, types::U256};
+use ethers::{
+    contract::{parse_log, EthLogDecode, Event as ContractEvent, Multicall},
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, Block, BlockNumber, Log, H256, U256},
+    utils::{format_units, parse_units},
+};
 use indicatif::{ProgressBar, ProgressState, ProgressStyle};
-use std::{fmt::Write, process::exit, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Write},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
+
+use crate::types::{Action, ClaimOutcome, CompetitorClaim, ControlCommand, Event, LoanKey};
+
+use chrono::{TimeZone, Utc};
+
+/// Loans fetched per Multicall3 `aggregate3` call during sync.
+pub const MULTICALL_CHUNK_SIZE: usize = 200;
+/// Multicall chunks in flight at once.
+const MULTICALL_PARALLEL_CHUNKS: usize = 4;
+/// Gas estimates of a claim, dropping the loans that make it revert in between.
+const ESTIMATE_ATTEMPTS: usize = 3;
+/// Failed claims after which a loan is no longer claimed, until the bot restarts.
+const MAX_CLAIM_ATTEMPTS: u32 = 3;
+/// Seconds a loan whose claim was abandoned sits out before it is claimed again.
+const ABANDONED_CLAIM_COOLDOWN: u64 = 60 * 60;
+/// Gas of a typical claim of a few loans, for the default low balance threshold.
+const CLAIM_GAS: u64 = 250_000;
+/// Attempts of a loan fetch before the event is skipped, the delay doubles after each.
+const RPC_ATTEMPTS: u32 = 3;
+const RPC_RETRY_DELAY: Duration = Duration::from_millis(500);
+/// Seconds a loan stays contested after a competing claim was seen, in case it never lands.
+const CONTEST_TTL_SECS: u64 = 60;
+
+/// Failures of a single event. They are logged and the event skipped, the event loop keeps
+/// running and the next block or event picks the loan up again.
+#[derive(Debug, thiserror::Error)]
+pub enum StrategyError {
+    #[error("could not fetch loan {loan_id} of cooler {cooler:?}: {reason}")]
+    Loan { cooler: Address, loan_id: U256, reason: ReadError },
+    #[error("could not decode {event} log: {reason}")]
+    Log { event: &'static str, reason: String },
+    #[error("no {asset} price: {reason}")]
+    Price { asset: &'static str, reason: String },
+}
+
+/// Runs `call` up to `RPC_ATTEMPTS` times, backing off in between, so a single dropped
+/// request doesn't fail the event.
+pub async fn with_retries<T, E, F, Fut>(mut call: F) -> Result<T, E>
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut delay = RPC_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < RPC_ATTEMPTS => {
+                debug!(attempt, %err, "request failed, retrying in {:?}", delay);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// `eth_getLogs` errors of providers capping the block range or the number of results, e.g.
+/// Infura's "query returned more than 10000 results" or Alchemy's "Log response size exceeded".
+fn is_log_range_error(err: &str) -> bool {
+    let err = err.to_lowercase();
+    [
+        "more than",
+        "response size exceeded",
+        "block range",
+        "range too large",
+        "range is too large",
+        "too many",
+        "limit exceeded",
+    ]
+    .iter()
+    .any(|message| err.contains(message))
+}
+
+/// Decodes a factory event log, `event` names it in the error.
+fn decode_log<D: EthLogDecode>(log: Log, event: &'static str) -> Result<D, StrategyError> {
+    parse_log(log).map_err(|err| StrategyError::Log {
+        event,
+        reason: err.to_string(),
+    })
+}
+
+/// A candidate claim through one clearinghouse, priced at the current gas price.
+struct PricedClaim<M> {
+    clearinghouse: Clearinghouse<M>,
+    batch: Vec<LoanTarget<M>>,
+    tx: TypedTransaction,
+    gas_estimate: U256,
+    base_fee: U256,
+    gas_price: U256,
+    eth_price: Price,
+    /// Gas price (wei) at which the batch stops being profitable.
+    break_even: U256,
+    reward_dollar: Usd,
+    gas_cost_dollar: Usd,
+    /// Reward after gas and the profit bid.
+    net_dollar: Usd,
+    gas_bid_info: Option<GasBidInfo>,
+}
+
+/// A competing `claimDefaulted` seen in the mempool for a loan.
+#[derive(Debug, Clone, Copy)]
+pub struct Contest {
+    /// Highest priority fee offered for the loan.
+    pub priority_fee: U256,
+    pub seen_at: u64,
+}
+
+/// Loans with a claim in flight, shared by all tenants so they don't claim the same loan twice.
+pub type InFlightClaims = Arc<Mutex<HashSet<LoanKey>>>;
 
-use crate::types::{Action, Event};
+/// Claims of a tenant that did not go through, kept across reconnects.
+#[derive(Debug, Default)]
+pub struct ClaimAttempts {
+    /// Failed claims of each loan. Loans stay in the batches until `MAX_CLAIM_ATTEMPTS` of them.
+    pub failed: HashMap<LoanKey, u32>,
+    /// Head timestamp each loan's claim was abandoned at, see `ABANDONED_CLAIM_COOLDOWN`.
+    pub abandoned: HashMap<LoanKey, u64>,
+}
 
-use chrono::{DateTime, TimeZone, Utc};
+pub type SharedClaimAttempts = Arc<Mutex<ClaimAttempts>>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct LoanTarget<M> {
     pub cooler: Cooler<M>,
+    /// Lender of the loan, the clearinghouse it has to be claimed through.
+    pub clearinghouse: Address,
     pub req_id: U256,
     pub loan_id: U256,
     pub collateral: U256,
     pub expiry: U256,
 }
 
+// Derived `Clone` would require `M: Clone`, the contract handle only clones an `Arc`.
+impl<M> Clone for LoanTarget<M> {
+    fn clone(&self) -> Self {
+        Self {
+            cooler: self.cooler.clone(),
+            clearinghouse: self.clearinghouse,
+            req_id: self.req_id,
+            loan_id: self.loan_id,
+            collateral: self.collateral,
+            expiry: self.expiry,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct LiquidationStrategy<M> {
     pub client: Arc<M>,
-    pub clearinghouse: Clearinghouse<M>,
+    /// Every clearinghouse loans are claimed through, the current deployment first.
+    pub clearinghouses: Vec<Clearinghouse<M>>,
     pub cooler_factory: CoolerFactory<M>,
-    pub loans: Vec<LoanTarget<M>>,
+    pub loans: LoanBook<M>,
+    pub config: Config,
+    pub policy: Option<ScriptPolicy>,
+    pub store: Option<Arc<LoanStore>>,
+    pub snapshot: SharedSnapshot,
+    pub notifier: Notifier,
+    pub chain_monitor: ChainMonitor,
+    pub reorgs: ReorgTracker,
+    /// Prices gOHM and ETH, the oracle `price_source` selects.
+    pub oracle: Arc<dyn PriceOracle>,
+    /// Reward auction of the clearinghouses, read from the current one at startup.
+    pub auction: Auction,
+    /// Timestamp of the latest block, what rewards and claimability are computed at. The
+    /// local time until the first block arrives.
+    pub head_timestamp: u64,
+    /// Reads loans from their coolers.
+    pub loan_reader: Arc<dyn LoanReader>,
+    /// Simulates, estimates and prices claims.
+    pub gas_estimator: Arc<dyn GasEstimator>,
+    pub claim_attempts: SharedClaimAttempts,
+    /// Wakeups for when loans pass the reward period target.
+    pub expiry_schedule: ExpirySchedule,
+    /// Loans other bots are claiming according to the mempool.
+    pub contested: HashMap<LoanKey, Contest>,
+    /// Gas of the last priced claim, to project when loans become profitable.
+    pub gas_model: Option<GasModel>,
+    /// Wakeup scheduled for the next loan to become profitable.
+    pub profit_wakeup: Option<u64>,
+    /// Owners of the coolers checked against the cooler filters, they never change.
+    pub owners: HashMap<Address, Address>,
+    /// Cooler V2 positions, with `mono_cooler_address` set.
+    pub mono_cooler: Option<MonoCoolerPositions<M>>,
+    /// Name of the keeper profile this strategy claims for.
+    pub tenant: String,
+    pub in_flight: InFlightClaims,
+    /// Shares `loans` between the tenants, only the lead tenant syncs them.
+    pub loan_feed: LoanFeed<M>,
+    /// Set by `pause` on the control socket, cleared by `resume`.
+    pub manual_pause: bool,
+    /// Gas the tenant's wallet spent, filled in by its executor.
+    pub gas_spent: GasTracker,
+    /// Whether the daily gas budget paused claiming as of the last block.
+    pub over_gas_budget: bool,
+    /// Wallet the tenant claims from, whose balance is monitored.
+    pub wallet: Address,
+    /// Set while the wallet balance is below `balance.min_eth`, describing the shortfall.
+    pub low_balance: Option<String>,
+    /// Set while the base fee of the head is above `max_gas_price_gwei`.
+    pub gas_price_high: Option<String>,
+    /// Set while there is no gOHM price, every source failed past `stale_price.max_age_secs`.
+    pub price_unavailable: Option<String>,
+    /// When the capital efficiency report was last printed.
+    pub last_report: u64,
+    /// When the CSV files were last written to `export_dir`.
+    pub last_export: u64,
+    /// Head the last reconciliation read new loans up to.
+    pub reconciled_block: u64,
+    /// When the last reconciliation was started.
+    pub last_reconcile: u64,
+    /// The reconciliation running in the background, if one is.
+    pub reconciliation: Option<JoinHandle<Result<Reconciliation>>>,
+    /// The dashboard draws the block summary, so the tables are not printed.
+    pub dashboard: bool,
 }
 
 impl<M: Middleware + 'static> LoanTarget<M> {
-    pub async fn new(cooler: Cooler<M>, req_id: U256, loan_id: U256) -> Self {
-        let loan = cooler.get_loan(loan_id).await.unwrap();
+    pub async fn new(reader: &dyn LoanReader, cooler: Cooler<M>, req_id: U256, loan_id: U256) -> Result<Self, StrategyError> {
+        let loan = fetch_loan(reader, cooler.address(), loan_id).await?;
+        Ok(Self {
+            cooler,
+            clearinghouse: loan.lender,
+            req_id,
+            loan_id,
+            collateral: loan.collateral,
+            expiry: loan.expiry,
+        })
+    }
+
+    pub fn from_loan(cooler: Cooler<M>, req_id: U256, loan_id: U256, loan: &Loan) -> Self {
         Self {
             cooler,
+            clearinghouse: loan.lender,
             req_id,
             loan_id,
             collateral: loan.collateral,
@@ -49,8 +301,31 @@ impl<M: Middleware + 'static> LoanTarget<M> {
         }
     }
 
-    pub async fn update(&mut self) {
-        let loan = self.cooler.get_loan(self.loan_id).await.unwrap();
+    pub fn from_stored(cooler: Cooler<M>, stored: &StoredLoan) -> Self {
+        Self {
+            cooler,
+            clearinghouse: stored.clearinghouse,
+            req_id: stored.req_id,
+            loan_id: stored.loan_id,
+            collateral: stored.collateral,
+            expiry: stored.expiry,
+        }
+    }
+
+    pub fn to_stored(&self) -> StoredLoan {
+        StoredLoan {
+            cooler: self.cooler.address(),
+            clearinghouse: self.clearinghouse,
+            req_id: self.req_id,
+            loan_id: self.loan_id,
+            collateral: self.collateral,
+            expiry: self.expiry,
+        }
+    }
+
+    /// Takes over the lender, collateral and expiry of a freshly read `loan`.
+    pub fn set(&mut self, loan: &Loan) {
+        self.clearinghouse = loan.lender;
         self.collateral = loan.collateral;
         self.expiry = loan.expiry;
     }
@@ -63,91 +338,482 @@ impl<M: Middleware + 'static> LoanTarget<M> {
         }
     }
 
-    pub fn calc_reward_percentage(&self) -> U256 {
-        let timestamp = U256::from(get_sys_time_in_secs());
-        let elapsed = timestamp - self.expiry;
-        let seven_days_in_s: U256 = (7 * 24 * 60 * 60).into();
-        let reward_percentage = if elapsed < seven_days_in_s {
-            elapsed * 100 / seven_days_in_s
-        } else {
-            100.into()
-        };
+    pub fn calc_reward_percentage(&self, auction: &Auction, timestamp: U256) -> U256 {
+        auction.reward_percentage(self.expiry, timestamp)
+    }
 
-        return reward_percentage;
+    pub fn calc_rewards_in_dollar(&self, auction: &Auction, timestamp: U256, ohm_price: Price) -> Usd {
+        auction.rewards_in_dollar(self.collateral, self.expiry, timestamp, ohm_price)
     }
+}
 
-    pub fn calc_rewards_in_dollar(&self, timestamp: U256, ohm_price: U256) -> U256 {
-        let elapsed = timestamp - self.expiry;
-        let seven_days_in_s: U256 = (7 * 24 * 60 * 60).into();
-        let mut max_reward: U256 = (1e17 as u64).into();
+pub async fn fetch_loan(reader: &dyn LoanReader, cooler: Address, loan_id: U256) -> Result<Loan, StrategyError> {
+    reader
+        .get_loan(cooler, loan_id)
+        .await
+        .map_err(|reason| StrategyError::Loan { cooler, loan_id, reason })
+}
 
-        let max_auction_reward = (self.collateral * 5e16 as u64) / 1e18 as u64;
-        max_reward = if max_auction_reward < max_reward {
-            max_auction_reward
-        } else {
-            max_reward
-        };
+fn persist<M: Middleware + 'static>(store: &Option<Arc<LoanStore>>, loan: &LoanTarget<M>) {
+    if let Some(store) = store {
+        if let Err(err) = store.upsert_loan(&loan.to_stored()) {
+            warn!(loan_id = %loan.loan_id, cooler = ?loan.cooler.address(), %err, "could not save loan");
+        }
+    }
+}
 
-        let reward_in_gohm: U256 = if elapsed < seven_days_in_s {
-            (max_reward * elapsed) / seven_days_in_s
-        } else {
-            max_reward
+/// Records that `loan` got claimed, before its collateral is refreshed to zero.
+fn record_claim<M: Middleware + 'static>(
+    store: &Option<Arc<LoanStore>>,
+    loan: &LoanTarget<M>,
+    claimed_at: u64,
+    gohm_price: Price,
+    ours: bool,
+) {
+    if let Some(store) = store {
+        let claim = StoredClaim {
+            cooler: loan.cooler.address(),
+            loan_id: loan.loan_id,
+            collateral: loan.collateral,
+            expiry: loan.expiry,
+            claimed_at,
+            gohm_price,
+            ours,
         };
-
-        let reward_in_dollar = reward_in_gohm * ohm_price / (1e18 as u64);
-
-        return reward_in_dollar.into();
+        if let Err(err) = store.record_claim(&claim) {
+            warn!(loan_id = %loan.loan_id, cooler = ?loan.cooler.address(), %err, "could not record claim");
+        }
     }
 }
 
 impl<M: Middleware + 'static> LiquidationStrategy<M> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         client: Arc<M>,
-        clearinghouse: Clearinghouse<M>,
+        clearinghouses: Vec<Clearinghouse<M>>,
         cooler_factory: CoolerFactory<M>,
+        config: Config,
+        policy: Option<ScriptPolicy>,
+        store: Option<Arc<LoanStore>>,
+        snapshot: SharedSnapshot,
+        notifier: Notifier,
     ) -> Self {
+        let chain_monitor = ChainMonitor::new(config.chain_health.clone());
+        let oracle = Arc::new(CachedOracle::from_config(client.clone(), &config));
+        let reader = Arc::new(ChainReader::new(client.clone()));
+        let mono_cooler = config
+            .mono_cooler_address
+            .map(|address| MonoCoolerPositions::new(address, client.clone(), config.mono_cooler_start_block));
         Self {
             client,
-            clearinghouse,
+            clearinghouses,
             cooler_factory,
-            loans: vec![],
+            loans: LoanBook::default(),
+            config,
+            policy,
+            store,
+            snapshot,
+            notifier,
+            chain_monitor,
+            reorgs: ReorgTracker::default(),
+            oracle,
+            auction: Auction::default(),
+            head_timestamp: get_sys_time_in_secs(),
+            loan_reader: reader.clone(),
+            gas_estimator: reader,
+            claim_attempts: SharedClaimAttempts::default(),
+            expiry_schedule: ExpirySchedule::default(),
+            contested: HashMap::new(),
+            gas_model: None,
+            profit_wakeup: None,
+            owners: HashMap::new(),
+            mono_cooler,
+            tenant: "default".to_string(),
+            in_flight: InFlightClaims::default(),
+            loan_feed: LoanFeed::default(),
+            manual_pause: false,
+            gas_spent: GasTracker::default(),
+            over_gas_budget: false,
+            wallet: Address::zero(),
+            low_balance: None,
+            gas_price_high: None,
+            price_unavailable: None,
+            last_report: get_sys_time_in_secs(),
+            last_export: 0,
+            reconciled_block: 0,
+            last_reconcile: get_sys_time_in_secs(),
+            reconciliation: None,
+            dashboard: false,
         }
     }
 }
 
 impl<M: Middleware + 'static> LiquidationStrategy<M> {
-    async fn print_table(&self, claimable: U256, gohm_price: U256, claimable_consider_gas_and_targets: U256) {
-        println!("\x1B[2J\x1B[1;1H");
-        greet();
+    async fn publish_snapshot(&self, gohm_price: Price) {
+        if self.loan_feed.leads() {
+            let mut loans: Vec<LoanSnapshot> = self
+                .loans
+                .iter()
+                .map(|loan| LoanSnapshot {
+                    cooler: loan.cooler.address(),
+                    clearinghouse: loan.clearinghouse,
+                    loan_id: loan.loan_id,
+                    collateral: loan.collateral,
+                    expiry: loan.expiry,
+                    profitable_at: self.profitable_at(loan, gohm_price),
+                })
+                .collect();
+            loans.sort_by_key(|loan| loan.expiry);
+
+            let mut snapshot = self.snapshot.write().await;
+            snapshot.loans = loans;
+            snapshot.gohm_price = gohm_price;
+            snapshot.auction = self.auction;
+            snapshot.head_timestamp = self.head_timestamp;
+            snapshot.lead_tenant = self.tenant.clone();
+        }
+        self.publish_tenant(|tenant| {
+            tenant.pause_reason = self.pause_reason();
+            tenant.break_even_gas_price = None;
+        })
+        .await;
+    }
+
+    /// Updates this tenant's part of the snapshot.
+    async fn publish_tenant(&self, update: impl FnOnce(&mut TenantSnapshot)) {
+        update(self.snapshot.write().await.tenants.entry(self.tenant.clone()).or_default());
+    }
+
+    /// Claims for `tenant`, coordinating with the other tenants through `in_flight`.
+    pub fn with_tenant(mut self, tenant: String, in_flight: InFlightClaims) -> Self {
+        self.tenant = tenant;
+        self.in_flight = in_flight;
+        self
+    }
+
+    /// Keeps the failed and abandoned claims in `claim_attempts`, which outlives reconnects.
+    pub fn with_claim_attempts(mut self, claim_attempts: SharedClaimAttempts) -> Self {
+        self.claim_attempts = claim_attempts;
+        self
+    }
+
+    /// Mirrors the loans published to `loan_feed` unless it leads it, see `LoanFeed`.
+    pub fn with_loan_feed(mut self, loan_feed: LoanFeed<M>) -> Self {
+        self.loan_feed = loan_feed;
+        self
+    }
+
+    /// Counts gas against the daily budget through `gas_spent`, shared with the executor.
+    pub fn with_gas_tracker(mut self, gas_spent: GasTracker) -> Self {
+        self.gas_spent = gas_spent;
+        self
+    }
+
+    /// Computes rewards with `auction` instead of the default parameters.
+    pub fn with_auction(mut self, auction: Auction) -> Self {
+        self.auction = auction;
+        self
+    }
+
+    /// Monitors the balance of `wallet`, the tenant's signer.
+    pub fn with_wallet(mut self, wallet: Address) -> Self {
+        self.wallet = wallet;
+        self
+    }
+
+    /// Moves `head_timestamp` to the latest block, keeping the old one if it can't be read.
+    pub async fn sync_head(&mut self) {
+        match self.client.get_block(BlockNumber::Latest).await {
+            Ok(Some(block)) => self.advance_head(block.timestamp.as_u64()),
+            Ok(None) => warn!("latest block not found"),
+            Err(err) => warn!(%err, "could not read the latest block"),
+        }
+    }
+
+    /// Moves `head_timestamp` to `timestamp`, notifying the loans that expired in between and
+    /// the large ones that came within `expiry_warning.before_mins` of their expiry.
+    fn advance_head(&mut self, timestamp: u64) {
+        let warning = &self.config.expiry_warning;
+        if warning.before_mins > 0 {
+            let before = warning.before_mins * 60;
+            let window_start = U256::from(self.head_timestamp + before);
+            for loan in self
+                .loans
+                .expired((timestamp + before).into())
+                .skip_while(|loan| loan.expiry < window_start)
+                .filter(|loan| loan.expiry > timestamp.into() && to_ether(loan.collateral) >= warning.min_collateral)
+            {
+                self.notifier.notify(Notification::LoanExpiring {
+                    cooler: loan.cooler.address(),
+                    loan_id: loan.loan_id,
+                    collateral_gohm: to_ether(loan.collateral),
+                    expiry: loan.expiry.low_u64(),
+                    expires_in_secs: loan.expiry.low_u64() - timestamp,
+                });
+            }
+        }
+        let previous = U256::from(self.head_timestamp);
+        for loan in self.loans.expired(timestamp.into()).skip_while(|loan| loan.expiry < previous) {
+            self.notifier.notify(Notification::LoanExpired {
+                cooler: loan.cooler.address(),
+                loan_id: loan.loan_id,
+                collateral_gohm: to_ether(loan.collateral),
+                expiry: loan.expiry.low_u64(),
+            });
+        }
+        self.head_timestamp = timestamp;
+    }
+
+    /// Unix time at which `loan` passes its reward period target, None if it never does.
+    fn due_at(&self, loan: &LoanTarget<M>) -> Option<u64> {
+        let offset = self.auction.claim_offset(self.config.reward_period_target_for(loan.collateral))?;
+        Some(loan.expiry.low_u64() + offset)
+    }
+
+    /// Time from which claiming `loan` on its own clears `min_profit` at the gas of the last
+    /// priced claim, never before its reward period target. None before any claim was priced or
+    /// if its reward never gets there.
+    fn profitable_at(&self, loan: &LoanTarget<M>, gohm_price: Price) -> Option<u64> {
+        let gas_cost = self.gas_model?.cost_dollar(1);
+        let required = gohm_price.amount(self.config.min_profit_for(gas_cost) + gas_cost);
+        let offset = self.auction.time_to_reward(loan.collateral, required)?;
+        Some((loan.expiry.low_u64() + offset).max(self.due_at(loan)?))
+    }
+
+    /// Wakes the strategy when the next expired loan becomes profitable, so it is claimed then
+    /// instead of at the next block that happens to price it.
+    fn schedule_profitable(&mut self, gohm_price: Price) {
+        let next = self
+            .loans
+            .expired(U256::from(self.head_timestamp))
+            .filter_map(|loan| self.profitable_at(loan, gohm_price))
+            .filter(|at| *at > self.head_timestamp)
+            .min();
+        if let Some(at) = next.filter(|at| self.profit_wakeup != Some(*at)) {
+            debug!(at, "next loan becomes profitable");
+            self.expiry_schedule.schedule(at);
+        }
+        self.profit_wakeup = next;
+    }
+
+    /// Wakes the strategy once `loan` passes its reward period target.
+    fn schedule_expiry(&self, loan: &LoanTarget<M>) {
+        if let Some(at) = self.due_at(loan) {
+            self.expiry_schedule.schedule(at);
+        }
+    }
+
+    /// Schedules a wakeup for every open loan that hasn't passed its reward period target yet.
+    fn schedule_open(&self) {
+        let now = get_sys_time_in_secs();
+        for at in self.loans.open().filter_map(|loan| self.due_at(loan)) {
+            if at > now {
+                self.expiry_schedule.schedule(at);
+            }
+        }
+    }
+
+    /// Whether a loan passed its reward period target and isn't being claimed. Only then can
+    /// pricing claims lead to one, so otherwise it is skipped.
+    fn loans_due(&mut self) -> bool {
+        // The targets may have been reloaded since the last block.
+        self.loans.set_targets(ClaimTargets {
+            auction: self.auction,
+            reward_period_target: self.config.reward_period_target,
+            reward_period_tiers: self.config.reward_period_tiers.clone(),
+        });
+        let in_flight = self.in_flight.lock().unwrap();
+        self.loans.due(self.head_timestamp).any(|loan| {
+            let key = (loan.cooler.address(), loan.loan_id);
+            !in_flight.contains(&key) && !self.gave_up(&key)
+        })
+    }
+
+    /// Whether claiming `key` failed too often to try again, or was abandoned too recently.
+    fn gave_up(&self, key: &LoanKey) -> bool {
+        let attempts = self.claim_attempts.lock().unwrap();
+        attempts.failed.get(key).is_some_and(|failed| *failed >= MAX_CLAIM_ATTEMPTS)
+            || attempts
+                .abandoned
+                .get(key)
+                .is_some_and(|at| self.head_timestamp < at + ABANDONED_CLAIM_COOLDOWN)
+    }
+
+    /// The gOHM price, the cached one while the sources fail. Once that is too old claims
+    /// are paused and the operator alerted until a price arrives again.
+    async fn gohm_price(&mut self) -> Result<Price, StrategyError> {
+        match self.oracle.gohm_price().await {
+            Ok(price) => {
+                if self.price_unavailable.take().is_some() {
+                    info!(%price, "gOHM price available again, resuming claims");
+                    self.notifier.notify(Notification::EngineResumed);
+                }
+                Ok(price)
+            }
+            Err(err) => {
+                let err = StrategyError::Price {
+                    asset: "gOHM",
+                    reason: err.to_string(),
+                };
+                if self.price_unavailable.is_none() {
+                    warn!(%err, "pausing claims until a price arrives");
+                    self.notifier.notify(Notification::EnginePaused { reason: err.to_string() });
+                }
+                self.price_unavailable = Some(err.to_string());
+                // No snapshot is published without a price, the dashboard would show claiming.
+                self.publish_tenant(|tenant| tenant.pause_reason = self.pause_reason()).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Prices claims for the loans past the reward period target and returns the ones to send.
+    async fn evaluate(&mut self, gohm_price: Price) -> Vec<Action> {
+        let claimable_dollar_raw: Usd = self
+            .loans
+            .expired(U256::from(self.head_timestamp))
+            .map(|loan| loan.calc_rewards_in_dollar(&self.auction, U256::from(self.head_timestamp), gohm_price))
+            .sum();
+
+        let batch = if self.loans_due() { self.claim_batch(gohm_price).await } else { vec![] };
+        let mut claims = vec![];
+        for (clearinghouse, batch) in self.by_clearinghouse(batch) {
+            let batch = self.simulate_batch(&clearinghouse, batch).await;
+            if batch.is_empty() {
+                continue;
+            }
+            for batch in self.split_by_gas(&clearinghouse, batch).await {
+                match self.price_claim(clearinghouse.clone(), batch, gohm_price).await {
+                    Ok(Some(claim)) => claims.push(claim),
+                    Ok(None) => {}
+                    Err(err) => warn!(clearinghouse = ?clearinghouse.address(), %err, "skipping batch"),
+                }
+            }
+        }
+
+        if let Some(claim) = claims.first() {
+            self.gas_model = Some(GasModel::from_estimate(claim.gas_estimate, claim.batch.len(), claim.gas_price, claim.eth_price));
+        }
+        let net_claimable: Usd = claims.iter().map(|claim| claim.net_dollar).sum();
+        // With several clearinghouses the batch that stays profitable the longest.
+        if let Some(break_even) = claims.iter().map(|claim| claim.break_even).max() {
+            self.publish_tenant(|tenant| tenant.break_even_gas_price = Some(break_even)).await;
+        }
+        self.schedule_profitable(gohm_price);
+        self.print_table(claimable_dollar_raw, gohm_price, net_claimable)
+            .await;
+
+        let mut actions = vec![];
+        for claim in claims {
+            actions.extend(self.claim_action(claim, gohm_price));
+        }
+        actions
+    }
+
+    /// Marks the tracked loans `claim` targets as contested. With the `drop` response they are
+    /// left out of batches, with `outbid` claimed with a higher tip than the competitor's.
+    fn contest(&mut self, claim: CompetitorClaim) {
+        let now = get_sys_time_in_secs();
+        let contested: Vec<LoanKey> = claim.loans.into_iter().filter(|key| self.loans.get(key).is_some()).collect();
+        if contested.is_empty() {
+            return;
+        }
+        warn!(
+            tx = ?claim.tx_hash,
+            from = ?claim.from,
+            loans = contested.len(),
+            priority_fee_gwei = %format_units(claim.priority_fee, "gwei").unwrap_or_default(),
+            response = %self.config.competition.response,
+            "competing claim in the mempool"
+        );
+        for key in contested {
+            let contest = self.contested.entry(key).or_insert(Contest {
+                priority_fee: U256::zero(),
+                seen_at: now,
+            });
+            contest.priority_fee = contest.priority_fee.max(claim.priority_fee);
+            contest.seen_at = now;
+        }
+    }
+
+    /// Marks `loans` as in flight. Fails without marking any if another claim holds one of them.
+    fn reserve(&self, loans: &[LoanKey]) -> bool {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if loans.iter().any(|loan| in_flight.contains(loan)) {
+            return false;
+        }
+        in_flight.extend(loans.iter().copied());
+        true
+    }
+
+    fn release(&self, loans: &[LoanKey]) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        for loan in loans {
+            in_flight.remove(loan);
+        }
+    }
+
+    /// Why claims are not being submitted right now, if they aren't.
+    fn pause_reason(&self) -> Option<String> {
+        if self.manual_pause {
+            return Some("paused by operator".to_string());
+        }
+        if let Some(reason) = self.chain_monitor.pause_reason() {
+            return Some(reason.to_string());
+        }
+        if let Some(reason) = &self.gas_price_high {
+            return Some(reason.clone());
+        }
+        if let Some(reason) = &self.price_unavailable {
+            return Some(reason.clone());
+        }
+        if let Some(reason) = self.low_balance.as_ref().filter(|_| self.config.balance.pause_when_low) {
+            return Some(reason.clone());
+        }
+        self.gas_spent.exceeded(self.config.gas.daily_budget_eth)
+    }
 
+    async fn print_table(&self, claimable: Usd, gohm_price: Price, claimable_consider_gas_and_targets: Usd) {
         let mut table_info = Table::new();
-        let ohm_price = get_token_price("governance-ohm").await.unwrap() as u64;
+        let timestamp = U256::from(self.head_timestamp);
         let expired_loans: Vec<&LoanTarget<M>> = self
             .loans
-            .iter()
-            .filter(|loan| {
-                loan.expiry < U256::from(get_sys_time_in_secs())
-                    && loan.collateral > 0.into()
-                    && loan.calc_rewards_in_dollar(
-                        U256::from(get_sys_time_in_secs()),
-                        ohm_price.into(),
-                    ) > 0.into()
-            })
+            .expired(timestamp)
+            .filter(|loan| !loan.calc_rewards_in_dollar(&self.auction, timestamp, gohm_price).is_zero())
             .collect();
 
-        let total_collateral_gohm = expired_loans
-            .iter()
-            .fold(U256::from(0), |acc, loan| acc + loan.collateral)
-            / (1e18 as u64) as u64;
+        let total_collateral_gohm = expired_loans.iter().fold(U256::zero(), |acc, loan| acc + loan.collateral);
 
-        let timestamp = U256::from(get_sys_time_in_secs());
-        let next_expiry = self.loans.iter().fold(U256::MAX, |acc, loan| {
-            if loan.expiry > timestamp && loan.expiry < acc {
-                loan.expiry - timestamp
-            } else {
-                acc
-            }
-        });
+        let next_claimable = self.loans.next_due(self.head_timestamp).map(|at| at - self.head_timestamp);
+        let next_profitable = self.profit_wakeup.map(|at| at.saturating_sub(self.head_timestamp));
+
+        let summary = BlockSummary {
+            claimable,
+            claimable_net: claimable_consider_gas_and_targets,
+            min_profit: self.config.min_profit,
+            reward_period_target: self.config.reward_period_target,
+            expired_loans: expired_loans.len(),
+            total_collateral_gohm,
+            next_claimable,
+            next_profitable,
+        };
+        self.publish_tenant(|tenant| tenant.summary = Some(summary)).await;
+        debug!(
+            claimable_usd = %claimable,
+            net_usd = %claimable_consider_gas_and_targets,
+            gohm_price = %gohm_price,
+            expired_loans = expired_loans.len(),
+            "block summary"
+        );
+        // The tables would break up JSON and headless logs and aren't wanted quiet, the summary
+        // event above carries the same numbers.
+        if self.dashboard
+            || self.config.log_format == LogFormat::Json
+            || self.config.ui == UiMode::Headless
+            || self.config.verbosity < 0
+        {
+            return;
+        }
 
         table_info.load_preset(UTF8_FULL).set_header(vec![
             "Claimable",
@@ -156,19 +822,33 @@ impl<M: Middleware + 'static> LiquidationStrategy<M> {
             "Reward Period Target",
             "Expired Loans",
             "Total Collateral",
-            "Next Expiry",
+            "Next Claimable",
+            "Next Profitable",
+            "Wallet Balance",
         ]);
 
-        let duration: DateTime<Utc> = Utc.timestamp_opt(next_expiry.as_u64() as i64, 0).unwrap();
-        let duration = duration.format("%Hh:%Mm:%Ss");
+        let duration = next_claimable
+            .and_then(|next_claimable| Utc.timestamp_opt(next_claimable as i64, 0).single())
+            .map_or("-".to_string(), |duration| duration.format("%Hh:%Mm:%Ss").to_string());
+        let profitable_in = next_profitable
+            .and_then(|next_profitable| Utc.timestamp_opt(next_profitable as i64, 0).single())
+            .map_or("-".to_string(), |duration| duration.format("%Hh:%Mm:%Ss").to_string());
         table_info.load_preset(UTF8_FULL).add_row(vec![
             format!("{} dollar", claimable.to_string()),
             format!("{} dollar", claimable_consider_gas_and_targets.to_string()),
-            format!("{} dollar", std::env::var("MIN_PROFIT").unwrap()),
-            format!("{}%", std::env::var("REWARD_PERIOD_TARGET").unwrap()),
+            format!("{} dollar", self.config.min_profit),
+            format!("{}%", self.config.reward_period_target),
             expired_loans.len().to_string(),
-            format!("{} gOHM", total_collateral_gohm.to_string()),
+            format!("{} gOHM", Amount(total_collateral_gohm)),
             format!("{}", duration),
+            profitable_in,
+            self.snapshot
+                .read()
+                .await
+                .tenants
+                .get(&self.tenant)
+                .and_then(|tenant| tenant.balance)
+                .map_or("-".to_string(), |balance| format!("{} ETH", Amount(balance))),
         ]);
 
         let mut table_loans = Table::new();
@@ -179,14 +859,12 @@ impl<M: Middleware + 'static> LiquidationStrategy<M> {
             "Expire time (UTC)",
             "Reward period passed",
             "Reward",
+            "Profitable at (UTC)",
         ]);
         for loan in expired_loans.iter() {
-            let is_reward_period_target_hit = loan.calc_reward_percentage()
-                > std::env::var("REWARD_PERIOD_TARGET")
-                    .unwrap()
-                    .parse::<u64>()
-                    .unwrap().into();
-            let reward_target_text = format!("{}%", loan.calc_reward_percentage());
+            let is_reward_period_target_hit =
+                loan.calc_reward_percentage(&self.auction, timestamp) > self.config.reward_period_target_for(loan.collateral).into();
+            let reward_target_text = format!("{}%", loan.calc_reward_percentage(&self.auction, timestamp));
             let reward_target_text: Cell = if is_reward_period_target_hit {
                 Cell::new(reward_target_text)
                     .fg(Color::Green)
@@ -195,24 +873,34 @@ impl<M: Middleware + 'static> LiquidationStrategy<M> {
                 Cell::new(reward_target_text)
             };
 
-            let readable_expiry = chrono::Utc
+            let readable_expiry = Utc
                 .timestamp_opt(loan.expiry.as_u64() as i64, 0)
-                .unwrap();
-            let readable_expiry = readable_expiry.format("%Y-%m-%d %H:%M:%S").to_string();
+                .single()
+                .map_or("-".to_string(), |expiry| expiry.format("%Y-%m-%d %H:%M:%S").to_string());
+            let profitable_at = match self.profitable_at(loan, gohm_price) {
+                Some(at) if at <= self.head_timestamp => "now".to_string(),
+                Some(at) => Utc
+                    .timestamp_opt(at as i64, 0)
+                    .single()
+                    .map_or("-".to_string(), |at| at.format("%Y-%m-%d %H:%M:%S").to_string()),
+                None => "-".to_string(),
+            };
             table_loans.load_preset(UTF8_FULL).add_row(vec![
                 Cell::new(loan.cooler.address().to_string()),
                 Cell::new(loan.loan_id.to_string()),
-                Cell::new(loan.collateral.to_string()),
+                Cell::new(format!("{} gOHM", Amount(loan.collateral))),
                 Cell::new(readable_expiry),
                 reward_target_text,
                 Cell::new(format!(
                     "{} dollar",
                     loan.calc_rewards_in_dollar(
-                        U256::from(get_sys_time_in_secs()),
-                        gohm_price.into(),
+                        &self.auction,
+                        timestamp,
+                        gohm_price,
                     )
                     .to_string(),
                 )),
+                Cell::new(profitable_at),
             ]);
         }
 
@@ -224,195 +912,2158 @@ impl<M: Middleware + 'static> LiquidationStrategy<M> {
             println!("{}", table_loans);
         }
     }
-    pub async fn set_loans(&mut self) -> Result<()> {
-        println!("Fetching Cooler Loans... ");
-        let event: ethers::contract::Event<_, _, _> = self.cooler_factory.clear_request_filter();
-        let logs: Vec<ClearRequestFilter> = event.from_block(0).query().await?;
-        let logs_len = logs.len();
-        let pb = ProgressBar::new(logs_len as u64);
-        pb.set_style(
-            ProgressStyle::with_template("[{elapsed_precise}] [{wide_bar:.cyan/blue}] ({eta})")
-                .unwrap()
-                .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
-                    let eta = state.eta();
-                    let hours = eta.as_secs() / 3600;
-                    let minutes = (eta.as_secs() % 3600) / 60;
-                    let seconds = eta.as_secs() % 60;
-                    write!(w, "{:02}:{:02}:{:02}", hours, minutes, seconds).unwrap()
-                })
-                .progress_chars("#>-"),
-        );
-        for log in logs.iter() {
-            let cooler = Cooler::new(log.cooler, self.client.clone());
-            let new_loan = LoanTarget::new(cooler, log.req_id, log.loan_id).await;
+    /// Loans past the reward period target and allowed by the policy, refreshed from chain.
+    pub async fn claim_batch(&mut self, gohm_price: Price) -> Vec<LoanTarget<M>> {
+        let timestamp = U256::from(self.head_timestamp);
+        let in_flight = self.in_flight.lock().unwrap().clone();
+        let clearinghouses: Vec<Address> = self.clearinghouses.iter().map(|clearinghouse| clearinghouse.address()).collect();
+        // Loans from other lenders can't be claimed through a clearinghouse. Stored loans
+        // without a known lender are refreshed first.
+        let claimable_lender = |lender: &Address| lender.is_zero() || clearinghouses.contains(lender);
+        // With the `drop` response loans other bots are claiming are left to them.
+        let contested: HashSet<LoanKey> = match self.config.competition.response {
+            CompetitionResponse::Drop => self.contested.keys().copied().collect(),
+            CompetitionResponse::Outbid => HashSet::new(),
+        };
+        let candidates: Vec<LoanKey> = self
+            .loans
+            .expired(timestamp)
+            .filter(|loan| {
+                !in_flight.contains(&(loan.cooler.address(), loan.loan_id))
+                    && !self.gave_up(&(loan.cooler.address(), loan.loan_id))
+                    && !contested.contains(&(loan.cooler.address(), loan.loan_id))
+                    && claimable_lender(&loan.clearinghouse)
+                    && !loan.calc_rewards_in_dollar(&self.auction, timestamp, gohm_price).is_zero()
+                    && loan.calc_reward_percentage(&self.auction, timestamp) > self.config.reward_period_target_for(loan.collateral).into()
+            })
+            .map(|loan| (loan.cooler.address(), loan.loan_id))
+            .collect();
+        let mut batch = vec![];
+        let concurrency = self.config.loan_refresh_concurrency();
+        for (key, refreshed) in self.loans.refresh_all(candidates, concurrency, &*self.loan_reader).await {
+            if let Err(err) = refreshed {
+                warn!(%err, "skipping loan this block");
+                continue;
+            }
+            // The refresh may show the loan was repaid or claimed in the meantime.
+            let claimable = |loan: &&LoanTarget<M>| !loan.collateral.is_zero() && clearinghouses.contains(&loan.clearinghouse);
+            if let Some(loan) = self.loans.get(&key).filter(claimable) {
+                batch.push(loan.clone());
+            }
+        }
 
-            self.loans.push(new_loan);
-            pb.inc(1);
+        let mut permitted = Vec::with_capacity(batch.len());
+        for loan in batch {
+            if self.permits_cooler(loan.cooler.address()).await {
+                permitted.push(loan);
+            }
         }
+        batch = permitted;
 
-        pb.finish_and_clear();
+        if let Some(policy) = &self.policy {
+            batch.retain(|loan| {
+                let candidate = ClaimCandidate {
+                    cooler: loan.cooler.address(),
+                    loan_id: loan.loan_id,
+                    collateral: loan.collateral,
+                    expiry: loan.expiry,
+                    reward_percentage: loan.calc_reward_percentage(&self.auction, timestamp),
+                    reward_usd: loan.calc_rewards_in_dollar(&self.auction, timestamp, gohm_price),
+                    timestamp: timestamp.as_u64(),
+                };
+                policy.should_claim(&candidate).unwrap_or_else(|err| {
+                    warn!(loan_id = %loan.loan_id, cooler = ?loan.cooler.address(), %err, "policy failed, skipping loan");
+                    false
+                })
+            });
+        }
 
-        println!("done fetching {} loans.", logs_len);
+        batch
+    }
 
-        Ok(())
+    /// Whether the cooler filters let us claim loans of `cooler`. Coolers whose owner can't be
+    /// read are skipped, better to miss a claim than to claim against a blocked owner.
+    pub async fn permits_cooler(&mut self, cooler: Address) -> bool {
+        if self.config.coolers.is_empty() {
+            return true;
+        }
+        let owner = match self.owners.get(&cooler) {
+            Some(owner) => *owner,
+            None => match self.loan_reader.get_owner(cooler).await {
+                Ok(owner) => *self.owners.entry(cooler).or_insert(owner),
+                Err(err) => {
+                    warn!(?cooler, %err, "could not read cooler owner, skipping its loans");
+                    return false;
+                }
+            },
+        };
+        let permitted = self.config.coolers.permits(cooler, owner);
+        if !permitted {
+            debug!(?cooler, ?owner, "cooler filtered out");
+        }
+
+        permitted
     }
-}
 
-#[async_trait]
-impl<M: Middleware + 'static> Strategy<Event, Action> for LiquidationStrategy<M> {
-    async fn sync_state(&mut self) -> Result<()> {
-        self.set_loans().await.unwrap();
-        println!("Running event loop...");
-        Ok(())
+    /// Splits `batch` into one batch per clearinghouse, in the configured order.
+    pub fn by_clearinghouse(&self, batch: Vec<LoanTarget<M>>) -> Vec<(Clearinghouse<M>, Vec<LoanTarget<M>>)> {
+        self.clearinghouses
+            .iter()
+            .filter_map(|clearinghouse| {
+                let loans: Vec<_> =
+                    batch.iter().filter(|loan| loan.clearinghouse == clearinghouse.address()).cloned().collect();
+                (!loans.is_empty()).then(|| (clearinghouse.clone(), loans))
+            })
+            .collect()
     }
 
-    async fn process_event(&mut self, event: Event) -> Vec<Action> {
-        match event {
-            Event::NewBlock(_) => {
-                let gohm_price = get_token_price("governance-ohm").await.unwrap() as u64;
-                let mut claimable_loans = self
-                    .loans
-                    .iter_mut()
-                    .filter(|loan| {
-                        loan.is_claimable(U256::from(get_sys_time_in_secs()))
-                            && loan.calc_rewards_in_dollar(
-                                U256::from(get_sys_time_in_secs()),
-                                gohm_price.into(),
-                            ) > 0.into()
-                    })
-                    .collect::<Vec<&mut LoanTarget<M>>>();
-
-                let claimable_dollar_raw =
-                    claimable_loans.iter_mut().fold(U256::from(0), |acc, loan| {
-                        acc + loan.calc_rewards_in_dollar(
-                            U256::from(get_sys_time_in_secs()),
-                            gohm_price.into(),
-                        )
-                    });
-
-                let mut claimable_loans_with_reward_limit_hit = claimable_loans
-                    .iter_mut()
-                    .filter(|loan| {
-                        loan.calc_reward_percentage()
-                            > std::env::var("REWARD_PERIOD_TARGET")
-                                .unwrap()
-                                .parse::<u64>()
-                                .unwrap().into()
-                    })
-                    .collect::<Vec<&mut &mut LoanTarget<M>>>();
+    pub fn claim_tx(&self, clearinghouse: &Clearinghouse<M>, batch: &[LoanTarget<M>]) -> TypedTransaction {
+        let claim_default_arguments: ClaimDefaultedCall = batch.iter().fold(
+            ClaimDefaultedCall {
+                loans: vec![],
+                coolers: vec![],
+            },
+            |mut acc, loan| {
+                acc.loans.push(loan.loan_id);
+                acc.coolers.push(loan.cooler.address());
+                acc
+            },
+        );
 
-                for loan in claimable_loans_with_reward_limit_hit.iter_mut() {
-                    loan.update().await;
-                }
+        clearinghouse
+            .claim_defaulted(claim_default_arguments.coolers, claim_default_arguments.loans)
+            .tx
+    }
 
-                if claimable_loans_with_reward_limit_hit.len() == 0 {
-                    self.print_table(claimable_dollar_raw, gohm_price.into(), 0.into())
-                        .await;
-                    return vec![];
-                }
+    fn plan_consolidation(&self, clearinghouse: Address, batch: &[LoanTarget<M>], gohm_price: Price, gas: GasModel) -> Plan {
+        let now = self.head_timestamp;
+        let in_flight = self.in_flight.lock().unwrap().clone();
+        let upcoming: Vec<&LoanTarget<M>> = self
+            .loans
+            .open()
+            .filter(|loan| {
+                let key = (loan.cooler.address(), loan.loan_id);
+                loan.clearinghouse == clearinghouse
+                    && !in_flight.contains(&key)
+                    && !batch.iter().any(|ready| (ready.cooler.address(), ready.loan_id) == key)
+            })
+            .collect();
 
-                let claimable_reward_hit_dollar = claimable_loans_with_reward_limit_hit
-                    .iter()
-                    .fold(U256::from(0), |acc, loan| {
-                        acc + loan.calc_rewards_in_dollar(
-                            U256::from(get_sys_time_in_secs()),
-                            gohm_price.into(),
-                        )
-                    });
-
-                let claim_default_arguments: ClaimDefaultedCall =
-                    claimable_loans_with_reward_limit_hit.iter().fold(
-                        ClaimDefaultedCall {
-                            loans: vec![],
-                            coolers: vec![],
-                        },
-                        |mut acc, loan| {
-                            acc.loans.push(loan.loan_id);
-                            acc.coolers.push(loan.cooler.address());
-                            acc
-                        },
-                    );
+        planner::plan(
+            batch,
+            &upcoming,
+            &self.auction,
+            now,
+            gohm_price,
+            gas,
+            |loan| self.config.reward_period_target_for(loan.collateral),
+            &self.config.consolidation,
+        )
+    }
 
-                let tx = self
-                    .clearinghouse
-                    .claim_defaulted(
-                        claim_default_arguments.coolers,
-                        claim_default_arguments.loans,
-                    )
-                    .tx;
-
-                let gas_estimate = self.client.estimate_gas(&tx, None).await.unwrap();
-                let gas_price = self.client.get_gas_price().await.unwrap();
-                let eth_price = get_token_price("ethereum").await.unwrap() as u64;
-                let gas_cost_dollar = gas_estimate * gas_price * eth_price / (1e+18 as u64);
-                let net_claimable_reward_target_hit_dollar = if claimable_reward_hit_dollar > gas_cost_dollar {
-                    claimable_reward_hit_dollar - gas_cost_dollar
-                } else {
-                    0.into()
-                };
+    /// Base fee and the priority fee we'd bid, without the profit share of `profit` bidding.
+    async fn expected_fees(&self, gas_usage: U256) -> Result<(U256, U256)> {
+        self.gas_estimator.fees(&self.config.gas, gas_usage).await
+    }
 
-                let profit_target_hit = net_claimable_reward_target_hit_dollar
-                    > std::env::var("MIN_PROFIT").unwrap().parse::<u64>().unwrap().into();
+    /// Re-reads thresholds and the policy script from the config file and env.
+    fn reload(&mut self) -> Result<()> {
+        let config = Config::load()?;
+        self.policy = config.policy_script.as_deref().map(ScriptPolicy::load).transpose()?;
+        self.config.policy_script = config.policy_script;
+        self.config.min_profit = config.min_profit;
+        self.config.min_profit_gas_multiple = config.min_profit_gas_multiple;
+        self.config.reward_period_target = config.reward_period_target;
+        self.config.reward_period_tiers = config.reward_period_tiers;
+        self.config.coolers = config.coolers;
+        info!(
+            min_profit = %self.config.min_profit,
+            reward_period_target = self.config.reward_period_target,
+            "config reloaded"
+        );
+        self.schedule_open();
 
-                self.print_table(claimable_dollar_raw, gohm_price.into(), net_claimable_reward_target_hit_dollar)
-                    .await;
+        Ok(())
+    }
 
-                if profit_target_hit {
-                    println!("[ACTION] Claiming loans...");
-                    return vec![Action::SubmitTx(SubmitTxToMempool {
-                        tx,
-                        gas_bid_info: None,
-                    })];
-                }
-            }
+    /// Prints the capital efficiency report every `report_interval_hours`, if enabled.
+    fn maybe_print_report(&mut self) {
+        let interval = self.config.report_interval_hours * 60 * 60;
+        let now = get_sys_time_in_secs();
+        if interval == 0 || now < self.last_report + interval {
+            return;
+        }
+        self.last_report = now;
 
-            Event::NewLoan(log) => {
-                let new_loan: ClearRequestFilter = parse_log(log).unwrap();
-                let cooler = Cooler::new(new_loan.cooler, self.client.clone());
-                println!("[EVENT] New loan created");
-                self.loans
-                    .push(LoanTarget::new(cooler, new_loan.req_id, new_loan.loan_id).await);
+        if let Some(store) = &self.store {
+            match store.load_claims() {
+                Ok(claims) if self.config.ui == UiMode::Headless => EfficiencyReport::from_claims(&claims, &self.auction).log(),
+                Ok(claims) => EfficiencyReport::from_claims(&claims, &self.auction).print(),
+                Err(err) => warn!(%err, "could not load claims"),
             }
+        }
+    }
 
-            Event::RepayLoan(log) => {
-                let repay_loan: RepayLoanFilter = parse_log(log).unwrap();
-                let address = repay_loan.cooler;
-                let loan_id = repay_loan.loan_id;
+    /// Writes the loans and claims to `export_dir` every `export_interval_hours`, starting with
+    /// the first block.
+    fn maybe_export(&mut self) {
+        let Some(dir) = &self.config.export_dir else {
+            return;
+        };
+        let interval = self.config.export_interval_hours() * 60 * 60;
+        let now = get_sys_time_in_secs();
+        if interval == 0 || now < self.last_export + interval {
+            return;
+        }
+        self.last_export = now;
 
-                // update existing loan
-                for loan in self.loans.iter_mut() {
-                    if loan.loan_id == loan_id && loan.cooler.address() == address {
-                        println!("[EVENT] Loan got repayed");
-                        loan.update().await;
-                    }
-                }
-            }
+        match export::write(self, dir) {
+            Ok(paths) => debug!(files = paths.len(), dir = %dir.display(), "exported loans"),
+            Err(err) => warn!(%err, "could not export loans"),
+        }
+    }
 
-            Event::ExtendLoan(log) => {
-                let extend_loan: ExtendLoanFilter = parse_log(log).unwrap();
-                let address = extend_loan.cooler;
-                let loan_id = extend_loan.loan_id;
-                for loan in self.loans.iter_mut() {
-                    if loan.loan_id == loan_id && loan.cooler.address() == address {
-                        println!("[EVENT] Loan got extended");
-                        loan.update().await;
-                    }
-                }
+    /// Every `reconcile_interval_hours` re-reads the loans from chain in the background and,
+    /// once that finished, corrects the tracked ones that drifted from it, e.g. after an event
+    /// was lost in a websocket gap.
+    async fn maybe_reconcile(&mut self) {
+        if self.reconciliation.as_ref().is_some_and(JoinHandle::is_finished) {
+            match self.reconciliation.take().unwrap().await {
+                Ok(Ok(reconciliation)) => self.apply_reconciliation(reconciliation).await,
+                Ok(Err(err)) => warn!(%err, "reconciliation failed"),
+                Err(err) => warn!(%err, "reconciliation panicked"),
             }
+        }
 
-            Event::DefaultLoan(log) => {
-                let default_loan: DefaultLoanFilter = parse_log(log).unwrap();
-                let address = default_loan.cooler;
-                let loan_id = default_loan.loan_id;
-                for loan in self.loans.iter_mut() {
-                    if loan.loan_id == loan_id && loan.cooler.address() == address {
-                        println!("[EVENT] Load got defaulted");
-                        loan.update().await;
+        let interval = self.config.reconcile_interval_hours() * 60 * 60;
+        let now = get_sys_time_in_secs();
+        if interval == 0 || self.reconciliation.is_some() || now < self.last_reconcile + interval {
+            return;
+        }
+        self.last_reconcile = now;
+
+        let open = self
+            .loans
+            .open()
+            .map(|loan| ClearRequestFilter {
+                cooler: loan.cooler.address(),
+                req_id: loan.req_id,
+                loan_id: loan.loan_id,
+            })
+            .collect();
+        debug!(from_block = self.reconciled_block, "reconciling loans");
+        self.reconciliation = Some(tokio::spawn(reconcile::derive(
+            self.client.clone(),
+            self.cooler_factory.clone(),
+            self.config.log_block_range,
+            open,
+            self.reconciled_block,
+        )));
+    }
+
+    /// Adds the loans missing from the tracked set and corrects the ones that differ.
+    async fn apply_reconciliation(&mut self, reconciliation: Reconciliation) {
+        let checked = reconciliation.loans.len();
+        let (mut missing, mut drifted) = (0, 0);
+        for (request, loan) in reconciliation.loans {
+            let key = (request.cooler, request.loan_id);
+            let Some(tracked) = self.loans.get(&key) else {
+                // Pruned after it closed, it can't change anymore.
+                if self.loans.knows(&key) {
+                    continue;
+                }
+                if !loan.collateral.is_zero() {
+                    warn!(loan_id = %request.loan_id, cooler = ?request.cooler, "loan was not tracked, adding it");
+                    missing += 1;
+                }
+                let cooler = Cooler::new(request.cooler, self.client.clone());
+                let loan = LoanTarget::from_loan(cooler, request.req_id, request.loan_id, &loan);
+                persist(&self.store, &loan);
+                self.loans.insert(loan);
+                continue;
+            };
+            let before = (tracked.clearinghouse, tracked.collateral, tracked.expiry);
+            if before == (loan.lender, loan.collateral, loan.expiry) {
+                continue;
+            }
+            // An event may have updated the loan after it was read, so it is read again.
+            match self.loans.refresh(key, &*self.loan_reader).await {
+                Ok(Some(loan)) if (loan.clearinghouse, loan.collateral, loan.expiry) != before => {
+                    warn!(
+                        loan_id = %request.loan_id,
+                        cooler = ?request.cooler,
+                        collateral_gohm = %Amount(loan.collateral),
+                        expiry = %loan.expiry,
+                        "tracked loan drifted from chain, corrected it"
+                    );
+                    persist(&self.store, loan);
+                    drifted += 1;
+                }
+                Ok(_) => {}
+                Err(err) => warn!(%err, "could not refresh loan"),
+            }
+        }
+        self.reconciled_block = reconciliation.to_block;
+        self.schedule_open();
+        info!(checked, missing, drifted, "reconciled loans with chain");
+    }
+
+    /// Holds claims back while the head's base fee is above `max_gas_price_gwei`, so a stale
+    /// estimate can't send one into a gas spike. Announces when it starts and stops waiting.
+    fn check_gas_price(&mut self, header: Option<&Block<H256>>) {
+        let Some(cap) = self.config.gas.max_gas_price_gwei else {
+            return;
+        };
+        let Some(base_fee) = header.and_then(|header| header.base_fee_per_gas) else {
+            return;
+        };
+        let base_fee_gwei = format_units(base_fee, "gwei").ok().and_then(|fee| fee.parse::<f64>().ok()).unwrap_or_default();
+        if base_fee_gwei <= cap {
+            if self.gas_price_high.take().is_some() {
+                info!(base_fee_gwei, cap_gwei = cap, "base fee back under the cap, resuming claims");
+                self.notifier.notify(Notification::EngineResumed);
+            }
+            return;
+        }
+
+        let reason = format!("base fee {:.1} gwei above MAX_GAS_PRICE_GWEI {}", base_fee_gwei, cap);
+        if self.gas_price_high.is_none() {
+            warn!(base_fee_gwei, cap_gwei = cap, "base fee above the cap, waiting for lower gas");
+            self.notifier.notify(Notification::EnginePaused { reason: reason.clone() });
+        }
+        self.gas_price_high = Some(reason);
+    }
+
+    /// Checks the wallet balance every `check_every_blocks` blocks and alerts when it drops
+    /// below `min_eth`, or below the most one more claim can cost when that is unset.
+    async fn check_balance(&mut self, block: u64) {
+        if !block.is_multiple_of(self.config.balance.check_every_blocks) {
+            return;
+        }
+        let balance = match self.client.get_balance(self.wallet, None).await {
+            Ok(balance) => balance,
+            Err(err) => {
+                warn!(%err, "could not fetch the wallet balance");
+                return;
+            }
+        };
+        self.publish_tenant(|tenant| tenant.balance = Some(balance)).await;
+
+        let threshold = match self.config.balance.min_eth {
+            Some(min_eth) => min_eth,
+            None => match gas::fees(&*self.client, &self.config.gas, None, CLAIM_GAS.into()).await {
+                Ok(fees) => to_ether(fees.max_fee_per_gas * CLAIM_GAS),
+                Err(err) => {
+                    warn!(%err, "could not price a claim for the balance check");
+                    return;
+                }
+            },
+        };
+        let balance_eth = to_ether(balance);
+        if balance_eth >= threshold {
+            if self.low_balance.take().is_some() {
+                info!(balance_eth, "wallet balance restored");
+                if self.config.balance.pause_when_low {
+                    self.notifier.notify(Notification::EngineResumed);
+                }
+            }
+            return;
+        }
+        if self.low_balance.is_none() {
+            warn!(wallet = ?self.wallet, balance_eth, threshold_eth = threshold, "wallet balance low");
+            self.notifier.notify(Notification::LowBalance {
+                wallet: self.wallet,
+                balance_eth,
+                threshold_eth: threshold,
+            });
+            if self.config.balance.pause_when_low {
+                self.notifier.notify(Notification::EnginePaused {
+                    reason: "wallet balance low".to_string(),
+                });
+            }
+        }
+        self.low_balance = Some(format!("wallet balance {:.4} ETH below {:.4} ETH", balance_eth, threshold));
+    }
+
+    /// Announces when the daily gas budget runs out and when a new day resets it.
+    fn check_gas_budget(&mut self) {
+        let exceeded = self.gas_spent.exceeded(self.config.gas.daily_budget_eth);
+        let was_exceeded = std::mem::replace(&mut self.over_gas_budget, exceeded.is_some());
+        match (exceeded, was_exceeded) {
+            (Some(reason), false) => {
+                warn!(%reason, "pausing claims");
+                self.notifier.notify(Notification::EnginePaused { reason });
+            }
+            (None, true) => {
+                info!("new gas budget day, resuming claims");
+                self.notifier.notify(Notification::EngineResumed);
+            }
+            _ => {}
+        }
+    }
+
+    /// Feeds the new head into the chain monitor and announces pauses and resumes.
+    async fn check_chain_health(&mut self, block: &NewBlock, header: Option<&Block<H256>>) {
+        let Some(timestamp) = header.map(|header| header.timestamp.as_u64()) else {
+            warn!(block = %block.number, "could not fetch block");
+            return;
+        };
+        let finalized = self
+            .client
+            .get_block(BlockNumber::Finalized)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|finalized| finalized.number)
+            .map(|number| number.as_u64());
+
+        match self.chain_monitor.observe(block.number.as_u64(), timestamp, finalized) {
+            HealthChange::Paused(reason) => {
+                warn!(%reason, "chain unhealthy, pausing claims");
+                self.notifier.notify(Notification::EnginePaused { reason });
+            }
+            HealthChange::Resumed => {
+                info!("chain healthy again, resuming claims");
+                self.notifier.notify(Notification::EngineResumed);
+            }
+            HealthChange::Unchanged => {}
+        }
+    }
+
+    /// Detects heads that replace recorded blocks and re-fetches the loans whose logs were in
+    /// the dropped blocks. The logs of the new branch arrive again through the collectors.
+    async fn check_reorg(&mut self, block: &NewBlock, header: Option<&Block<H256>>) {
+        let number = block.number.as_u64();
+        let parent_hash = header.map_or(H256::zero(), |header| header.parent_hash);
+        if header.is_some() && self.reorgs.is_reorg(number, block.hash, parent_hash) {
+            // The first recorded block that is no longer canonical.
+            let mut fork = number;
+            for (height, hash) in self.reorgs.known_below(number) {
+                match self.client.get_block(height).await {
+                    Ok(Some(canonical)) if canonical.hash != Some(hash) => fork = height,
+                    _ => break,
+                }
+            }
+            let reorg = self.reorgs.rewind(fork);
+            warn!(depth = reorg.depth, fork = reorg.fork, loans = reorg.loans.len(), "reorg detected");
+            if reorg.depth > self.config.chain_health.reorg_depth {
+                self.revalidate(&reorg.loans).await;
+            }
+        }
+        self.reorgs.record(number, block.hash);
+        let retries = self.reorgs.take_retries();
+        if !retries.is_empty() {
+            self.revalidate(&retries).await;
+        }
+    }
+
+    /// Re-fetches `loans` from their coolers. Loans whose read reverts or comes back empty no
+    /// longer exist and are dropped. Loans that can't be read are revalidated on the next block.
+    async fn revalidate(&mut self, loans: &HashSet<LoanKey>) {
+        let mut dropped = vec![];
+        for key in loans {
+            match self.loans.refresh(*key, &*self.loan_reader).await {
+                Ok(Some(loan)) if loan.clearinghouse.is_zero() => {
+                    warn!(loan_id = %key.1, cooler = ?key.0, "loan empty after reorg, dropping it");
+                    self.loans.remove(key);
+                    dropped.push(*key);
+                }
+                Ok(Some(loan)) => persist(&self.store, loan),
+                Ok(None) => {}
+                Err(StrategyError::Loan {
+                    reason: ReadError::Reverted(reason),
+                    ..
+                }) => {
+                    warn!(loan_id = %key.1, cooler = ?key.0, %reason, "loan gone after reorg, dropping it");
+                    self.loans.remove(key);
+                    dropped.push(*key);
+                }
+                Err(err) => {
+                    warn!(%err, "could not revalidate loan after reorg, retrying next block");
+                    self.reorgs.retry(*key);
+                }
+            }
+        }
+        if let Some(store) = &self.store {
+            for (cooler, loan_id) in dropped {
+                if let Err(err) = store.delete_loan(cooler, loan_id) {
+                    warn!(%loan_id, ?cooler, %err, "could not delete loan");
+                }
+            }
+        }
+    }
+
+    /// Simulates each loan on its own and builds a claim for the ones that would succeed.
+    /// Drops the loans whose claim reverts against the latest state, e.g. ones claimed or
+    /// repaid since the last update. Single loans are only simulated when the batch reverts.
+    async fn simulate_batch(&self, clearinghouse: &Clearinghouse<M>, batch: Vec<LoanTarget<M>>) -> Vec<LoanTarget<M>> {
+        if batch.is_empty() {
+            return batch;
+        }
+        if self.gas_estimator.simulate(&self.claim_tx(clearinghouse, &batch)).await.is_ok() {
+            return batch;
+        }
+
+        let mut passing = vec![];
+        for loan in batch {
+            match self.gas_estimator.simulate(&self.claim_tx(clearinghouse, std::slice::from_ref(&loan))).await {
+                Ok(()) => passing.push(loan),
+                Err(reason) => warn!(
+                    loan_id = %loan.loan_id,
+                    cooler = ?loan.cooler.address(),
+                    %reason,
+                    "claim reverts, dropping loan from the batch"
+                ),
+            }
+        }
+        passing
+    }
+
+    /// Splits `batch` into batches that stay within `max_batch_gas`, so no claim runs into the
+    /// block gas limit or the provider's estimation cap. Batches are packed by the gas of claiming
+    /// each loan on its own, which counts the fixed part of a claim for every loan and so errs on
+    /// the safe side. Loans that can't be estimated on their own are left out this block.
+    async fn split_by_gas(&self, clearinghouse: &Clearinghouse<M>, batch: Vec<LoanTarget<M>>) -> Vec<Vec<LoanTarget<M>>> {
+        let budget = U256::from(self.config.gas.max_batch_gas);
+        if budget.is_zero() || batch.len() < 2 {
+            return vec![batch];
+        }
+        let estimate = self.gas_estimator.estimate_gas(&self.claim_tx(clearinghouse, &batch)).await;
+        if estimate.is_ok_and(|gas| gas <= budget) {
+            return vec![batch];
+        }
+
+        let mut batches: Vec<Vec<LoanTarget<M>>> = vec![];
+        let mut batch_gas = U256::zero();
+        for loan in batch {
+            let gas = match self.gas_estimator.estimate_gas(&self.claim_tx(clearinghouse, std::slice::from_ref(&loan))).await {
+                Ok(gas) => gas,
+                Err(reason) => {
+                    warn!(
+                        loan_id = %loan.loan_id,
+                        cooler = ?loan.cooler.address(),
+                        %reason,
+                        "gas estimate failed, dropping loan from the batch"
+                    );
+                    continue;
+                }
+            };
+            match batches.last_mut() {
+                Some(current) if batch_gas + gas <= budget => {
+                    current.push(loan);
+                    batch_gas += gas;
+                }
+                _ => {
+                    batches.push(vec![loan]);
+                    batch_gas = gas;
+                }
+            }
+        }
+        info!(
+            clearinghouse = ?clearinghouse.address(),
+            batches = batches.len(),
+            max_batch_gas = self.config.gas.max_batch_gas,
+            "splitting the claim to stay within the gas budget"
+        );
+        batches
+    }
+
+    /// Estimates the gas of claiming `batch`. When the estimate reverts, e.g. because a loan
+    /// was claimed since the simulation, the batch is simulated again without the failing
+    /// loans and re-estimated, up to `ESTIMATE_ATTEMPTS` times. None when nothing is left or
+    /// the estimate keeps failing.
+    async fn estimate_claim(
+        &self,
+        clearinghouse: &Clearinghouse<M>,
+        mut batch: Vec<LoanTarget<M>>,
+    ) -> Option<(Vec<LoanTarget<M>>, TypedTransaction, U256)> {
+        for attempt in 1..=ESTIMATE_ATTEMPTS {
+            let tx = self.claim_tx(clearinghouse, &batch);
+            let reason = match self.gas_estimator.estimate_gas(&tx).await {
+                Ok(gas_estimate) => return Some((batch, tx, gas_estimate)),
+                Err(reason) => reason,
+            };
+            warn!(
+                clearinghouse = ?clearinghouse.address(),
+                loans = batch.len(),
+                attempt,
+                %reason,
+                "gas estimate failed"
+            );
+
+            let loans = batch.len();
+            batch = self.simulate_batch(clearinghouse, batch).await;
+            if batch.is_empty() {
+                return None;
+            }
+            if batch.len() == loans && attempt > 1 {
+                break;
+            }
+        }
+        warn!(clearinghouse = ?clearinghouse.address(), "giving up on the gas estimate, not claiming this block");
+        None
+    }
+
+    /// Prices a simulated batch at the current gas price, leaving out loans worth less than
+    /// the gas they add. None when no loan is worth it.
+    async fn price_claim(
+        &self,
+        clearinghouse: Clearinghouse<M>,
+        batch: Vec<LoanTarget<M>>,
+        gohm_price: Price,
+    ) -> Result<Option<PricedClaim<M>>> {
+        let Some((batch, tx, gas_estimate)) = self.estimate_claim(&clearinghouse, batch).await else {
+            return Ok(None);
+        };
+        let (base_fee, priority_fee) = self
+            .expected_fees(gas_estimate)
+            .await
+            .map_err(|err| anyhow!("no gas price: {}", err))?;
+        let eth_price = self
+            .oracle
+            .eth_price()
+            .await
+            .map_err(|err| StrategyError::Price {
+                asset: "ETH",
+                reason: err.to_string(),
+            })?;
+        let gas_price = base_fee + priority_fee;
+
+        // Leave out loans worth less than the gas they add, they join once their reward grew.
+        let gas_model = GasModel::from_estimate(gas_estimate, batch.len(), gas_price, eth_price);
+        let (batch, left_out) = planner::select(batch, &self.auction, self.head_timestamp, gohm_price, gas_model);
+        if batch.is_empty() {
+            return Ok(None);
+        }
+        let (batch, tx, gas_estimate) = if left_out.is_empty() {
+            (batch, tx, gas_estimate)
+        } else {
+            info!(
+                loans = left_out.len(),
+                gas_usd = %gas_model.marginal_cost_dollar(),
+                "leaving out loans worth less than their marginal gas"
+            );
+            match self.estimate_claim(&clearinghouse, batch).await {
+                Some(estimated) => estimated,
+                None => return Ok(None),
+            }
+        };
+
+        let reward_dollar: Usd = batch
+            .iter()
+            .map(|loan| loan.calc_rewards_in_dollar(&self.auction, U256::from(self.head_timestamp), gohm_price))
+            .sum();
+        let gas_cost_dollar = eth_price.value(gas_estimate * gas_price);
+        let break_even = gas::break_even_gas_price(reward_dollar, gas_estimate, eth_price);
+        let mut net_dollar = reward_dollar.saturating_sub(gas_cost_dollar);
+
+        // With profit bidding part of the net reward goes to the block builder as tip.
+        let gas_bid_info = (self.config.gas.priority_fee == PriorityFee::Profit).then(|| {
+            let profit_wei = eth_price.amount(net_dollar);
+            let bid_percentage = self.config.gas.profit_bid_percent;
+            net_dollar = net_dollar - net_dollar.percent(bid_percentage);
+            GasBidInfo {
+                total_profit: profit_wei,
+                bid_percentage,
+            }
+        });
+
+        Ok(Some(PricedClaim {
+            clearinghouse,
+            batch,
+            tx,
+            gas_estimate,
+            base_fee,
+            gas_price,
+            eth_price,
+            break_even,
+            reward_dollar,
+            gas_cost_dollar,
+            net_dollar,
+            gas_bid_info,
+        }))
+    }
+
+    /// The transaction for `claim` if it hits the profit target and nothing holds it back:
+    /// the break-even safety factor, a pause, a consolidation plan or dry run.
+    fn claim_action(&self, claim: PricedClaim<M>, gohm_price: Price) -> Option<Action> {
+        let min_profit = self.config.min_profit_for(claim.gas_cost_dollar);
+        let profit_target_hit = claim.net_dollar > min_profit;
+        info!(
+            clearinghouse = ?claim.clearinghouse.address(),
+            break_even_gwei = %format_units(claim.break_even, "gwei").unwrap_or_default(),
+            gas_price_gwei = %format_units(claim.gas_price, "gwei").unwrap_or_default(),
+            net_usd = %claim.net_dollar,
+            min_profit_usd = %min_profit,
+            "claim priced"
+        );
+
+        if let Some(factor) = self.config.gas.break_even_safety_factor {
+            let limit = U256::from((claim.break_even.as_u128() as f64 * factor) as u128);
+            if profit_target_hit && claim.base_fee >= limit {
+                warn!(factor, "base fee above the break-even safety limit, not claiming");
+                return None;
+            }
+        }
+
+        if let Some(reason) = self.pause_reason() {
+            if profit_target_hit {
+                warn!(%reason, "paused, not claiming");
+            }
+            return None;
+        }
+
+        if profit_target_hit && self.config.consolidation.window_secs > 0 {
+            let gas = GasModel::from_estimate(claim.gas_estimate, claim.batch.len(), claim.gas_price, claim.eth_price);
+            let clearinghouse = claim.clearinghouse.address();
+            if let Plan::Wait { until, joining, gain } = self.plan_consolidation(clearinghouse, &claim.batch, gohm_price, gas) {
+                info!(
+                    loans = claim.batch.len(),
+                    wait_secs = until.saturating_sub(self.head_timestamp),
+                    joining,
+                    gain_usd = %gain,
+                    "holding loans to claim them together with upcoming ones"
+                );
+                return None;
+            }
+        }
+
+        if profit_target_hit && self.config.dry_run {
+            info!(
+                loans = claim.batch.len(),
+                clearinghouse = ?claim.clearinghouse.address(),
+                net_usd = %claim.net_dollar,
+                "dry run, would claim"
+            );
+            let timestamp = U256::from(self.head_timestamp);
+            for loan in &claim.batch {
+                info!(
+                    loan_id = %loan.loan_id,
+                    cooler = ?loan.cooler.address(),
+                    reward_usd = %loan.calc_rewards_in_dollar(&self.auction, timestamp, gohm_price),
+                    "dry run, would claim loan"
+                );
+            }
+            return None;
+        }
+
+        if !profit_target_hit {
+            return None;
+        }
+        let keys: Vec<LoanKey> = claim.batch.iter().map(|loan| (loan.cooler.address(), loan.loan_id)).collect();
+        let mut gas_bid_info = claim.gas_bid_info;
+        let outbidding = self.config.competition.response == CompetitionResponse::Outbid;
+        let competing = keys.iter().filter_map(|key| self.contested.get(key)).map(|contest| contest.priority_fee).max();
+        if let Some(competing) = competing.filter(|_| outbidding) {
+            let competition = &self.config.competition;
+            let increment: U256 = parse_units(competition.outbid_increment_gwei, "gwei").map(Into::into).unwrap_or_default();
+            let bid = competing * (100 + competition.outbid_percent) / 100 + increment;
+            // The highest tip that still leaves the profit target.
+            let profit_left = claim.reward_dollar.saturating_sub(min_profit);
+            let cap = gas::break_even_gas_price(profit_left, claim.gas_estimate, claim.eth_price).saturating_sub(claim.base_fee);
+            if cap <= competing {
+                warn!(
+                    competing_gwei = %format_units(competing, "gwei").unwrap_or_default(),
+                    cap_gwei = %format_units(cap, "gwei").unwrap_or_default(),
+                    "outbidding the competing claim would not be profitable, not claiming"
+                );
+                return None;
+            }
+            // A profit bid above the competitor's already wins.
+            let profit_bid = gas_bid_info.as_ref().map_or(U256::zero(), |info| {
+                info.total_profit / claim.gas_estimate.max(U256::one()) * info.bid_percentage / 100
+            });
+            let priority_fee = bid.max(profit_bid).min(cap);
+            info!(
+                competing_gwei = %format_units(competing, "gwei").unwrap_or_default(),
+                priority_fee_gwei = %format_units(priority_fee, "gwei").unwrap_or_default(),
+                "outbidding competing claim"
+            );
+            // Spread over the gas used again by the executor, this is the tip it pays at least.
+            gas_bid_info = Some(GasBidInfo {
+                total_profit: priority_fee * claim.gas_estimate,
+                bid_percentage: 100,
+            });
+        }
+        if !self.reserve(&keys) {
+            info!("another tenant is already claiming some of the loans");
+            return None;
+        }
+        info!(
+            loans = claim.batch.len(),
+            clearinghouse = ?claim.clearinghouse.address(),
+            net_usd = %claim.net_dollar,
+            "claiming loans"
+        );
+        Some(Action::SubmitTx(self.tenant.clone(), SubmitTxToMempool {
+            tx: claim.tx,
+            gas_bid_info,
+        }))
+    }
+
+    /// Finds the Cooler V2 accounts that borrowed up to the latest block.
+    async fn sync_mono_cooler(&mut self) -> Result<()> {
+        let Some(mono_cooler) = &mut self.mono_cooler else {
+            return Ok(());
+        };
+        let current_block = self.client.get_block_number().await?.as_u64();
+        mono_cooler.sync(self.config.log_block_range, current_block).await?;
+        info!(
+            mono_cooler = ?mono_cooler.contract.address(),
+            accounts = mono_cooler.accounts.len(),
+            "loaded Cooler V2 accounts"
+        );
+        Ok(())
+    }
+
+    /// Liquidates the Cooler V2 accounts past the liquidation LTV in one `batchLiquidate` once
+    /// their incentive pays for the gas and the profit target. Pauses and dry run hold it back
+    /// like a claim.
+    async fn liquidate_mono_cooler(&mut self, block: u64, gohm_price: Price) -> Vec<Action> {
+        let Some(mono_cooler) = &mut self.mono_cooler else {
+            return vec![];
+        };
+        if let Err(err) = mono_cooler.sync(self.config.log_block_range, block).await {
+            warn!(%err, "could not read Cooler V2 borrows");
+            return vec![];
+        }
+        let liquidatable = match mono_cooler.liquidatable().await {
+            Ok(liquidatable) => liquidatable,
+            Err(err) => {
+                warn!(%err, "could not check Cooler V2 positions");
+                return vec![];
+            }
+        };
+        let positions: Vec<(LoanKey, Liquidatable)> =
+            liquidatable.into_iter().map(|position| (mono_cooler.key(position.account), position)).collect();
+        // Positions another tenant is liquidating or that failed too often are left out, like loans.
+        let in_flight = self.in_flight.lock().unwrap().clone();
+        let positions: Vec<(LoanKey, Liquidatable)> =
+            positions.into_iter().filter(|(key, _)| !in_flight.contains(key) && !self.gave_up(key)).collect();
+        let Some(mono_cooler) = self.mono_cooler.as_ref().filter(|_| !positions.is_empty()) else {
+            return vec![];
+        };
+        let keys: Vec<LoanKey> = positions.iter().map(|(key, _)| *key).collect();
+        let accounts: Vec<Address> = positions.iter().map(|(_, position)| position.account).collect();
+        let incentive = positions.iter().fold(U256::zero(), |sum, (_, position)| sum + position.incentive);
+        let tx = mono_cooler.liquidate_tx(&accounts);
+
+        if let Err(reason) = self.gas_estimator.simulate(&tx).await {
+            warn!(accounts = accounts.len(), %reason, "Cooler V2 liquidation reverts");
+            return vec![];
+        }
+        let gas_estimate = match self.gas_estimator.estimate_gas(&tx).await {
+            Ok(gas_estimate) => gas_estimate,
+            Err(reason) => {
+                warn!(accounts = accounts.len(), %reason, "gas estimate of the Cooler V2 liquidation failed");
+                return vec![];
+            }
+        };
+        let (base_fee, priority_fee) = match self.expected_fees(gas_estimate).await {
+            Ok(fees) => fees,
+            Err(err) => {
+                warn!(%err, "no gas price, not liquidating");
+                return vec![];
+            }
+        };
+        let eth_price = match self.oracle.eth_price().await {
+            Ok(price) => price,
+            Err(err) => {
+                warn!(%err, "no ETH price, not liquidating");
+                return vec![];
+            }
+        };
+        let reward_dollar = gohm_price.value(incentive);
+        let gas_cost_dollar = eth_price.value(gas_estimate * (base_fee + priority_fee));
+        let net_dollar = reward_dollar.saturating_sub(gas_cost_dollar);
+        let min_profit = self.config.min_profit_for(gas_cost_dollar);
+        info!(
+            accounts = accounts.len(),
+            incentive_gohm = %Amount(incentive),
+            reward_usd = %reward_dollar,
+            gas_usd = %gas_cost_dollar,
+            net_usd = %net_dollar,
+            min_profit_usd = %min_profit,
+            "Cooler V2 liquidation priced"
+        );
+        if net_dollar <= min_profit {
+            return vec![];
+        }
+        if let Some(reason) = self.pause_reason() {
+            warn!(%reason, "paused, not liquidating");
+            return vec![];
+        }
+        if self.config.dry_run {
+            info!(accounts = ?accounts, net_usd = %net_dollar, "dry run, would liquidate Cooler V2 accounts");
+            return vec![];
+        }
+
+        if !self.reserve(&keys) {
+            info!("another tenant is already liquidating some of the accounts");
+            return vec![];
+        }
+        info!(accounts = accounts.len(), net_usd = %net_dollar, "liquidating Cooler V2 accounts");
+        vec![Action::SubmitTx(self.tenant.clone(), SubmitTxToMempool {
+            tx,
+            gas_bid_info: None,
+        })]
+    }
+
+    /// Simulates each loan on its own through its lender and builds one claim per
+    /// clearinghouse for the loans that would succeed.
+    async fn simulated_claim(&mut self, loans: Vec<LoanKey>) -> Vec<Action> {
+        let mut passing: Vec<(Address, LoanKey)> = vec![];
+        for (cooler, loan_id) in loans {
+            if !self.permits_cooler(cooler).await {
+                warn!(%loan_id, ?cooler, "cooler is filtered out, skipping loan");
+                continue;
+            }
+            let Some(clearinghouse) = self.lender_of(cooler, loan_id).await else {
+                warn!(%loan_id, ?cooler, "not lent by a configured clearinghouse, skipping loan");
+                continue;
+            };
+            let tx = clearinghouse.claim_defaulted(vec![cooler], vec![loan_id]).tx;
+            if self.gas_estimator.simulate(&tx).await.is_ok() {
+                passing.push((clearinghouse.address(), (cooler, loan_id)));
+            }
+        }
+
+        if passing.is_empty() {
+            info!("none of the loans are claimable");
+            return vec![];
+        }
+        let keys: Vec<LoanKey> = passing.iter().map(|(_, key)| *key).collect();
+        if self.config.dry_run {
+            for (cooler, loan_id) in &keys {
+                info!(%loan_id, ?cooler, "dry run, simulation passed, would claim loan");
+            }
+            return vec![];
+        }
+        if !self.reserve(&keys) {
+            info!("another tenant is already claiming some of the loans");
+            return vec![];
+        }
+
+        info!(loans = keys.len(), "claiming loans that passed simulation");
+        self.clearinghouses
+            .iter()
+            .filter_map(|clearinghouse| {
+                let (coolers, loan_ids): (Vec<Address>, Vec<U256>) = passing
+                    .iter()
+                    .filter(|(lender, _)| *lender == clearinghouse.address())
+                    .map(|(_, key)| *key)
+                    .unzip();
+                (!coolers.is_empty()).then(|| {
+                    Action::SubmitTx(self.tenant.clone(), SubmitTxToMempool {
+                        tx: clearinghouse.claim_defaulted(coolers, loan_ids).tx,
+                        gas_bid_info: None,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// The configured clearinghouse that lent the loan, read from chain if the loan is not tracked.
+    async fn lender_of(&self, cooler: Address, loan_id: U256) -> Option<&Clearinghouse<M>> {
+        let tracked = self
+            .loans
+            .get(&(cooler, loan_id))
+            .map(|loan| loan.clearinghouse)
+            .filter(|lender| !lender.is_zero());
+        let lender = match tracked {
+            Some(lender) => lender,
+            None => self.loan_reader.get_loan(cooler, loan_id).await.ok()?.lender,
+        };
+        self.clearinghouses.iter().find(|clearinghouse| clearinghouse.address() == lender)
+    }
+
+    pub async fn set_loans(&mut self) -> Result<()> {
+        self.loans.set_min_collateral(self.config.min_collateral);
+        let mut from_block = 0;
+        if let Some(store) = &self.store {
+            for stored in store.load_loans()? {
+                let cooler = Cooler::new(stored.cooler, self.client.clone());
+                self.loans.insert(LoanTarget::from_stored(cooler, &stored));
+            }
+            from_block = store.checkpoint()?.unwrap_or(0);
+            info!(loans = self.loans.len(), from_block, "loaded loans from store, backfilling");
+        }
+        if let Some(path) = &self.config.snapshot_path {
+            let snapshot = StateSnapshot::read(path)?;
+            snapshot.check(self.client.get_chainid().await?.as_u64(), self.cooler_factory.address())?;
+            if snapshot.block > from_block {
+                for stored in &snapshot.loans {
+                    let cooler = Cooler::new(stored.cooler, self.client.clone());
+                    let loan = LoanTarget::from_stored(cooler, stored);
+                    persist(&self.store, &loan);
+                    self.loans.insert(loan);
+                }
+                for key in snapshot.closed {
+                    if let Some(store) = &self.store {
+                        store.delete_loan(key.0, key.1)?;
+                    }
+                    self.loans.forget_closed(key);
+                }
+                from_block = snapshot.block;
+                info!(loans = snapshot.loans.len(), from_block, "loaded loans from snapshot, backfilling");
+            } else {
+                info!(block = snapshot.block, from_block, "store is past the snapshot, ignoring it");
+            }
+        }
+        let current_block = self.client.get_block_number().await?;
+
+        // Without a checkpoint the subgraph replaces the scan from block 0, the blocks it
+        // hasn't indexed yet are still read from the logs.
+        let mut logs: Vec<ClearRequestFilter> = vec![];
+        let mut logs_from = from_block;
+        let subgraph_url = self.config.subgraph_url.as_deref().filter(|_| self.config.sync_source == SyncSource::Subgraph);
+        if let Some(url) = subgraph_url.filter(|_| from_block == 0) {
+            match subgraph::clear_requests(url).await {
+                Ok((requests, indexed_block)) => {
+                    info!(loans = requests.len(), indexed_block, "fetched cooler loans from subgraph");
+                    logs = requests;
+                    logs_from = indexed_block + 1;
+                }
+                Err(err) => warn!(%err, "subgraph sync failed, scanning logs instead"),
+            }
+        }
+
+        info!(from_block = logs_from, to_block = current_block.as_u64(), "fetching cooler loans");
+        logs.extend(
+            self.query_logs(logs_from, current_block.as_u64(), || self.cooler_factory.clear_request_filter())
+                .await?,
+        );
+        let mut seen = HashSet::new();
+        logs.retain(|log| !self.loans.knows(&(log.cooler, log.loan_id)) && seen.insert((log.cooler, log.loan_id)));
+        let logs_len = logs.len();
+        let pb = match self.config.ui {
+            UiMode::Headless => ProgressBar::hidden(),
+            _ => ProgressBar::new(logs_len.div_ceil(MULTICALL_CHUNK_SIZE) as u64),
+        };
+        pb.set_style(
+            ProgressStyle::with_template("[{elapsed_precise}] [{wide_bar:.cyan/blue}] ({eta})")
+                .unwrap()
+                .with_key("eta", |state: &ProgressState, w: &mut dyn Write| {
+                    let eta = state.eta();
+                    let hours = eta.as_secs() / 3600;
+                    let minutes = (eta.as_secs() % 3600) / 60;
+                    let seconds = eta.as_secs() % 60;
+                    write!(w, "{:02}:{:02}:{:02}", hours, minutes, seconds).unwrap()
+                })
+                .progress_chars("#>-"),
+        );
+        for (log, loan) in fetch_loans(self.client.clone(), logs, &pb).await? {
+            let cooler = Cooler::new(log.cooler, self.client.clone());
+            let new_loan = LoanTarget::from_loan(cooler, log.req_id, log.loan_id, &loan);
+
+            persist(&self.store, &new_loan);
+            self.loans.insert(new_loan);
+        }
+
+        pb.finish_and_clear();
+
+        info!(loans = logs_len, "fetched cooler loans");
+
+        if from_block > 0 {
+            self.backfill_updates(from_block, current_block.as_u64()).await?;
+        }
+        if let Some(store) = &self.store {
+            store.set_checkpoint(current_block.as_u64())?;
+        }
+        self.reconciled_block = current_block.as_u64();
+
+        Ok(())
+    }
+
+    /// Refreshes stored loans that got repaid, extended or defaulted since `from_block`.
+    async fn backfill_updates(&mut self, from_block: u64, to_block: u64) -> Result<()> {
+        let mut changed: HashSet<(Address, U256)> = HashSet::new();
+        let repaid: Vec<RepayLoanFilter> = self
+            .query_logs(from_block, to_block, || self.cooler_factory.repay_loan_filter())
+            .await?;
+        changed.extend(repaid.iter().map(|log| (log.cooler, log.loan_id)));
+        let extended: Vec<ExtendLoanFilter> = self
+            .query_logs(from_block, to_block, || self.cooler_factory.extend_loan_filter())
+            .await?;
+        changed.extend(extended.iter().map(|log| (log.cooler, log.loan_id)));
+        let defaulted: Vec<DefaultLoanFilter> = self
+            .query_logs(from_block, to_block, || self.cooler_factory.default_loan_filter())
+            .await?;
+        changed.extend(defaulted.iter().map(|log| (log.cooler, log.loan_id)));
+
+        let concurrency = self.config.loan_refresh_concurrency();
+        for (key, refreshed) in self.loans.refresh_all(changed.iter().copied().collect(), concurrency, &*self.loan_reader).await {
+            refreshed?;
+            if let Some(loan) = self.loans.get(&key) {
+                persist(&self.store, loan);
+            }
+        }
+        info!(loans = changed.len(), "refreshed changed loans");
+
+        Ok(())
+    }
+
+    async fn query_logs<D: EthLogDecode>(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        event: impl Fn() -> ContractEvent<Arc<M>, M, D>,
+    ) -> Result<Vec<D>> {
+        query_logs(self.config.log_block_range, from_block, to_block, event).await
+    }
+}
+
+/// Queries the logs of `event` between both blocks, in `log_block_range` sized requests if set.
+/// When the provider rejects a request for its range or result count, the range is halved
+/// for it and every later request.
+pub async fn query_logs<M: Middleware + 'static, D: EthLogDecode>(
+    log_block_range: Option<u64>,
+    from_block: u64,
+    to_block: u64,
+    event: impl Fn() -> ContractEvent<Arc<M>, M, D>,
+) -> Result<Vec<D>> {
+    let mut range = log_block_range.unwrap_or(u64::MAX);
+    let mut logs = vec![];
+    let mut start = from_block;
+    while start <= to_block {
+        let end = to_block.min(start.saturating_add(range - 1));
+        let query = || {
+            let query = event().from_block(start).to_block(end);
+            async move { query.query().await }
+        };
+        let chunk = match query().await {
+            Ok(chunk) => chunk,
+            Err(err) if end > start && is_log_range_error(&err.to_string()) => {
+                range = (end - start).div_ceil(2);
+                info!(from_block = start, to_block = end, range, "log query too large, splitting it");
+                continue;
+            }
+            Err(err) => {
+                debug!(%err, "log query failed, retrying");
+                with_retries(query).await?
+            }
+        };
+        logs.extend(chunk);
+        start = end + 1;
+    }
+
+    Ok(logs)
+}
+
+/// Reads the loans of `requests` through Multicall3, `MULTICALL_PARALLEL_CHUNKS` chunks of
+/// `MULTICALL_CHUNK_SIZE` at a time. `pb` advances by one per chunk.
+pub async fn fetch_loans<M: Middleware + 'static>(
+    client: Arc<M>,
+    requests: Vec<ClearRequestFilter>,
+    pb: &ProgressBar,
+) -> Result<Vec<(ClearRequestFilter, Loan)>> {
+    let chunks: Vec<Vec<ClearRequestFilter>> = requests.chunks(MULTICALL_CHUNK_SIZE).map(<[_]>::to_vec).collect();
+    let multicall = Multicall::new(client.clone(), None).await?;
+    let mut loans = Vec::with_capacity(requests.len());
+    for wave in chunks.chunks(MULTICALL_PARALLEL_CHUNKS) {
+        let mut tasks = JoinSet::new();
+        for chunk in wave {
+            let mut multicall = multicall.clone();
+            let chunk = chunk.clone();
+            let client = client.clone();
+            tasks.spawn(async move {
+                for log in &chunk {
+                    multicall.add_call(Cooler::new(log.cooler, client.clone()).get_loan(log.loan_id), false);
+                }
+                let loans: Vec<Loan> = multicall.call_array().await?;
+                Ok::<_, anyhow::Error>(chunk.into_iter().zip(loans).collect::<Vec<_>>())
+            });
+        }
+
+        while let Some(result) = tasks.join_next().await {
+            loans.extend(result??);
+            pb.inc(1);
+        }
+    }
+
+    Ok(loans)
+}
+
+impl<M: Middleware + 'static> LiquidationStrategy<M> {
+    async fn handle_event(&mut self, event: Event) -> Vec<Action> {
+        // Only the lead tenant keeps the loan book, the others mirror it.
+        let leads = self.loan_feed.leads();
+        match event {
+            Event::NewBlock(block) => {
+                if leads {
+                    if let Some(store) = &self.store {
+                        if let Err(err) = store.set_checkpoint(block.number.as_u64()) {
+                            warn!(%err, "could not save checkpoint");
+                        }
+                    }
+                    let pruned = self.loans.prune();
+                    if pruned > 0 {
+                        debug!(loans = pruned, "pruned closed loans");
+                    }
+                    self.snapshot.write().await.last_block = Some((block.number.as_u64(), get_sys_time_in_secs()));
+                }
+
+                let now = get_sys_time_in_secs();
+                let loans = &self.loans;
+                self.contested
+                    .retain(|key, contest| now < contest.seen_at + CONTEST_TTL_SECS && !loans.is_closed(key));
+                let header = self.client.get_block(block.hash).await.ok().flatten();
+                if let Some(header) = &header {
+                    self.advance_head(header.timestamp.as_u64());
+                }
+                self.check_chain_health(&block, header.as_ref()).await;
+                if leads {
+                    self.check_reorg(&block, header.as_ref()).await;
+                }
+                self.check_gas_budget();
+                self.check_gas_price(header.as_ref());
+                self.check_balance(block.number.as_u64()).await;
+
+                let gohm_price = match self.gohm_price().await {
+                    Ok(price) => price,
+                    Err(err) => {
+                        warn!(%err, "skipping block");
+                        return vec![];
+                    }
+                };
+                self.publish_snapshot(gohm_price).await;
+                if leads {
+                    self.maybe_print_report();
+                    self.maybe_export();
+                    self.maybe_reconcile().await;
+                }
+
+                let mut actions = self.evaluate(gohm_price).await;
+                actions.extend(self.liquidate_mono_cooler(block.number.as_u64(), gohm_price).await);
+                return actions;
+            }
+
+            Event::ExpiryDue(at) => {
+                // Woken by the local clock, so the last head may still be before `at`.
+                self.sync_head().await;
+                if !self.loans_due() {
+                    return vec![];
+                }
+                debug!(at, "loans passed the reward period target");
+                let gohm_price = match self.gohm_price().await {
+                    Ok(price) => price,
+                    Err(err) => {
+                        warn!(%err, "skipping wakeup");
+                        return vec![];
+                    }
+                };
+                return self.evaluate(gohm_price).await;
+            }
+
+            Event::NewLoan(_) | Event::RepayLoan(_) | Event::ExtendLoan(_) | Event::DefaultLoan(_) if !leads => {}
+
+            Event::NewLoan(log) => {
+                let block_number = log.block_number;
+                let new_loan: ClearRequestFilter = match decode_log(log, "NewLoan") {
+                    Ok(new_loan) => new_loan,
+                    Err(err) => {
+                        warn!(%err, "skipping event");
+                        return vec![];
+                    }
+                };
+                if let Some(number) = block_number {
+                    self.reorgs.touch(number.as_u64(), (new_loan.cooler, new_loan.loan_id));
+                }
+                // A log re-emitted after a reorg, the loan is already tracked.
+                if self.loans.knows(&(new_loan.cooler, new_loan.loan_id)) {
+                    return vec![];
+                }
+                let cooler = Cooler::new(new_loan.cooler, self.client.clone());
+                info!(loan_id = %new_loan.loan_id, cooler = ?new_loan.cooler, "new loan");
+                let new_loan = match LoanTarget::new(&*self.loan_reader, cooler, new_loan.req_id, new_loan.loan_id).await {
+                    Ok(new_loan) => new_loan,
+                    Err(err) => {
+                        warn!(%err, "skipping new loan");
+                        return vec![];
+                    }
+                };
+                persist(&self.store, &new_loan);
+                self.schedule_expiry(&new_loan);
+                self.loans.insert(new_loan);
+            }
+
+            Event::RepayLoan(log) => {
+                let block_number = log.block_number;
+                let repay_loan: RepayLoanFilter = match decode_log(log, "RepayLoan") {
+                    Ok(repay_loan) => repay_loan,
+                    Err(err) => {
+                        warn!(%err, "skipping event");
+                        return vec![];
+                    }
+                };
+                let address = repay_loan.cooler;
+                let loan_id = repay_loan.loan_id;
+                if let Some(number) = block_number {
+                    self.reorgs.touch(number.as_u64(), (address, loan_id));
+                }
+
+                // update existing loan
+                match self.loans.refresh((address, loan_id), &*self.loan_reader).await {
+                    Ok(Some(loan)) => {
+                        info!(%loan_id, cooler = ?address, "loan repaid");
+                        persist(&self.store, loan);
+                    }
+                    Ok(None) => {}
+                    Err(err) => warn!(%err, "could not refresh loan"),
+                }
+            }
+
+            Event::ExtendLoan(log) => {
+                let block_number = log.block_number;
+                let extend_loan: ExtendLoanFilter = match decode_log(log, "ExtendLoan") {
+                    Ok(extend_loan) => extend_loan,
+                    Err(err) => {
+                        warn!(%err, "skipping event");
+                        return vec![];
+                    }
+                };
+                let address = extend_loan.cooler;
+                let loan_id = extend_loan.loan_id;
+                if let Some(number) = block_number {
+                    self.reorgs.touch(number.as_u64(), (address, loan_id));
+                }
+                match self.loans.refresh((address, loan_id), &*self.loan_reader).await {
+                    Ok(Some(loan)) => {
+                        info!(%loan_id, cooler = ?address, "loan extended");
+                        persist(&self.store, loan);
+                        let loan = loan.clone();
+                        self.schedule_expiry(&loan);
+                    }
+                    Ok(None) => {}
+                    Err(err) => warn!(%err, "could not refresh loan"),
+                }
+            }
+
+            Event::DefaultLoan(log) => {
+                let block_number = log.block_number;
+                let default_loan: DefaultLoanFilter = match decode_log(log, "DefaultLoan") {
+                    Ok(default_loan) => default_loan,
+                    Err(err) => {
+                        warn!(%err, "skipping event");
+                        return vec![];
+                    }
+                };
+                let address = default_loan.cooler;
+                let loan_id = default_loan.loan_id;
+                if let Some(number) = block_number {
+                    self.reorgs.touch(number.as_u64(), (address, loan_id));
+                }
+                let claimed_at = match block_number {
+                    Some(number) => self.client.get_block(number).await.ok().flatten(),
+                    None => None,
+                }
+                .map(|block| block.timestamp.as_u64())
+                .unwrap_or(self.head_timestamp);
+                let gohm_price = self.snapshot.read().await.gohm_price;
+                let key = (address, loan_id);
+                // The claim may be mined before its outcome arrives, and the loan pruned by then.
+                let ours = self.in_flight.lock().unwrap().contains(&key);
+                if let Some(loan) = self.loans.get(&key).filter(|loan| !loan.collateral.is_zero()) {
+                    if ours {
+                        info!(%loan_id, cooler = ?address, "loan defaulted");
+                    } else {
+                        info!(%loan_id, cooler = ?address, "loan claimed by someone else");
+                    }
+                    record_claim(&self.store, loan, claimed_at, gohm_price, ours);
+                    // The log shows the collateral is gone, so the loan is closed right away
+                    // instead of after a refresh that may lag behind it.
+                    if let Some(loan) = self.loans.close(key) {
+                        persist(&self.store, loan);
+                    }
+                }
+                self.claim_attempts.lock().unwrap().failed.remove(&key);
+            }
+
+            Event::ClaimOutcome(tenant, _) if tenant != self.tenant => {}
+
+            Event::ClaimOutcome(_, ClaimOutcome::Failed(loans)) => {
+                warn!(loans = loans.len(), "claim failed, retrying next block");
+                self.release(&loans);
+                // Loans someone else claimed in the meantime drop out of the batches anyway.
+                let mut attempts = self.claim_attempts.lock().unwrap();
+                for key in loans.into_iter().filter(|key| !self.loans.is_closed(key)) {
+                    let failed = attempts.failed.entry(key).or_default();
+                    *failed += 1;
+                    if *failed == MAX_CLAIM_ATTEMPTS {
+                        warn!(loan_id = %key.1, cooler = ?key.0, failed = *failed, "giving up on claiming loan");
                     }
                 }
             }
+
+            Event::ClaimOutcome(_, ClaimOutcome::Abandoned(loans)) => {
+                warn!(loans = loans.len(), cooldown_secs = ABANDONED_CLAIM_COOLDOWN, "claim abandoned, pausing its loans");
+                self.release(&loans);
+                let now = self.head_timestamp;
+                let mut attempts = self.claim_attempts.lock().unwrap();
+                attempts.abandoned.retain(|_, at| now < *at + ABANDONED_CLAIM_COOLDOWN);
+                attempts.abandoned.extend(loans.into_iter().map(|key| (key, now)));
+            }
+
+            Event::ClaimOutcome(_, ClaimOutcome::Mined(loans)) => {
+                info!(loans = loans.len(), "claim mined");
+                self.release(&loans);
+                self.claim_attempts.lock().unwrap().failed.retain(|key, _| !loans.contains(key));
+                let gohm_price = self.snapshot.read().await.gohm_price;
+                for key in loans {
+                    let Some(loan) = self.loans.get(&key) else { continue };
+                    record_claim(&self.store, loan, self.head_timestamp, gohm_price, true);
+                    match self.loans.refresh(key, &*self.loan_reader).await {
+                        Ok(Some(loan)) => persist(&self.store, loan),
+                        Ok(None) => {}
+                        Err(err) => warn!(%err, "could not refresh loan"),
+                    }
+                }
+            }
+
+            Event::Control(ControlCommand::Pause) => {
+                info!("claiming paused by operator");
+                self.manual_pause = true;
+                self.notifier.notify(Notification::EnginePaused {
+                    reason: "paused by operator".to_string(),
+                });
+            }
+
+            Event::Control(ControlCommand::Resume) => {
+                info!("claiming resumed by operator");
+                self.manual_pause = false;
+                self.notifier.notify(Notification::EngineResumed);
+            }
+
+            Event::Control(ControlCommand::Claim(loans)) => {
+                info!(loans = loans.len(), "claim requested");
+                return self.simulated_claim(loans).await;
+            }
+
+            Event::Control(ControlCommand::Reload) => {
+                if let Err(err) = self.reload() {
+                    warn!(%err, "reload failed");
+                }
+            }
+
+            Event::CompetitorClaim(claim) => self.contest(claim),
         }
 
         vec![]
     }
 }
+
+#[async_trait]
+impl<M: Middleware + 'static> Strategy<Event, Action> for LiquidationStrategy<M> {
+    async fn sync_state(&mut self) -> Result<()> {
+        if self.loan_feed.leads() {
+            self.set_loans().await?;
+            self.loan_feed.publish(&self.loans);
+        } else {
+            // The lead tenant synced before, strategies are synced in order.
+            self.loans.set_min_collateral(self.config.min_collateral);
+            self.loan_feed.mirror(&mut self.loans);
+            info!(loans = self.loans.len(), "mirrored the loans of the lead tenant");
+        }
+        self.schedule_open();
+        self.sync_mono_cooler().await?;
+        if self.config.dry_run {
+            info!("dry run, claims are only logged, nothing is submitted");
+        }
+        info!("running event loop");
+        Ok(())
+    }
+
+    async fn process_event(&mut self, event: Event) -> Vec<Action> {
+        if !self.loan_feed.leads() && self.loan_feed.mirror(&mut self.loans) {
+            self.schedule_open();
+        }
+        let actions = self.handle_event(event).await;
+        if self.loan_feed.leads() {
+            self.loan_feed.publish(&self.loans);
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        competition::CompetitionResponse,
+        config::{GasConfig, RewardTier},
+        hooks::Hooks,
+        oracle::{ETH_ID, GOHM_ID},
+    };
+    use ethers::{
+        abi::{self, AbiDecode, Token},
+        contract::EthEvent,
+        providers::{MockProvider, Provider},
+    };
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    type Client = Provider<MockProvider>;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    /// Loans as the coolers would return them, counting the reads.
+    #[derive(Debug, Default)]
+    struct MockLoans {
+        loans: Mutex<HashMap<LoanKey, Loan>>,
+        owners: Mutex<HashMap<Address, Address>>,
+        reads: AtomicUsize,
+    }
+
+    impl MockLoans {
+        fn set(&self, key: LoanKey, loan: Loan) {
+            self.loans.lock().unwrap().insert(key, loan);
+        }
+
+        fn update(&self, key: LoanKey, update: impl FnOnce(&mut Loan)) {
+            update(self.loans.lock().unwrap().get_mut(&key).unwrap());
+        }
+    }
+
+    #[async_trait]
+    impl LoanReader for MockLoans {
+        async fn get_loan(&self, cooler: Address, loan_id: U256) -> Result<Loan, ReadError> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            self.loans
+                .lock()
+                .unwrap()
+                .get(&(cooler, loan_id))
+                .cloned()
+                .ok_or_else(|| ReadError::Reverted("no such loan".to_string()))
+        }
+
+        async fn get_owner(&self, cooler: Address) -> Result<Address, String> {
+            self.owners.lock().unwrap().get(&cooler).copied().ok_or_else(|| "no such cooler".to_string())
+        }
+    }
+
+    /// Claims revert if they include one of `reverting`, and cost `GAS_PER_LOAN` per loan.
+    #[derive(Debug, Default)]
+    struct MockGas {
+        reverting: Mutex<HashSet<LoanKey>>,
+    }
+
+    const GAS_PER_LOAN: u64 = 100_000;
+
+    impl MockGas {
+        fn loans(tx: &TypedTransaction) -> Vec<LoanKey> {
+            let call = ClaimDefaultedCall::decode(tx.data().unwrap()).unwrap();
+            call.coolers.into_iter().zip(call.loans).collect()
+        }
+
+        fn check(&self, tx: &TypedTransaction) -> Result<usize, String> {
+            let loans = Self::loans(tx);
+            let reverting = self.reverting.lock().unwrap();
+            match loans.iter().any(|key| reverting.contains(key)) {
+                true => Err("LoanNotExpired".to_string()),
+                false => Ok(loans.len()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl GasEstimator for MockGas {
+        async fn simulate(&self, tx: &TypedTransaction) -> Result<(), String> {
+            self.check(tx).map(|_| ())
+        }
+
+        async fn estimate_gas(&self, tx: &TypedTransaction) -> Result<U256, String> {
+            self.check(tx).map(|loans| U256::from(GAS_PER_LOAN * loans as u64))
+        }
+
+        async fn fees(&self, _config: &GasConfig, _gas_usage: U256) -> Result<(U256, U256)> {
+            Ok((parse_units(10, "gwei")?.into(), parse_units(1, "gwei")?.into()))
+        }
+    }
+
+    /// gOHM at $3000 and ETH at $2000, no price at all while `down`.
+    #[derive(Debug, Default)]
+    struct MockPrices {
+        down: AtomicBool,
+    }
+
+    #[async_trait]
+    impl PriceOracle for MockPrices {
+        async fn price_usd(&self, token: &str) -> Result<Price> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(anyhow!("every source failed"));
+            }
+            match token {
+                GOHM_ID => Ok(Price::from_f64(3000.0)),
+                ETH_ID => Ok(Price::from_f64(2000.0)),
+                _ => Err(anyhow!("no {} price", token)),
+            }
+        }
+    }
+
+    fn clearinghouse() -> Address {
+        Address::repeat_byte(0xc1)
+    }
+
+    fn cooler(n: u8) -> Address {
+        Address::repeat_byte(n)
+    }
+
+    /// A loan of 10 gOHM from `lender`, expiring at `expiry`.
+    fn loan(lender: Address, expiry: u64) -> Loan {
+        Loan {
+            collateral: parse_units(10, "ether").unwrap().into(),
+            expiry: expiry.into(),
+            lender,
+            ..Default::default()
+        }
+    }
+
+    fn strategy(loans: Arc<MockLoans>, gas: Arc<MockGas>, clearinghouses: &[Address]) -> LiquidationStrategy<Client> {
+        let (provider, _) = Provider::mocked();
+        let client = Arc::new(provider);
+        let config = Config::default();
+        let notifier = Notifier::spawn(Hooks::new(&config.hooks), None, None, None, None, None);
+        let clearinghouses = clearinghouses.iter().map(|address| Clearinghouse::new(*address, client.clone())).collect();
+        let cooler_factory = CoolerFactory::new(Address::repeat_byte(0xfa), client.clone());
+        let mut strategy = LiquidationStrategy::new(
+            client,
+            clearinghouses,
+            cooler_factory,
+            config,
+            None,
+            None,
+            SharedSnapshot::default(),
+            notifier,
+        );
+        strategy.loan_reader = loans;
+        strategy.gas_estimator = gas;
+        strategy.oracle = Arc::new(MockPrices::default());
+        strategy
+    }
+
+    /// Tracks `loan` in both the mock chain and the strategy.
+    fn track(strategy: &mut LiquidationStrategy<Client>, loans: &MockLoans, key: LoanKey, loan: Loan) {
+        let target = LoanTarget::from_loan(Cooler::new(key.0, strategy.client.clone()), U256::zero(), key.1, &loan);
+        loans.set(key, loan);
+        strategy.loans.insert(target);
+    }
+
+    /// A factory log of event `E` for `cooler`, the other fields being `data`.
+    fn log<E: EthEvent>(cooler: Address, data: &[Token]) -> Log {
+        Log {
+            topics: vec![E::signature(), H256::from(cooler)],
+            data: abi::encode(data).into(),
+            ..Default::default()
+        }
+    }
+
+    fn new_block(number: u64) -> Event {
+        Event::NewBlock(NewBlock {
+            hash: H256::repeat_byte(number as u8),
+            number: number.into(),
+        })
+    }
+
+    fn claimed(actions: &[Action]) -> Vec<LoanKey> {
+        actions.iter().flat_map(|Action::SubmitTx(_, submit)| MockGas::loans(&submit.tx)).collect()
+    }
+
+    #[test]
+    fn rewards_and_break_even_keep_cents() {
+        // Half the auction on 1 gOHM, which pays at most 0.05 gOHM.
+        let reward =
+            Auction::default().rewards_in_dollar(U256::exp10(18), 0.into(), (7 * DAY / 2).into(), Price::from_f64(2843.25));
+        assert_eq!(reward, "71.08125".parse().unwrap());
+
+        // $7.50 buys 0.003 ETH at $2500, spread over 150k gas.
+        let break_even = gas::break_even_gas_price("7.50".parse().unwrap(), 150_000.into(), Price::from_f64(2500.0));
+        assert_eq!(break_even, parse_units(20, "gwei").unwrap().into());
+    }
+
+    #[tokio::test]
+    async fn claims_follow_the_auction_parameters() {
+        let loans = Arc::new(MockLoans::default());
+        let auction = Auction {
+            max_reward: U256::exp10(16),
+            duration: 80 * DAY,
+            ..Default::default()
+        };
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]).with_auction(auction);
+        strategy.config.reward_period_target = 50;
+        let key = (cooler(1), U256::zero());
+        let expired = get_sys_time_in_secs() - 8 * DAY;
+        track(&mut strategy, &loans, key, loan(clearinghouse(), expired));
+
+        // Eight days into an 80 day auction is 10%, below the target.
+        assert_eq!(strategy.loans.get(&key).unwrap().calc_reward_percentage(&strategy.auction, get_sys_time_in_secs().into()), 10.into());
+        assert!(strategy.process_event(new_block(1)).await.is_empty());
+        assert_eq!(strategy.auction.claim_offset(50), Some(51 * 80 * DAY / 100));
+
+        // 10% of the 0.01 gOHM max reward at $3000.
+        let reward = auction.rewards_in_dollar(U256::exp10(19), 0.into(), (8 * DAY).into(), Price::from_f64(3000.0));
+        assert_eq!(reward, Usd::from_dollars(3));
+    }
+
+    #[tokio::test]
+    async fn new_loan_is_tracked_once() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        let key = (cooler(1), U256::from(3));
+        let expiry = get_sys_time_in_secs() + 30 * DAY;
+        loans.set(key, loan(clearinghouse(), expiry));
+
+        let event = log::<ClearRequestFilter>(key.0, &[Token::Uint(U256::from(7)), Token::Uint(key.1)]);
+        assert!(strategy.process_event(Event::NewLoan(event.clone())).await.is_empty());
+        let tracked = strategy.loans.get(&key).unwrap();
+        assert_eq!(tracked.req_id, U256::from(7));
+        assert_eq!(tracked.expiry, U256::from(expiry));
+        assert_eq!(tracked.clearinghouse, clearinghouse());
+
+        // A log re-emitted after a reorg doesn't fetch the loan again.
+        strategy.process_event(Event::NewLoan(event)).await;
+        assert_eq!(loans.reads.load(Ordering::SeqCst), 1);
+        assert_eq!(strategy.loans.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn new_loan_is_skipped_when_the_read_fails() {
+        let mut strategy = strategy(Arc::default(), Arc::default(), &[clearinghouse()]);
+        let event = log::<ClearRequestFilter>(cooler(1), &[Token::Uint(U256::zero()), Token::Uint(U256::zero())]);
+        assert!(strategy.process_event(Event::NewLoan(event)).await.is_empty());
+        assert_eq!(strategy.loans.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn repaid_loan_is_closed_and_pruned() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        let key = (cooler(1), U256::zero());
+        track(&mut strategy, &loans, key, loan(clearinghouse(), get_sys_time_in_secs() + DAY));
+
+        loans.update(key, |loan| loan.collateral = U256::zero());
+        let event = log::<RepayLoanFilter>(key.0, &[Token::Uint(key.1), Token::Uint(U256::from(1000))]);
+        strategy.process_event(Event::RepayLoan(event)).await;
+        assert!(strategy.loans.is_closed(&key));
+
+        assert_eq!(strategy.loans.prune(), 1);
+        assert!(strategy.loans.get(&key).is_none());
+        assert!(strategy.loans.knows(&key));
+    }
+
+    #[tokio::test]
+    async fn extended_loan_moves_its_expiry() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        let key = (cooler(1), U256::zero());
+        let now = get_sys_time_in_secs();
+        track(&mut strategy, &loans, key, loan(clearinghouse(), now - DAY));
+        assert_eq!(strategy.loans.expired(now.into()).count(), 1);
+
+        loans.update(key, |loan| loan.expiry = (now + 30 * DAY).into());
+        let event = log::<ExtendLoanFilter>(key.0, &[Token::Uint(key.1), Token::Uint(U256::one())]);
+        strategy.process_event(Event::ExtendLoan(event)).await;
+        assert_eq!(strategy.loans.get(&key).unwrap().expiry, U256::from(now + 30 * DAY));
+        assert_eq!(strategy.loans.expired(now.into()).count(), 0);
+    }
+
+    #[tokio::test]
+    async fn defaulted_loan_is_closed_and_forgets_failed_claims() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        let key = (cooler(1), U256::zero());
+        let other = (cooler(2), U256::zero());
+        track(&mut strategy, &loans, key, loan(clearinghouse(), get_sys_time_in_secs() - 8 * DAY));
+        strategy.claim_attempts.lock().unwrap().failed = HashMap::from([(key, 1), (other, 1)]);
+
+        let event = log::<DefaultLoanFilter>(key.0, &[Token::Uint(key.1), Token::Uint(U256::from(1000))]);
+        strategy.process_event(Event::DefaultLoan(event)).await;
+        assert!(strategy.loans.is_closed(&key));
+        assert_eq!(strategy.claim_attempts.lock().unwrap().failed, HashMap::from([(other, 1)]));
+        // Closed from the log alone, without reading the loan again.
+        assert_eq!(loans.reads.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn new_block_claims_due_loans_that_pass_simulation() {
+        let loans = Arc::new(MockLoans::default());
+        let gas = Arc::new(MockGas::default());
+        let mut strategy = strategy(loans.clone(), gas.clone(), &[clearinghouse()]);
+        let expired = get_sys_time_in_secs() - 8 * DAY;
+        let due = (cooler(1), U256::zero());
+        let reverting = (cooler(2), U256::zero());
+        let early = (cooler(3), U256::zero());
+        track(&mut strategy, &loans, due, loan(clearinghouse(), expired));
+        track(&mut strategy, &loans, reverting, loan(clearinghouse(), expired));
+        track(&mut strategy, &loans, early, loan(clearinghouse(), get_sys_time_in_secs() + DAY));
+        gas.reverting.lock().unwrap().insert(reverting);
+
+        let actions = strategy.process_event(new_block(1)).await;
+        assert_eq!(claimed(&actions), vec![due]);
+        assert!(strategy.in_flight.lock().unwrap().contains(&due));
+
+        // The claim is in flight, so the next block doesn't send it again.
+        assert!(strategy.process_event(new_block(2)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn profit_threshold_scales_with_gas_cost() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        let due = (cooler(1), U256::zero());
+        track(&mut strategy, &loans, due, loan(clearinghouse(), get_sys_time_in_secs() - 8 * DAY));
+
+        // 100k gas at 11 gwei and $2000 ETH is $2.20, the reward doesn't cover a thousand times that.
+        strategy.config.min_profit_gas_multiple = Some(1000.0);
+        assert!(strategy.process_event(new_block(1)).await.is_empty());
+
+        strategy.config.min_profit_gas_multiple = Some(2.0);
+        assert_eq!(claimed(&strategy.process_event(new_block(2)).await), vec![due]);
+    }
+
+    #[tokio::test]
+    async fn wakes_up_when_a_loan_becomes_profitable() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        let expiry = get_sys_time_in_secs() - DAY;
+        let key = (cooler(1), U256::zero());
+        track(&mut strategy, &loans, key, loan(clearinghouse(), expiry));
+
+        // The reward grows to $300 over 7 days, $150 plus gas is reached after about 3.6 days.
+        strategy.config.min_profit = Usd::from_dollars(150);
+        assert!(strategy.process_event(new_block(1)).await.is_empty());
+        let at = strategy.profit_wakeup.unwrap();
+        assert!(expiry + 7 * DAY / 2 < at && at < expiry + 7 * DAY * 52 / 100, "{}", at - expiry);
+        assert_eq!(strategy.loans.iter().next().and_then(|loan| strategy.profitable_at(loan, Price::from_f64(3000.0))), Some(at));
+
+        // More than the reward ever pays.
+        strategy.config.min_profit = Usd::from_dollars(1000);
+        assert!(strategy.process_event(new_block(2)).await.is_empty());
+        assert_eq!(strategy.profit_wakeup, None);
+    }
+
+    #[tokio::test]
+    async fn batches_are_split_at_the_gas_budget() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        strategy.config.gas.max_batch_gas = 2 * GAS_PER_LOAN + GAS_PER_LOAN / 2;
+        let expired = get_sys_time_in_secs() - 8 * DAY;
+        let keys: Vec<LoanKey> = (1..=5).map(|n| (cooler(n), U256::zero())).collect();
+        for key in &keys {
+            track(&mut strategy, &loans, *key, loan(clearinghouse(), expired));
+        }
+
+        let actions = strategy.process_event(new_block(1)).await;
+        let sizes: Vec<usize> = actions.iter().map(|Action::SubmitTx(_, submit)| MockGas::loans(&submit.tx).len()).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        let mut claimed = claimed(&actions);
+        claimed.sort();
+        assert_eq!(claimed, keys);
+    }
+
+    #[tokio::test]
+    async fn loans_are_indexed_by_when_they_pass_their_target() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        strategy.config.reward_period_target = 50;
+        strategy.config.reward_period_tiers = vec![RewardTier { min_collateral: 50.0, target: 10 }];
+        strategy.head_timestamp = get_sys_time_in_secs();
+        let now = strategy.head_timestamp;
+        let small = (cooler(1), U256::zero());
+        let big = (cooler(2), U256::zero());
+        track(&mut strategy, &loans, small, loan(clearinghouse(), now - DAY));
+        track(&mut strategy, &loans, big, Loan { collateral: parse_units(100, "ether").unwrap().into(), ..loan(clearinghouse(), now - DAY) });
+
+        // A day into the seven day auction only the big loan passed its 10% target.
+        assert!(strategy.loans_due());
+        let due: Vec<LoanKey> = strategy.loans.due(now).map(|loan| (loan.cooler.address(), loan.loan_id)).collect();
+        assert_eq!(due, vec![big]);
+        // The small one passes 50% three and a half days after expiry.
+        assert_eq!(strategy.loans.next_due(now), strategy.auction.claim_offset(50).map(|offset| now - DAY + offset));
+
+        strategy.in_flight.lock().unwrap().insert(big);
+        assert!(!strategy.loans_due());
+    }
+
+    #[tokio::test]
+    async fn claims_are_timed_by_the_head_not_the_local_clock() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        let key = (cooler(1), U256::zero());
+        let expiry = get_sys_time_in_secs() - 8 * DAY;
+        track(&mut strategy, &loans, key, loan(clearinghouse(), expiry));
+
+        // The local clock is ahead, by the chain the loan hasn't expired yet.
+        strategy.head_timestamp = expiry - 60;
+        assert!(strategy.process_event(new_block(1)).await.is_empty());
+
+        strategy.head_timestamp = expiry + 8 * DAY;
+        assert_eq!(claimed(&strategy.process_event(new_block(2)).await), vec![key]);
+    }
+
+    #[tokio::test]
+    async fn new_block_prunes_closed_loans() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        let key = (cooler(1), U256::zero());
+        track(&mut strategy, &loans, key, loan(clearinghouse(), get_sys_time_in_secs() - 8 * DAY));
+        strategy.loans.close(key);
+
+        assert!(strategy.process_event(new_block(1)).await.is_empty());
+        assert!(strategy.loans.get(&key).is_none());
+    }
+
+    #[tokio::test]
+    async fn new_block_sends_nothing_while_paused() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        track(&mut strategy, &loans, (cooler(1), U256::zero()), loan(clearinghouse(), get_sys_time_in_secs() - 8 * DAY));
+        strategy.process_event(Event::Control(ControlCommand::Pause)).await;
+
+        assert!(strategy.process_event(new_block(1)).await.is_empty());
+        assert!(strategy.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn claims_pause_while_there_is_no_price() {
+        let loans = Arc::new(MockLoans::default());
+        let prices = Arc::new(MockPrices::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        strategy.oracle = prices.clone();
+        let due = (cooler(1), U256::zero());
+        track(&mut strategy, &loans, due, loan(clearinghouse(), get_sys_time_in_secs() - 8 * DAY));
+
+        prices.down.store(true, Ordering::SeqCst);
+        assert!(strategy.process_event(new_block(1)).await.is_empty());
+        assert!(strategy.pause_reason().is_some());
+
+        prices.down.store(false, Ordering::SeqCst);
+        assert_eq!(claimed(&strategy.process_event(new_block(2)).await), vec![due]);
+        assert!(strategy.pause_reason().is_none());
+    }
+
+    #[tokio::test]
+    async fn claim_batch_filters_loans() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        strategy.config.reward_period_target = 50;
+        let now = get_sys_time_in_secs();
+        let due = (cooler(1), U256::zero());
+        let below_target = (cooler(2), U256::zero());
+        let not_expired = (cooler(3), U256::zero());
+        let in_flight = (cooler(4), U256::zero());
+        let other_lender = (cooler(5), U256::zero());
+        let contested = (cooler(6), U256::zero());
+        let repaid = (cooler(7), U256::zero());
+        track(&mut strategy, &loans, due, loan(clearinghouse(), now - 6 * DAY));
+        track(&mut strategy, &loans, below_target, loan(clearinghouse(), now - DAY));
+        track(&mut strategy, &loans, not_expired, loan(clearinghouse(), now + DAY));
+        track(&mut strategy, &loans, in_flight, loan(clearinghouse(), now - 6 * DAY));
+        track(&mut strategy, &loans, other_lender, loan(Address::repeat_byte(0xee), now - 6 * DAY));
+        track(&mut strategy, &loans, contested, loan(clearinghouse(), now - 6 * DAY));
+        track(&mut strategy, &loans, repaid, loan(clearinghouse(), now - 6 * DAY));
+        strategy.in_flight.lock().unwrap().insert(in_flight);
+        strategy.contest(CompetitorClaim {
+            tx_hash: H256::zero(),
+            from: Address::repeat_byte(0xbb),
+            clearinghouse: clearinghouse(),
+            priority_fee: U256::one(),
+            loans: vec![contested],
+        });
+        // Repaid since it was tracked, the refresh before claiming finds out.
+        loans.update(repaid, |loan| loan.collateral = U256::zero());
+
+        let batch = strategy.claim_batch(Price::from_f64(3000.0)).await;
+        let keys: Vec<LoanKey> = batch.iter().map(|loan| (loan.cooler.address(), loan.loan_id)).collect();
+        assert_eq!(keys, vec![due]);
+        assert!(strategy.loans.is_closed(&repaid));
+
+        // Outbidding keeps contested loans in the batch.
+        strategy.config.competition.response = CompetitionResponse::Outbid;
+        let batch = strategy.claim_batch(Price::from_f64(3000.0)).await;
+        assert_eq!(batch.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_claims_are_retried_until_the_limit() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        strategy.config.reward_period_target = 50;
+        let now = get_sys_time_in_secs();
+        let failing = (cooler(1), U256::zero());
+        let abandoned = (cooler(2), U256::zero());
+        track(&mut strategy, &loans, failing, loan(clearinghouse(), now - 6 * DAY));
+        track(&mut strategy, &loans, abandoned, loan(clearinghouse(), now - 6 * DAY));
+        let outcome = |outcome| Event::ClaimOutcome("default".to_string(), outcome);
+
+        // Cancelled at the fee ceiling, claiming it again right away would end the same way.
+        strategy.process_event(outcome(ClaimOutcome::Abandoned(vec![abandoned]))).await;
+        for _ in 1..MAX_CLAIM_ATTEMPTS {
+            strategy.process_event(outcome(ClaimOutcome::Failed(vec![failing]))).await;
+        }
+        let batch = strategy.claim_batch(Price::from_f64(3000.0)).await;
+        let keys: Vec<LoanKey> = batch.iter().map(|loan| (loan.cooler.address(), loan.loan_id)).collect();
+        assert_eq!(keys, vec![failing]);
+
+        strategy.process_event(outcome(ClaimOutcome::Failed(vec![failing]))).await;
+        assert!(strategy.claim_batch(Price::from_f64(3000.0)).await.is_empty());
+        assert!(!strategy.loans_due());
+
+        // The abandoned loan is claimed again after the cooldown.
+        strategy.head_timestamp += ABANDONED_CLAIM_COOLDOWN;
+        let batch = strategy.claim_batch(Price::from_f64(3000.0)).await;
+        let keys: Vec<LoanKey> = batch.iter().map(|loan| (loan.cooler.address(), loan.loan_id)).collect();
+        assert_eq!(keys, vec![abandoned]);
+    }
+
+    #[tokio::test]
+    async fn tenants_share_the_lead_loan_book() {
+        let loans = Arc::new(MockLoans::default());
+        let mut lead = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        let mut other = strategy(loans.clone(), Arc::default(), &[clearinghouse()])
+            .with_tenant("other".to_string(), lead.in_flight.clone())
+            .with_loan_feed(lead.loan_feed.follower());
+        let key = (cooler(1), U256::zero());
+        track(&mut lead, &loans, key, loan(clearinghouse(), get_sys_time_in_secs() - 8 * DAY));
+        let resume = || Event::Control(ControlCommand::Resume);
+
+        lead.process_event(resume()).await;
+        other.process_event(resume()).await;
+        assert!(other.loans.get(&key).is_some());
+
+        // Only the lead tenant follows the loan events, the other one mirrors the result.
+        let event = log::<DefaultLoanFilter>(key.0, &[Token::Uint(key.1), Token::Uint(U256::from(1000))]);
+        other.process_event(Event::DefaultLoan(event.clone())).await;
+        assert!(!other.loans.is_closed(&key));
+        lead.process_event(Event::DefaultLoan(event)).await;
+        other.process_event(resume()).await;
+        assert!(other.loans.is_closed(&key));
+        assert_eq!(loans.reads.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn claim_state_outlives_a_reconnect() {
+        let loans = Arc::new(MockLoans::default());
+        let in_flight = InFlightClaims::default();
+        let claim_attempts = SharedClaimAttempts::default();
+        let connect = || {
+            let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()])
+                .with_tenant("default".to_string(), in_flight.clone())
+                .with_claim_attempts(claim_attempts.clone());
+            strategy.config.reward_period_target = 50;
+            strategy
+        };
+        let now = get_sys_time_in_secs();
+        let pending = (cooler(1), U256::zero());
+        let failing = (cooler(2), U256::zero());
+        let due = (cooler(3), U256::zero());
+        let outcome = |outcome| Event::ClaimOutcome("default".to_string(), outcome);
+
+        let mut strategy = connect();
+        for key in [pending, failing, due] {
+            track(&mut strategy, &loans, key, loan(clearinghouse(), now - 6 * DAY));
+        }
+        assert!(strategy.reserve(&[pending]));
+        for _ in 0..MAX_CLAIM_ATTEMPTS {
+            strategy.process_event(outcome(ClaimOutcome::Failed(vec![failing]))).await;
+        }
+
+        // The engine reconnects with a new strategy, the claim sent before is still pending.
+        let mut strategy = connect();
+        for key in [pending, failing, due] {
+            track(&mut strategy, &loans, key, loan(clearinghouse(), now - 6 * DAY));
+        }
+        let batch = strategy.claim_batch(Price::from_f64(3000.0)).await;
+        let keys: Vec<LoanKey> = batch.iter().map(|loan| (loan.cooler.address(), loan.loan_id)).collect();
+        assert_eq!(keys, vec![due]);
+
+        strategy.process_event(outcome(ClaimOutcome::Mined(vec![pending]))).await;
+        assert!(!in_flight.lock().unwrap().contains(&pending));
+    }
+
+    #[tokio::test]
+    async fn reward_tiers_claim_big_loans_earlier() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        strategy.config.reward_period_target = 90;
+        strategy.config.reward_period_tiers = vec![RewardTier { min_collateral: 50.0, target: 10 }];
+        let now = get_sys_time_in_secs();
+        let small = (cooler(1), U256::zero());
+        let big = (cooler(2), U256::zero());
+        track(&mut strategy, &loans, small, loan(clearinghouse(), now - 2 * DAY));
+        track(&mut strategy, &loans, big, Loan { collateral: parse_units(100, "ether").unwrap().into(), ..loan(clearinghouse(), now - 2 * DAY) });
+
+        let batch = strategy.claim_batch(Price::from_f64(3000.0)).await;
+        let keys: Vec<LoanKey> = batch.iter().map(|loan| (loan.cooler.address(), loan.loan_id)).collect();
+        assert_eq!(keys, vec![big]);
+    }
+
+    #[tokio::test]
+    async fn filtered_coolers_are_not_claimed() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        let expired = get_sys_time_in_secs() - 8 * DAY;
+        for n in 1..=4 {
+            track(&mut strategy, &loans, (cooler(n), U256::zero()), loan(clearinghouse(), expired));
+            loans.owners.lock().unwrap().insert(cooler(n), Address::repeat_byte(0xa0 + n));
+        }
+        let claimed_coolers = |batch: Vec<LoanTarget<Client>>| {
+            let mut coolers: Vec<Address> = batch.iter().map(|loan| loan.cooler.address()).collect();
+            coolers.sort();
+            coolers
+        };
+
+        // Blocked by cooler and by owner.
+        strategy.config.coolers.blocklist = vec![cooler(1), Address::repeat_byte(0xa2)];
+        let batch = strategy.claim_batch(Price::from_f64(3000.0)).await;
+        assert_eq!(claimed_coolers(batch), vec![cooler(3), cooler(4)]);
+
+        // The blocklist wins over the allowlist.
+        strategy.config.coolers.allowlist = vec![Address::repeat_byte(0xa2), cooler(3)];
+        let batch = strategy.claim_batch(Price::from_f64(3000.0)).await;
+        assert_eq!(claimed_coolers(batch), vec![cooler(3)]);
+    }
+
+    #[tokio::test]
+    async fn loans_below_min_collateral_are_not_tracked() {
+        let loans = Arc::new(MockLoans::default());
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[clearinghouse()]);
+        strategy.loans.set_min_collateral(50.0);
+        let expired = get_sys_time_in_secs() - 8 * DAY;
+        let dust = (cooler(1), U256::zero());
+        let big = (cooler(2), U256::zero());
+        track(&mut strategy, &loans, dust, loan(clearinghouse(), expired));
+        track(&mut strategy, &loans, big, Loan { collateral: parse_units(100, "ether").unwrap().into(), ..loan(clearinghouse(), expired) });
+        assert_eq!(strategy.loans.len(), 1);
+        assert!(strategy.loans.is_closed(&dust));
+
+        // A partial repayment below the threshold drops the loan on its next refresh.
+        loans.update(big, |loan| loan.collateral = parse_units(20, "ether").unwrap().into());
+        strategy.loans.refresh(big, &*loans).await.unwrap();
+        assert_eq!(strategy.loans.prune(), 1);
+        assert!(strategy.loans.is_closed(&big));
+        assert!(strategy.claim_batch(Price::from_f64(3000.0)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn batches_are_split_by_clearinghouse() {
+        let loans = Arc::new(MockLoans::default());
+        let current = Address::repeat_byte(0xc2);
+        let mut strategy = strategy(loans.clone(), Arc::default(), &[current, clearinghouse()]);
+        let expired = get_sys_time_in_secs() - 8 * DAY;
+        for n in 1..=3 {
+            let lender = if n == 2 { current } else { clearinghouse() };
+            track(&mut strategy, &loans, (cooler(n), U256::zero()), loan(lender, expired));
+        }
+
+        let batch = strategy.claim_batch(Price::from_f64(3000.0)).await;
+        let split: Vec<(Address, usize)> = strategy
+            .by_clearinghouse(batch)
+            .into_iter()
+            .map(|(clearinghouse, batch)| (clearinghouse.address(), batch.len()))
+            .collect();
+        assert_eq!(split, vec![(current, 1), (clearinghouse(), 2)]);
+
+        let actions = strategy.process_event(new_block(1)).await;
+        assert_eq!(actions.len(), 2);
+        assert_eq!(claimed(&actions).len(), 3);
+    }
+}
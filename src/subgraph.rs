@@ -0,0 +1,108 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, bail, Result};
+use ethers::types::{Address, U256};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::bindings::cooler_factory::ClearRequestFilter;
+
+/// Entities per request, the most The Graph serves at once.
+const PAGE_SIZE: usize = 1000;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const CLEAR_REQUESTS_QUERY: &str = r#"
+query ClearRequests($first: Int!, $last: String!) {
+  _meta { block { number } }
+  clearRequests(first: $first, orderBy: id, orderDirection: asc, where: { id_gt: $last }) {
+    id
+    cooler
+    reqID
+    loanID
+  }
+}"#;
+
+/// Where the initial loan set comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncSource {
+    /// `ClearRequest` logs of the CoolerFactory since block 0.
+    #[default]
+    Logs,
+    /// A subgraph indexing the CoolerFactory, with logs only for the blocks it hasn't indexed yet.
+    Subgraph,
+}
+
+impl FromStr for SyncSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "logs" => Ok(SyncSource::Logs),
+            "subgraph" => Ok(SyncSource::Subgraph),
+            _ => Err(format!("unknown sync source {}, expected logs or subgraph", value)),
+        }
+    }
+}
+
+impl fmt::Display for SyncSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncSource::Logs => write!(f, "logs"),
+            SyncSource::Subgraph => write!(f, "subgraph"),
+        }
+    }
+}
+
+/// Every `ClearRequest` the subgraph at `url` indexed, and the block it indexed up to. The
+/// subgraph has to index the CoolerFactory's `ClearRequest` event as a `ClearRequest` entity
+/// with the event parameters as fields, like `graph init --from-contract` scaffolds it.
+pub async fn clear_requests(url: &str) -> Result<(Vec<ClearRequestFilter>, u64)> {
+    let http = Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+    let mut requests = vec![];
+    let mut last = String::new();
+    let mut indexed_block = u64::MAX;
+    loop {
+        let body = json!({
+            "query": CLEAR_REQUESTS_QUERY,
+            "variables": { "first": PAGE_SIZE, "last": last },
+        });
+        let response: Value = http.post(url).json(&body).send().await?.error_for_status()?.json().await?;
+        if let Some(errors) = response.get("errors") {
+            bail!("subgraph query failed: {}", errors);
+        }
+        let data = &response["data"];
+        // Pages can be served by indexers at different heights, the lowest is safe to resume from.
+        let block = data["_meta"]["block"]["number"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("subgraph response has no indexed block"))?;
+        indexed_block = indexed_block.min(block);
+
+        let page = data["clearRequests"]
+            .as_array()
+            .ok_or_else(|| anyhow!("subgraph response has no clearRequests"))?;
+        for entity in page {
+            requests.push(clear_request(entity)?);
+        }
+        match page.last() {
+            Some(entity) if page.len() == PAGE_SIZE => last = entity["id"].as_str().unwrap_or_default().to_string(),
+            _ => break,
+        }
+    }
+
+    Ok((requests, indexed_block))
+}
+
+fn clear_request(entity: &Value) -> Result<ClearRequestFilter> {
+    let field = |name: &str| {
+        entity[name]
+            .as_str()
+            .ok_or_else(|| anyhow!("subgraph entity {} has no {}", entity["id"], name))
+    };
+    Ok(ClearRequestFilter {
+        cooler: field("cooler")?.parse::<Address>()?,
+        req_id: U256::from_dec_str(field("reqID")?)?,
+        loan_id: U256::from_dec_str(field("loanID")?)?,
+    })
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    contract::abigen,
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, TransactionReceipt, H256, U256},
+};
+use tracing::info;
+
+use crate::{
+    bindings::erc20::Erc20,
+    config::SwapConfig,
+    ledger::gohm_received,
+    math::Amount,
+    nonce::NonceTracker,
+    oracle::GOHM,
+    utils::get_sys_time_in_secs,
+};
+
+abigen!(
+    UniswapV2Router,
+    r#"[
+        function getAmountsOut(uint256 amountIn, address[] path) external view returns (uint256[] amounts)
+        function swapExactTokensForTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline) external returns (uint256[] amounts)
+    ]"#
+);
+
+pub const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+/// Swaps not mined within this many seconds revert instead of executing at a stale price.
+const DEADLINE_SECS: u64 = 300;
+
+/// Sells the gOHM a mined claim paid to its sender through the configured router. The
+/// minimum output is the router's quote less `max_slippage_bps`. Returns the swap hash,
+/// or None when the claim paid no gOHM.
+pub async fn sell_reward<M: Middleware + 'static>(
+    client: Arc<M>,
+    config: &SwapConfig,
+    nonces: &NonceTracker,
+    claim: &TransactionReceipt,
+) -> Result<Option<H256>> {
+    let owner = claim.from;
+    let gohm: Address = GOHM.parse()?;
+    let received = gohm_received(claim);
+    if received.is_zero() {
+        return Ok(None);
+    }
+
+    let weth: Address = WETH.parse()?;
+    let path = if config.output_token == weth {
+        vec![gohm, weth]
+    } else {
+        vec![gohm, weth, config.output_token]
+    };
+
+    let token = Erc20::new(gohm, client.clone());
+    if token.allowance(owner, config.router).call().await? < received {
+        info!(router = ?config.router, "approving the router to spend gOHM");
+        send(&*client, nonces, owner, token.approve(config.router, U256::MAX).tx).await?;
+    }
+
+    let router = UniswapV2Router::new(config.router, client.clone());
+    let amounts = router.get_amounts_out(received, path.clone()).call().await?;
+    let quote = *amounts.last().ok_or_else(|| anyhow!("router returned no quote"))?;
+    let min_out = quote * (10_000 - config.max_slippage_bps) / 10_000;
+    let deadline = U256::from(get_sys_time_in_secs() + DEADLINE_SECS);
+    let swap = router.swap_exact_tokens_for_tokens(received, min_out, path, owner, deadline);
+    let receipt = send(&*client, nonces, owner, swap.tx).await?;
+    info!(
+        gohm = %Amount(received),
+        %min_out,
+        output_token = ?config.output_token,
+        tx_hash = ?receipt.transaction_hash,
+        "sold the gOHM reward"
+    );
+
+    Ok(Some(receipt.transaction_hash))
+}
+
+/// Sends `tx` with the next nonce of the wallet, claims through other clearinghouses may still be in flight.
+async fn send<M: Middleware + 'static>(
+    client: &M,
+    nonces: &NonceTracker,
+    owner: Address,
+    mut tx: TypedTransaction,
+) -> Result<TransactionReceipt> {
+    tx.set_nonce(nonces.next(client, owner).await?);
+    let receipt = client
+        .send_transaction(tx, None)
+        .await?
+        .await?
+        .ok_or_else(|| anyhow!("transaction dropped from mempool"))?;
+    if receipt.status != Some(1.into()) {
+        bail!("transaction {:?} reverted", receipt.transaction_hash);
+    }
+
+    Ok(receipt)
+}
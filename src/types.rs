@@ -1,5 +1,9 @@
 use artemis_core::{collectors::block_collector::NewBlock, executors::mempool_executor::SubmitTxToMempool};
-use ethers::types::Log;
+use ethers::types::{Address, Log, H256, U256};
+use serde::{Deserialize, Serialize};
+
+/// A loan is identified by its cooler and its id within that cooler.
+pub type LoanKey = (Address, U256);
 
 #[derive(Debug, Clone)]
 pub enum Event {
@@ -8,9 +12,46 @@ pub enum Event {
     RepayLoan(Log),
     ExtendLoan(Log),
     DefaultLoan(Log),
+    /// Outcome of a claim sent by the named tenant.
+    ClaimOutcome(String, ClaimOutcome),
+    Control(ControlCommand),
+    CompetitorClaim(CompetitorClaim),
+    /// A loan passed the reward period target at the given unix time.
+    ExpiryDue(u64),
+}
+
+/// Result of a submitted claim, reported back by the executor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClaimOutcome {
+    Mined(Vec<LoanKey>),
+    Failed(Vec<LoanKey>),
+    /// Cancelled at the fee ceiling. Claiming the loans again right away at the same fees would
+    /// only end the same way, so they sit out a cooldown.
+    Abandoned(Vec<LoanKey>),
+}
+
+/// Someone else's pending `claimDefaulted` to one of the clearinghouses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompetitorClaim {
+    pub tx_hash: H256,
+    pub from: Address,
+    pub clearinghouse: Address,
+    /// Tip per gas the transaction offers, its whole gas price for legacy transactions.
+    pub priority_fee: U256,
+    pub loans: Vec<LoanKey>,
+}
+
+/// Operator command received on the control socket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ControlCommand {
+    Pause,
+    Resume,
+    Claim(Vec<LoanKey>),
+    Reload,
 }
 
 #[derive(Debug, Clone)]
 pub enum Action {
-    SubmitTx(SubmitTxToMempool)
+    /// Transaction to be sent from the named tenant's wallet.
+    SubmitTx(String, SubmitTxToMempool)
 }
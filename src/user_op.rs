@@ -0,0 +1,265 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use artemis_core::{executors::mempool_executor::SubmitTxToMempool, types::Executor};
+use async_trait::async_trait;
+use ethers::{
+    abi::{encode, AbiEncode, Token},
+    contract::abigen,
+    providers::{Http, Middleware, Provider},
+    signers::Signer,
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, TransactionReceipt, H256, U256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::info;
+
+use crate::{
+    config::AccountAbstractionConfig,
+    executor::claimed_loans,
+    notify::{Notification, Notifier},
+    signer::BotSigner,
+    types::ClaimOutcome,
+};
+
+abigen!(
+    EntryPoint,
+    r#"[
+        function getNonce(address sender, uint192 key) external view returns (uint256 nonce)
+    ]"#
+);
+
+abigen!(
+    SimpleAccount,
+    r#"[
+        function execute(address dest, uint256 value, bytes func)
+    ]"#
+);
+
+/// Placeholder signature accepted by SimpleAccount style wallets during gas estimation.
+const DUMMY_SIGNATURE: &str = "0xfffffffffffffffffffffffffffffff0000000000000000000000000000000007aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa1c";
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const RECEIPT_POLL_ATTEMPTS: usize = 120;
+
+/// ERC-4337 v0.6 user operation.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UserOperation {
+    sender: Address,
+    nonce: U256,
+    init_code: Bytes,
+    call_data: Bytes,
+    call_gas_limit: U256,
+    verification_gas_limit: U256,
+    pre_verification_gas: U256,
+    max_fee_per_gas: U256,
+    max_priority_fee_per_gas: U256,
+    paymaster_and_data: Bytes,
+    signature: Bytes,
+}
+
+impl UserOperation {
+    fn hash(&self, entry_point: Address, chain_id: U256) -> H256 {
+        let packed = encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data).to_vec()),
+        ]);
+        H256(keccak256(encode(&[
+            Token::FixedBytes(keccak256(packed).to_vec()),
+            Token::Address(entry_point),
+            Token::Uint(chain_id),
+        ])))
+    }
+}
+
+/// Answer of `eth_estimateUserOperationGas` and `pm_sponsorUserOperation`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GasEstimate {
+    call_gas_limit: U256,
+    verification_gas_limit: U256,
+    pre_verification_gas: U256,
+    paymaster_and_data: Option<Bytes>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UserOperationReceipt {
+    success: bool,
+    receipt: TransactionReceipt,
+}
+
+/// Experimental executor submitting claims as ERC-4337 user operations through a bundler.
+/// The claim is executed by a smart account owned by the signing key, so gas can be paid
+/// by the account itself or a paymaster instead of the key's ETH balance.
+pub struct UserOpExecutor<M> {
+    client: Arc<M>,
+    bundler: Provider<Http>,
+    paymaster: Option<Provider<Http>>,
+    wallet: BotSigner,
+    sender: Address,
+    entry_point: Address,
+    notifier: Notifier,
+    outcomes: broadcast::Sender<ClaimOutcome>,
+}
+
+impl<M: Middleware + 'static> UserOpExecutor<M> {
+    pub fn new(
+        client: Arc<M>,
+        config: &AccountAbstractionConfig,
+        wallet: BotSigner,
+        notifier: Notifier,
+        outcomes: broadcast::Sender<ClaimOutcome>,
+    ) -> Result<Self> {
+        let bundler_url = config
+            .bundler_url
+            .as_deref()
+            .ok_or_else(|| anyhow!("AA_BUNDLER_URL must be set"))?;
+
+        Ok(Self {
+            client,
+            bundler: Provider::try_from(bundler_url)?,
+            paymaster: config.paymaster_url.as_deref().map(Provider::try_from).transpose()?,
+            wallet,
+            sender: config.sender,
+            entry_point: config.entry_point,
+            notifier,
+            outcomes,
+        })
+    }
+
+    async fn user_operation(&self, to: Address, data: Bytes) -> Result<UserOperation> {
+        let entry_point = EntryPoint::new(self.entry_point, self.client.clone());
+        let nonce = entry_point.get_nonce(self.sender, U256::zero()).call().await?;
+        let (max_fee_per_gas, max_priority_fee_per_gas) = self
+            .client
+            .estimate_eip1559_fees(None)
+            .await
+            .map_err(|err| anyhow!("{}", err))?;
+
+        let call_data = ExecuteCall {
+            dest: to,
+            value: U256::zero(),
+            func: data,
+        };
+        let mut op = UserOperation {
+            sender: self.sender,
+            nonce,
+            init_code: Bytes::default(),
+            call_data: call_data.encode().into(),
+            call_gas_limit: U256::zero(),
+            verification_gas_limit: U256::zero(),
+            pre_verification_gas: U256::zero(),
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            paymaster_and_data: Bytes::default(),
+            signature: DUMMY_SIGNATURE.parse()?,
+        };
+
+        let estimate: GasEstimate = match &self.paymaster {
+            Some(paymaster) => {
+                paymaster
+                    .request("pm_sponsorUserOperation", (&op, self.entry_point))
+                    .await?
+            }
+            None => {
+                self.bundler
+                    .request("eth_estimateUserOperationGas", (&op, self.entry_point))
+                    .await?
+            }
+        };
+        op.call_gas_limit = estimate.call_gas_limit;
+        op.verification_gas_limit = estimate.verification_gas_limit;
+        op.pre_verification_gas = estimate.pre_verification_gas;
+        op.paymaster_and_data = estimate.paymaster_and_data.unwrap_or_default();
+
+        let chain_id = self.client.get_chainid().await.map_err(|err| anyhow!("{}", err))?;
+        let hash = op.hash(self.entry_point, chain_id);
+        op.signature = self.wallet.sign_message(hash).await?.to_vec().into();
+
+        Ok(op)
+    }
+
+    async fn submit(&self, tx: &TypedTransaction) -> Result<H256> {
+        let to = tx
+            .to_addr()
+            .copied()
+            .ok_or_else(|| anyhow!("claim transaction has no recipient"))?;
+        let op = self.user_operation(to, tx.data().cloned().unwrap_or_default()).await?;
+
+        Ok(self.bundler.request("eth_sendUserOperation", (op, self.entry_point)).await?)
+    }
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> Executor<SubmitTxToMempool> for UserOpExecutor<M> {
+    async fn execute(&self, action: SubmitTxToMempool) -> Result<()> {
+        let loans = claimed_loans(&action.tx);
+        let op_hash = match self.submit(&action.tx).await {
+            Ok(op_hash) => op_hash,
+            Err(err) => {
+                self.notifier.notify(Notification::ClaimFailed {
+                    tx_hash: None,
+                    error: err.to_string(),
+                });
+                let _ = self.outcomes.send(ClaimOutcome::Failed(loans));
+                return Err(err);
+            }
+        };
+        info!(?op_hash, "user operation submitted");
+        self.notifier.notify(Notification::ClaimSubmitted { tx_hash: op_hash });
+
+        let bundler = self.bundler.clone();
+        let notifier = self.notifier.clone();
+        let outcomes = self.outcomes.clone();
+        tokio::spawn(async move {
+            let event = match wait_for_receipt(&bundler, op_hash).await {
+                Ok(Some(op_receipt)) if op_receipt.success => Notification::ClaimMined {
+                    tx_hash: op_receipt.receipt.transaction_hash,
+                    block_number: op_receipt.receipt.block_number,
+                    gas_used: op_receipt.receipt.gas_used,
+                },
+                Ok(Some(op_receipt)) => Notification::ClaimFailed {
+                    tx_hash: Some(op_receipt.receipt.transaction_hash),
+                    error: "user operation reverted".to_string(),
+                },
+                Ok(None) => Notification::ClaimFailed {
+                    tx_hash: None,
+                    error: format!("user operation {:?} not included in time", op_hash),
+                },
+                Err(err) => Notification::ClaimFailed {
+                    tx_hash: None,
+                    error: err.to_string(),
+                },
+            };
+            let outcome = match event {
+                Notification::ClaimMined { .. } => ClaimOutcome::Mined(loans),
+                _ => ClaimOutcome::Failed(loans),
+            };
+            notifier.notify(event);
+            let _ = outcomes.send(outcome);
+        });
+
+        Ok(())
+    }
+}
+
+async fn wait_for_receipt(bundler: &Provider<Http>, op_hash: H256) -> Result<Option<UserOperationReceipt>> {
+    for _ in 0..RECEIPT_POLL_ATTEMPTS {
+        let receipt: Option<UserOperationReceipt> = bundler.request("eth_getUserOperationReceipt", [op_hash]).await?;
+        if receipt.is_some() {
+            return Ok(receipt);
+        }
+        tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use ethers::utils::hex;
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+
+use crate::{config::WebhookConfig, notify::Notification, utils::get_sys_time_in_secs};
+
+/// Delay before the first retry, doubled after every failed attempt.
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// POSTs every notification as JSON to the configured endpoints, retrying failed deliveries.
+/// With a secret, `X-Signature-256` carries the HMAC-SHA256 of `{timestamp}.{body}`, where the
+/// timestamp is sent in `X-Webhook-Timestamp`, so receivers can reject forged and replayed requests.
+pub struct WebhookNotifier {
+    client: Client,
+    urls: Vec<String>,
+    secret: Option<String>,
+    events: Vec<String>,
+    max_attempts: u32,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: &WebhookConfig) -> Option<Self> {
+        if config.urls.is_empty() {
+            return None;
+        }
+        Some(Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .build()
+                .unwrap_or_default(),
+            urls: config.urls.clone(),
+            secret: config.secret.clone(),
+            events: config.events.clone(),
+            max_attempts: config.max_attempts,
+        })
+    }
+
+    /// Delivers `notification` to every endpoint in the background, so a slow or failing
+    /// endpoint never holds up the other channels.
+    pub fn send(&self, notification: &Notification) {
+        if !self.events.is_empty() && !self.events.iter().any(|event| event == notification.name()) {
+            return;
+        }
+        let timestamp = get_sys_time_in_secs();
+        let mut payload = match serde_json::to_value(notification) {
+            Ok(payload) => payload,
+            Err(err) => {
+                println!("[WEBHOOK] Could not serialize {}: {}", notification.name(), err);
+                return;
+            }
+        };
+        if let Value::Object(payload) = &mut payload {
+            payload.insert("timestamp".to_string(), timestamp.into());
+        }
+        let body = payload.to_string();
+        let signature = match self.secret.as_deref().map(|secret| sign(secret, timestamp, &body)).transpose() {
+            Ok(signature) => signature,
+            Err(err) => {
+                println!("[WEBHOOK] Could not sign {}: {}", notification.name(), err);
+                return;
+            }
+        };
+
+        for url in &self.urls {
+            let mut request = self
+                .client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .header("X-Webhook-Timestamp", timestamp.to_string())
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header("X-Signature-256", format!("sha256={}", signature));
+            }
+            let (url, name, max_attempts) = (url.clone(), notification.name(), self.max_attempts);
+            tokio::spawn(async move {
+                if let Err(err) = deliver(request, max_attempts).await {
+                    println!("[WEBHOOK] Failed to send {} to {}: {}", name, url, err);
+                }
+            });
+        }
+    }
+}
+
+/// Sends `request` until it succeeds or `max_attempts` ran out. Client errors other than
+/// 429 Too Many Requests won't go away with a retry and fail at once.
+async fn deliver(request: reqwest::RequestBuilder, max_attempts: u32) -> Result<()> {
+    let mut backoff = RETRY_BACKOFF;
+    for attempt in 1..=max_attempts {
+        let Some(request) = request.try_clone() else {
+            bail!("request can't be retried");
+        };
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                if status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS {
+                    bail!("rejected with {}", status);
+                }
+                format!("responded with {}", status)
+            }
+            Err(err) => err.to_string(),
+        };
+        if attempt == max_attempts {
+            bail!("{} after {} attempts", error, attempt);
+        }
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+    Ok(())
+}
+
+/// Hex HMAC-SHA256 of `{timestamp}.{body}` keyed with `secret`.
+fn sign(secret: &str, timestamp: u64, body: &str) -> Result<String> {
+    let key = PKey::hmac(secret.as_bytes())?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(format!("{}.", timestamp).as_bytes())?;
+    signer.update(body.as_bytes())?;
+    Ok(hex::encode(signer.sign_to_vec()?))
+}
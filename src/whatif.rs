@@ -0,0 +1,183 @@
+use anyhow::Result;
+use comfy_table::{presets::UTF8_FULL, Table};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use tokio::io::{stdin, AsyncBufReadExt, BufReader};
+
+use crate::{
+    config::RewardTier,
+    math::{Price, Usd},
+    strategy::{LiquidationStrategy, LoanTarget},
+};
+
+const HELP: &str = "commands:
+  gas <gwei>        set the gas price
+  gaslimit <units>  override the gas estimate (0 = estimate live)
+  gohm <usd>        set the gOHM price
+  eth <usd>         set the ETH price
+  target <percent>  set the reward period target
+  profit <usd>      set the minimum profit
+  time <hours>      evaluate <hours> from now
+  show              evaluate with the current parameters
+  reset             reload live prices, gas and config thresholds
+  help / quit";
+
+#[derive(Debug, Clone)]
+struct Scenario {
+    gas_price_gwei: f64,
+    gas_limit: u64,
+    gohm_price: Price,
+    eth_price: Price,
+    reward_period_target: u64,
+    min_profit: Usd,
+    time_offset_in_s: u64,
+}
+
+/// Interactive what-if analysis over the live loan set.
+pub async fn run<M: Middleware + 'static>(mut strategy: LiquidationStrategy<M>) -> Result<()> {
+    strategy.set_loans().await?;
+    strategy.sync_head().await;
+    let mut scenario = live_scenario(&strategy).await?;
+    evaluate(&strategy, &scenario).await;
+    println!("{}", HELP);
+
+    let mut lines = BufReader::new(stdin()).lines();
+    loop {
+        print!("whatif> ");
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let Some(line) = lines.next_line().await? else {
+            return Ok(());
+        };
+        let mut parts = line.split_whitespace();
+        let command = parts.next().unwrap_or_default();
+        let value = parts.next();
+
+        let parsed = match (command, value) {
+            ("", _) => continue,
+            ("quit" | "exit", _) => return Ok(()),
+            ("help", _) => {
+                println!("{}", HELP);
+                continue;
+            }
+            ("show", _) => Ok(()),
+            ("reset", _) => {
+                scenario = live_scenario(&strategy).await?;
+                Ok(())
+            }
+            ("gas", Some(value)) => value.parse().map(|v| scenario.gas_price_gwei = v).map_err(|e| e.to_string()),
+            ("gaslimit", Some(value)) => value.parse().map(|v| scenario.gas_limit = v).map_err(|e| e.to_string()),
+            ("gohm", Some(value)) => value.parse().map(|v| scenario.gohm_price = v).map_err(|e| e.to_string()),
+            ("eth", Some(value)) => value.parse().map(|v| scenario.eth_price = v).map_err(|e| e.to_string()),
+            ("target", Some(value)) => value.parse().map(|v| scenario.reward_period_target = v).map_err(|e| e.to_string()),
+            ("profit", Some(value)) => value.parse().map(|v| scenario.min_profit = v).map_err(|e| e.to_string()),
+            ("time", Some(value)) => value
+                .parse::<u64>()
+                .map(|hours| scenario.time_offset_in_s = hours * 60 * 60)
+                .map_err(|e| e.to_string()),
+            _ => Err(format!("unknown command `{}`, type `help`", line.trim())),
+        };
+
+        match parsed {
+            Ok(()) => evaluate(&strategy, &scenario).await,
+            Err(err) => println!("{}", err),
+        }
+    }
+}
+
+async fn live_scenario<M: Middleware + 'static>(strategy: &LiquidationStrategy<M>) -> Result<Scenario> {
+    let gas_price = strategy.client.get_gas_price().await?;
+
+    Ok(Scenario {
+        gas_price_gwei: gas_price.as_u128() as f64 / 1e9,
+        gas_limit: 0,
+        gohm_price: strategy.oracle.gohm_price().await?,
+        eth_price: strategy.oracle.eth_price().await?,
+        reward_period_target: strategy.config.reward_period_target,
+        min_profit: strategy.config.min_profit,
+        time_offset_in_s: 0,
+    })
+}
+
+async fn evaluate<M: Middleware + 'static>(strategy: &LiquidationStrategy<M>, scenario: &Scenario) {
+    let timestamp = U256::from(strategy.head_timestamp + scenario.time_offset_in_s);
+    let gohm_price = scenario.gohm_price;
+    let batch: Vec<&LoanTarget<M>> = strategy
+        .loans
+        .expired(timestamp)
+        .filter(|loan| {
+            strategy.clearinghouses.iter().any(|clearinghouse| clearinghouse.address() == loan.clearinghouse)
+                && strategy.auction.reward_percentage(loan.expiry, timestamp) > RewardTier::target_for(&strategy.config.reward_period_tiers, loan.collateral, scenario.reward_period_target).into()
+                && !loan.calc_rewards_in_dollar(&strategy.auction, timestamp, gohm_price).is_zero()
+        })
+        .collect();
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_header(vec!["Cooler", "Loan ID", "Reward period passed", "Reward"]);
+    for loan in batch.iter() {
+        table.add_row(vec![
+            loan.cooler.address().to_string(),
+            loan.loan_id.to_string(),
+            format!("{}%", strategy.auction.reward_percentage(loan.expiry, timestamp)),
+            format!("{} dollar", loan.calc_rewards_in_dollar(&strategy.auction, timestamp, gohm_price)),
+        ]);
+    }
+
+    let reward: Usd = batch.iter().map(|loan| loan.calc_rewards_in_dollar(&strategy.auction, timestamp, gohm_price)).sum();
+
+    if batch.is_empty() {
+        println!("No loans would be claimed.");
+        return;
+    }
+    println!("{}", table);
+
+    let gas_limit = if scenario.gas_limit > 0 {
+        scenario.gas_limit
+    } else {
+        // One claim per clearinghouse.
+        let mut gas_limit = 0;
+        for clearinghouse in &strategy.clearinghouses {
+            let (coolers, loan_ids): (Vec<Address>, Vec<U256>) = batch
+                .iter()
+                .filter(|loan| loan.clearinghouse == clearinghouse.address())
+                .map(|loan| (loan.cooler.address(), loan.loan_id))
+                .unzip();
+            if coolers.is_empty() {
+                continue;
+            }
+            match clearinghouse.claim_defaulted(coolers, loan_ids).estimate_gas().await {
+                Ok(gas) => gas_limit += gas.as_u64(),
+                Err(err) => {
+                    println!("Reward: {} dollar. Gas estimation failed ({}), set one with `gaslimit <units>`.", reward, err);
+                    return;
+                }
+            }
+        }
+        gas_limit
+    };
+
+    let gas_price = U256::from((scenario.gas_price_gwei * 1e9) as u128);
+    let gas_cost = scenario.eth_price.value(U256::from(gas_limit) * gas_price);
+    let min_profit = strategy.config.min_profit_with_floor(scenario.min_profit, gas_cost);
+    let net_profit = if reward >= gas_cost {
+        format!("{}", reward - gas_cost)
+    } else {
+        format!("-{}", gas_cost - reward)
+    };
+    println!(
+        "{} loans, reward {} dollar, gas {} units ≈ {} dollar, net {} dollar -> {}",
+        batch.len(),
+        reward,
+        gas_limit,
+        gas_cost,
+        net_profit,
+        if reward.saturating_sub(gas_cost) > min_profit {
+            "CLAIM"
+        } else {
+            "wait"
+        }
+    );
+}